@@ -1,3 +1,30 @@
+pub mod aztec;
 pub mod barcode;
+pub mod color;
+pub mod contact;
+pub mod datamatrix;
+pub mod ean;
 pub mod encoder;
+pub mod eps;
+pub mod escpos;
+pub mod gs1;
+pub mod linear;
+pub mod logo;
+pub mod matrix;
+pub mod micro_qr;
+pub mod orientation;
+pub mod pdf;
+pub mod pdf417;
+pub mod print;
+pub mod qr_style;
 pub mod qrcode;
+pub mod raster;
+pub mod reed_solomon;
+pub mod rmqr;
+pub mod shape;
+pub mod sizing;
+pub mod svg;
+pub mod terminal;
+pub mod text;
+pub mod wifi;
+pub mod zpl;
@@ -0,0 +1,548 @@
+use crate::encoders::encoder::{Encode, OutputParams};
+use crate::encoders::matrix::{Matrix, QuietZone};
+use crate::encoders::reed_solomon::GaloisField;
+
+pub const DEFAULT_ECC_PERCENT: u8 = 33;
+
+const QUIET_ZONE: usize = 1;
+const MAX_LAYERS: usize = 32;
+const MAX_COMPACT_LAYERS: usize = 4;
+// Codeword size in bits, indexed by the number of layers. Index 0 is the mode message.
+const WORD_SIZE: [usize; 33] = [
+    4, 6, 6, 8, 8, 8, 8, 8, 8, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 12, 12, 12,
+    12, 12, 12, 12, 12, 12, 12,
+];
+const BINARY_SHIFT: u32 = 31;
+const MAX_BINARY_SHIFT: usize = 2047 + 31;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Mode {
+    Upper,
+    Lower,
+    Mixed,
+    Punct,
+    Digit,
+}
+
+impl Mode {
+    fn bits(self) -> usize {
+        match self {
+            Mode::Digit => 4,
+            _ => 5,
+        }
+    }
+
+    fn code(self, byte: u8) -> Option<u32> {
+        let code = match (self, byte) {
+            (Mode::Upper, b' ') | (Mode::Lower, b' ') | (Mode::Digit, b' ') => 1,
+            (Mode::Mixed, b' ') => 1,
+            (Mode::Upper, b'A'..=b'Z') => byte - b'A' + 2,
+            (Mode::Lower, b'a'..=b'z') => byte - b'a' + 2,
+            (Mode::Digit, b'0'..=b'9') => byte - b'0' + 2,
+            (Mode::Digit, b',') => 12,
+            (Mode::Digit, b'.') => 13,
+            (Mode::Mixed, 1..=13) => byte + 1,
+            (Mode::Mixed, 27..=31) => byte - 12,
+            (Mode::Mixed, b'@') => 20,
+            (Mode::Mixed, b'\\') => 21,
+            (Mode::Mixed, b'^') => 22,
+            (Mode::Mixed, b'_') => 23,
+            (Mode::Mixed, b'`') => 24,
+            (Mode::Mixed, b'|') => 25,
+            (Mode::Mixed, b'~') => 26,
+            (Mode::Mixed, 127) => 27,
+            (Mode::Punct, b'\r') => 1,
+            (Mode::Punct, b'!'..=b'/') => byte - b'!' + 6,
+            (Mode::Punct, b':'..=b'?') => byte - b':' + 21,
+            (Mode::Punct, b'[') => 27,
+            (Mode::Punct, b']') => 28,
+            (Mode::Punct, b'{') => 29,
+            (Mode::Punct, b'}') => 30,
+            _ => return None,
+        };
+        Some(code as u32)
+    }
+
+    /// Codes which switch from `self` to `target` for the rest of the message.
+    fn latch(self, target: Mode) -> &'static [(u32, usize)] {
+        match (self, target) {
+            (Mode::Upper, Mode::Lower) => &[(28, 5)],
+            (Mode::Upper, Mode::Mixed) => &[(29, 5)],
+            (Mode::Upper, Mode::Digit) => &[(30, 5)],
+            (Mode::Upper, Mode::Punct) => &[(29, 5), (30, 5)],
+            (Mode::Lower, Mode::Upper) => &[(30, 5), (14, 4)],
+            (Mode::Lower, Mode::Mixed) => &[(29, 5)],
+            (Mode::Lower, Mode::Digit) => &[(30, 5)],
+            (Mode::Lower, Mode::Punct) => &[(29, 5), (30, 5)],
+            (Mode::Mixed, Mode::Upper) => &[(29, 5)],
+            (Mode::Mixed, Mode::Lower) => &[(28, 5)],
+            (Mode::Mixed, Mode::Digit) => &[(29, 5), (30, 5)],
+            (Mode::Mixed, Mode::Punct) => &[(30, 5)],
+            (Mode::Punct, Mode::Upper) => &[(31, 5)],
+            (Mode::Punct, Mode::Lower) => &[(31, 5), (28, 5)],
+            (Mode::Punct, Mode::Mixed) => &[(31, 5), (29, 5)],
+            (Mode::Punct, Mode::Digit) => &[(31, 5), (30, 5)],
+            (Mode::Digit, Mode::Upper) => &[(14, 4)],
+            (Mode::Digit, Mode::Lower) => &[(14, 4), (28, 5)],
+            (Mode::Digit, Mode::Mixed) => &[(14, 4), (29, 5)],
+            (Mode::Digit, Mode::Punct) => &[(14, 4), (29, 5), (30, 5)],
+            _ => &[],
+        }
+    }
+
+    /// Code which switches to `target` for a single character.
+    fn shift(self, target: Mode) -> Option<u32> {
+        match (self, target) {
+            (Mode::Upper, Mode::Punct) | (Mode::Lower, Mode::Punct) => Some(0),
+            (Mode::Mixed, Mode::Punct) | (Mode::Digit, Mode::Punct) => Some(0),
+            (Mode::Lower, Mode::Upper) => Some(28),
+            (Mode::Digit, Mode::Upper) => Some(15),
+            _ => None,
+        }
+    }
+}
+
+const MODES: [Mode; 5] = [
+    Mode::Upper,
+    Mode::Lower,
+    Mode::Mixed,
+    Mode::Punct,
+    Mode::Digit,
+];
+
+fn is_encodable(byte: u8) -> bool {
+    MODES.iter().any(|mode| mode.code(byte).is_some())
+}
+
+fn append_bits(bits: &mut Vec<bool>, value: u32, count: usize) {
+    for i in (0..count).rev() {
+        bits.push(value & (1 << i) != 0);
+    }
+}
+
+/// Converts the payload into a bit stream using character modes and binary shifts.
+fn high_level_encode(data: &[u8]) -> Vec<bool> {
+    let mut bits = Vec::new();
+    let mut mode = Mode::Upper;
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        if let Some(code) = mode.code(byte) {
+            append_bits(&mut bits, code, mode.bits());
+            i += 1;
+            continue;
+        }
+
+        if !is_encodable(byte) {
+            let end = data[i..]
+                .iter()
+                .position(|&b| is_encodable(b))
+                .map_or(data.len(), |p| i + p);
+            let end = std::cmp::min(end, i + MAX_BINARY_SHIFT);
+            if mode == Mode::Punct || mode == Mode::Digit {
+                for &(code, count) in mode.latch(Mode::Upper) {
+                    append_bits(&mut bits, code, count);
+                }
+                mode = Mode::Upper;
+            }
+            append_bits(&mut bits, BINARY_SHIFT, 5);
+            let length = end - i;
+            if length <= 31 {
+                append_bits(&mut bits, length as u32, 5);
+            } else {
+                append_bits(&mut bits, 0, 5);
+                append_bits(&mut bits, (length - 31) as u32, 11);
+            }
+            for &b in &data[i..end] {
+                append_bits(&mut bits, b as u32, 8);
+            }
+            i = end;
+            continue;
+        }
+
+        let next = data.get(i + 1).copied();
+        let shift = MODES.iter().find(|&&target| {
+            target.code(byte).is_some()
+                && mode.shift(target).is_some()
+                && next.is_none_or(|n| target.code(n).is_none())
+        });
+        if let Some(&target) = shift {
+            append_bits(&mut bits, mode.shift(target).unwrap(), mode.bits());
+            append_bits(&mut bits, target.code(byte).unwrap(), target.bits());
+            i += 1;
+            continue;
+        }
+
+        let target = MODES
+            .iter()
+            .filter(|target| target.code(byte).is_some())
+            .min_by_key(|&&target| {
+                mode.latch(target)
+                    .iter()
+                    .map(|&(_, count)| count)
+                    .sum::<usize>()
+            })
+            .copied()
+            .unwrap();
+        for &(code, count) in mode.latch(target) {
+            append_bits(&mut bits, code, count);
+        }
+        mode = target;
+    }
+    bits
+}
+
+fn total_bits_in_layer(layers: usize, compact: bool) -> usize {
+    let base = if compact { 88 } else { 112 };
+    (base + 16 * layers) * layers
+}
+
+/// Splits the bits into codewords, avoiding the all-zero and all-one words.
+fn stuff_bits(bits: &[bool], word_size: usize) -> Vec<bool> {
+    let mut out = Vec::new();
+    let mask = (1u32 << word_size) - 2;
+    let mut i = 0;
+    while i < bits.len() {
+        let mut word = 0u32;
+        for j in 0..word_size {
+            if i + j >= bits.len() || bits[i + j] {
+                word |= 1 << (word_size - 1 - j);
+            }
+        }
+        if word & mask == mask {
+            append_bits(&mut out, word & mask, word_size);
+            i += word_size - 1;
+        } else if word & mask == 0 {
+            append_bits(&mut out, word | 1, word_size);
+            i += word_size - 1;
+        } else {
+            append_bits(&mut out, word, word_size);
+            i += word_size;
+        }
+    }
+    out
+}
+
+fn galois_field(word_size: usize) -> GaloisField {
+    match word_size {
+        4 => GaloisField::new(0x13, 16, 1),
+        6 => GaloisField::new(0x43, 64, 1),
+        8 => GaloisField::new(0x12D, 256, 1),
+        10 => GaloisField::new(0x409, 1024, 1),
+        _ => GaloisField::new(0x1069, 4096, 1),
+    }
+}
+
+fn to_words(bits: &[bool], word_size: usize) -> Vec<u16> {
+    bits.chunks(word_size)
+        .map(|chunk| chunk.iter().fold(0, |word, &bit| (word << 1) | bit as u16))
+        .collect()
+}
+
+/// Appends Reed-Solomon check words so that the message fills `total_bits`.
+fn generate_check_words(bits: &[bool], total_bits: usize, word_size: usize) -> Vec<bool> {
+    let data = to_words(bits, word_size);
+    let total_words = total_bits / word_size;
+    let ec = galois_field(word_size).encode(&data, total_words - data.len());
+
+    let mut out = vec![false; total_bits % word_size];
+    for word in data.iter().chain(ec.iter()) {
+        append_bits(&mut out, *word as u32, word_size);
+    }
+    out
+}
+
+fn generate_mode_message(compact: bool, layers: usize, message_words: usize) -> Vec<bool> {
+    let mut bits = Vec::new();
+    if compact {
+        append_bits(&mut bits, layers as u32 - 1, 2);
+        append_bits(&mut bits, message_words as u32 - 1, 6);
+        generate_check_words(&bits, 28, 4)
+    } else {
+        append_bits(&mut bits, layers as u32 - 1, 5);
+        append_bits(&mut bits, message_words as u32 - 1, 11);
+        generate_check_words(&bits, 40, 4)
+    }
+}
+
+fn draw_mode_message(matrix: &mut Matrix, compact: bool, mode_message: &[bool]) {
+    let center = matrix.width() / 2;
+    if compact {
+        for i in 0..7 {
+            let offset = center - 3 + i;
+            if mode_message[i] {
+                matrix.set(offset, center - 5, true);
+            }
+            if mode_message[i + 7] {
+                matrix.set(center + 5, offset, true);
+            }
+            if mode_message[20 - i] {
+                matrix.set(offset, center + 5, true);
+            }
+            if mode_message[27 - i] {
+                matrix.set(center - 5, offset, true);
+            }
+        }
+    } else {
+        for i in 0..10 {
+            let offset = center - 5 + i + i / 5;
+            if mode_message[i] {
+                matrix.set(offset, center - 7, true);
+            }
+            if mode_message[i + 10] {
+                matrix.set(center + 7, offset, true);
+            }
+            if mode_message[29 - i] {
+                matrix.set(offset, center + 7, true);
+            }
+            if mode_message[39 - i] {
+                matrix.set(center - 7, offset, true);
+            }
+        }
+    }
+}
+
+fn draw_bulls_eye(matrix: &mut Matrix, center: usize, size: usize) {
+    for i in (0..size).step_by(2) {
+        for j in center - i..=center + i {
+            matrix.set(j, center - i, true);
+            matrix.set(j, center + i, true);
+            matrix.set(center - i, j, true);
+            matrix.set(center + i, j, true);
+        }
+    }
+    // Orientation marks.
+    matrix.set(center - size, center - size, true);
+    matrix.set(center - size + 1, center - size, true);
+    matrix.set(center - size, center - size + 1, true);
+    matrix.set(center + size, center - size, true);
+    matrix.set(center + size, center - size + 1, true);
+    matrix.set(center + size, center + size - 1, true);
+}
+
+pub struct Aztec {
+    height: u32,
+    ecc_percent: u8,
+    payload: String,
+}
+
+impl Aztec {
+    pub fn new(payload: String, height: u32, ecc_percent: u8) -> Self {
+        Aztec {
+            payload,
+            height,
+            ecc_percent,
+        }
+    }
+
+    pub fn get_matrix(&self) -> Result<Matrix, String> {
+        if self.ecc_percent < 5 || self.ecc_percent > 95 {
+            return Err(format!(
+                "Error correction must be between 5% and 95%, got {}%",
+                self.ecc_percent
+            ));
+        }
+        if self.payload.is_empty() {
+            return Err("Payload is empty".to_string());
+        }
+        let bits = high_level_encode(self.payload.as_bytes());
+        let ecc_bits = bits.len() * self.ecc_percent as usize / 100 + 11;
+        let total_size_bits = bits.len() + ecc_bits;
+
+        // Find the smallest symbol which fits the data together with the error correction.
+        let mut symbol = None;
+        for i in 0..=MAX_LAYERS {
+            let compact = i < MAX_COMPACT_LAYERS;
+            let layers = if compact { i + 1 } else { i };
+            let total_bits = total_bits_in_layer(layers, compact);
+            if total_size_bits > total_bits {
+                continue;
+            }
+            let word_size = WORD_SIZE[layers];
+            let stuffed_bits = stuff_bits(&bits, word_size);
+            let usable_bits = total_bits - (total_bits % word_size);
+            if compact && stuffed_bits.len() > word_size * 64 {
+                continue;
+            }
+            if stuffed_bits.len() + ecc_bits <= usable_bits {
+                symbol = Some((compact, layers, word_size, stuffed_bits));
+                break;
+            }
+        }
+        let (compact, layers, word_size, stuffed_bits) = match symbol {
+            Some(symbol) => symbol,
+            None => return Err("Data too large for an Aztec code".to_string()),
+        };
+
+        let total_bits = total_bits_in_layer(layers, compact);
+        let message_bits = generate_check_words(&stuffed_bits, total_bits, word_size);
+        let mode_message = generate_mode_message(compact, layers, stuffed_bits.len() / word_size);
+
+        let base_matrix_size = if compact { 11 } else { 14 } + layers * 4;
+        let mut alignment_map: Vec<usize> = (0..base_matrix_size).collect();
+        let matrix_size = if compact {
+            base_matrix_size
+        } else {
+            let matrix_size = base_matrix_size + 1 + 2 * ((base_matrix_size / 2 - 1) / 15);
+            let original_center = base_matrix_size / 2;
+            let center = matrix_size / 2;
+            for i in 0..original_center {
+                let new_offset = i + i / 15;
+                alignment_map[original_center - i - 1] = center - new_offset - 1;
+                alignment_map[original_center + i] = center + new_offset + 1;
+            }
+            matrix_size
+        };
+
+        let mut matrix = Matrix::new(matrix_size, matrix_size);
+        let last = base_matrix_size - 1;
+        let mut row_offset = 0;
+        for i in 0..layers {
+            let row_size = (layers - i) * 4 + if compact { 9 } else { 12 };
+            for j in 0..row_size {
+                let column_offset = j * 2;
+                for k in 0..2 {
+                    let bit = |side: usize| {
+                        message_bits[row_offset + row_size * side + column_offset + k]
+                    };
+                    if bit(0) {
+                        matrix.set(alignment_map[i * 2 + k], alignment_map[i * 2 + j], true);
+                    }
+                    if bit(2) {
+                        matrix.set(
+                            alignment_map[i * 2 + j],
+                            alignment_map[last - i * 2 - k],
+                            true,
+                        );
+                    }
+                    if bit(4) {
+                        matrix.set(
+                            alignment_map[last - i * 2 - k],
+                            alignment_map[last - i * 2 - j],
+                            true,
+                        );
+                    }
+                    if bit(6) {
+                        matrix.set(
+                            alignment_map[last - i * 2 - j],
+                            alignment_map[i * 2 + k],
+                            true,
+                        );
+                    }
+                }
+            }
+            row_offset += row_size * 8;
+        }
+
+        draw_mode_message(&mut matrix, compact, &mode_message);
+
+        let center = matrix_size / 2;
+        if compact {
+            draw_bulls_eye(&mut matrix, center, 5);
+        } else {
+            draw_bulls_eye(&mut matrix, center, 7);
+            // Reference grid.
+            let mut i = 0;
+            let mut j = 0;
+            while i < base_matrix_size / 2 - 1 {
+                let mut k = center & 1;
+                while k < matrix_size {
+                    matrix.set(center - j, k, true);
+                    matrix.set(center + j, k, true);
+                    matrix.set(k, center - j, true);
+                    matrix.set(k, center + j, true);
+                    k += 2;
+                }
+                i += 15;
+                j += 16;
+            }
+        }
+        Ok(matrix)
+    }
+}
+
+impl Encode for Aztec {
+    fn symbol(&self) -> Result<Matrix, String> {
+        self.get_matrix()
+    }
+
+    fn quiet_zone(&self) -> QuietZone {
+        QuietZone::uniform(QUIET_ZONE)
+    }
+
+    fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    fn get_params(&self) -> OutputParams {
+        OutputParams {
+            append_text: false,
+            font_size: 0,
+            height: self.height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoders::encoder::{Encoded, RenderOptions};
+
+    fn read_mode_message(matrix: &Matrix) -> Vec<u16> {
+        let center = matrix.width() / 2;
+        let mut bits = vec![false; 28];
+        for i in 0..7 {
+            let offset = center - 3 + i;
+            bits[i] = matrix.get(offset, center - 5);
+            bits[i + 7] = matrix.get(center + 5, offset);
+            bits[20 - i] = matrix.get(offset, center + 5);
+            bits[27 - i] = matrix.get(center - 5, offset);
+        }
+        to_words(&bits, 4)
+    }
+
+    #[test]
+    fn test_high_level_encode() {
+        // A, L/L, b, D/L, 1.
+        let bits = high_level_encode(b"Ab1");
+        let words = to_words(&bits, 5);
+        assert_eq!(vec![2, 28, 3, 30], words[..4].to_vec());
+    }
+
+    #[test]
+    fn test_encode_compact() {
+        let aztec = Aztec::new("Code 2D!".to_owned(), 10, DEFAULT_ECC_PERCENT);
+        let matrix = aztec.get_matrix().unwrap();
+        assert_eq!(15, matrix.width());
+
+        let mode_message = read_mode_message(&matrix);
+        assert!(galois_field(4)
+            .syndromes(&mode_message, 5)
+            .iter()
+            .all(|&s| s == 0));
+        // A single layer.
+        assert_eq!(0, mode_message[0] >> 2);
+
+        let image = Encoded::new(&aztec, RenderOptions::default())
+            .and_then(|code| code.image())
+            .unwrap();
+        assert_eq!(17, image.to_luma().width());
+    }
+
+    #[test]
+    fn test_encode_full_range() {
+        let aztec = Aztec::new("x".repeat(200), 10, DEFAULT_ECC_PERCENT);
+        let matrix = aztec.get_matrix().unwrap();
+        assert!(matrix.width() > 27);
+        let center = matrix.width() / 2;
+        assert!(matrix.get(center, center));
+        assert!(!matrix.get(center + 1, center));
+        assert!(matrix.get(center + 6, center));
+    }
+
+    #[test]
+    fn test_ecc_percent_out_of_range() {
+        let aztec = Aztec::new("a".to_owned(), 10, 99);
+        assert!(Encoded::new(&aztec, RenderOptions::default()).is_err());
+    }
+}
@@ -1,129 +1,593 @@
-use barcoders::error::Error;
-use barcoders::generators::image::{Color, Image, Rotation};
 use barcoders::sym::code128::Code128;
-use image::{DynamicImage, ImageOutputFormat};
 
-use crate::encoders::encoder::{Encode, OutputParams};
+use crate::encoders::encoder::{Encode, Encoded, OutputParams};
+use crate::encoders::escpos::{self, BarcodeSystem};
+use crate::encoders::gs1;
+use crate::encoders::matrix::{Matrix, QuietZone};
+use crate::encoders::orientation::Rotation;
+use crate::encoders::text::TextOptions;
+use crate::encoders::zpl;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum CharacterSet {
+    A,
+    B,
+    C,
+}
+
+const CHARACTER_SETS: [CharacterSet; 3] = [CharacterSet::B, CharacterSet::A, CharacterSet::C];
+
+impl CharacterSet {
+    /// Character which starts a symbol in this set or switches to it, in `barcoders` notation.
+    fn switch(self) -> char {
+        match self {
+            CharacterSet::A => '\u{00C0}',
+            CharacterSet::B => '\u{0181}',
+            CharacterSet::C => '\u{0106}',
+        }
+    }
+
+    /// Number of characters consumed when `data` is encoded in this set, if it can be encoded.
+    fn consumes(self, data: &[char]) -> Option<usize> {
+        match (self, data[0]) {
+            (_, FNC1) => Some(1),
+            (CharacterSet::A, c) if (c as u32) < 96 => Some(1),
+            (CharacterSet::B, c) if (32..128).contains(&(c as u32)) => Some(1),
+            (CharacterSet::C, c) if c.is_ascii_digit() => match data.get(1) {
+                Some(d) if d.is_ascii_digit() => Some(2),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Light margin on both sides of the symbol, in modules.
+const QUIET_ZONE: usize = 10;
+
+/// FNC1 in `barcoders` notation.
+pub const FNC1: char = '\u{0179}';
+
+/// Most data characters of a GS1-128 symbol, longer element strings go in several symbols.
+const GS1_MAX_LENGTH: usize = 48;
+
+/// Finds the shortest sequence of Code 128 character sets and writes the data in `barcoders` notation.
+/// Only GS1-128 places FNC1 in the data, for plain Code 128 its character is rejected
+/// like any other outside ASCII.
+pub fn code128_data(data: &[char], gs1: bool) -> Result<String, String> {
+    if let Some(c) = data
+        .iter()
+        .find(|&&c| !(gs1 && c == FNC1) && c as u32 > 127)
+    {
+        return Err(format!("Character '{}' cannot be encoded in Code 128", c));
+    }
+    if data.is_empty() {
+        return Err("Payload is empty".to_string());
+    }
+
+    // costs[i][set] is the number of symbols needed for data[i..] when `set` is active.
+    let n = data.len();
+    let mut costs = vec![[usize::MAX; 3]; n + 1];
+    let mut choices = vec![[(CharacterSet::B, 0); 3]; n + 1];
+    costs[n] = [0; 3];
+    for i in (0..n).rev() {
+        for &current in CHARACTER_SETS.iter() {
+            for &next in CHARACTER_SETS.iter() {
+                let consumed = match next.consumes(&data[i..]) {
+                    Some(consumed) => consumed,
+                    None => continue,
+                };
+                let rest = costs[i + consumed][next as usize];
+                if rest == usize::MAX {
+                    continue;
+                }
+                let cost = rest + if next == current { 1 } else { 2 };
+                if cost < costs[i][current as usize] {
+                    costs[i][current as usize] = cost;
+                    choices[i][current as usize] = (next, consumed);
+                }
+            }
+        }
+    }
+
+    let start = CHARACTER_SETS
+        .iter()
+        .filter(|set| set.consumes(data).is_some())
+        .min_by_key(|&&set| costs[0][set as usize])
+        .copied()
+        .unwrap();
+    let mut result = String::new();
+    result.push(start.switch());
+    let mut current = start;
+    let mut i = 0;
+    while i < n {
+        let (next, consumed) = choices[i][current as usize];
+        if next != current {
+            result.push(next.switch());
+            current = next;
+        }
+        for &c in &data[i..i + consumed] {
+            // `barcoders` uses the division sign for DEL.
+            result.push(if c == '\u{007F}' { '\u{00F7}' } else { c });
+        }
+        i += consumed;
+    }
+    Ok(result)
+}
+
+/// Data in `barcoders` notation as ESC/POS CODE128 data: `{` and the letter of a character set
+/// switches to it, `{1` is FNC1 and the digit pairs of set C are sent as single bytes.
+fn escpos_code128_data(data: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut set_c = false;
+    let mut chars = data.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            FNC1 => bytes.extend_from_slice(b"{1"),
+            _ if c == CharacterSet::A.switch() || c == CharacterSet::B.switch() => {
+                bytes.push(b'{');
+                bytes.push(if c == CharacterSet::A.switch() {
+                    b'A'
+                } else {
+                    b'B'
+                });
+                set_c = false;
+            }
+            _ if c == CharacterSet::C.switch() => {
+                bytes.extend_from_slice(b"{C");
+                set_c = true;
+            }
+            _ if set_c => {
+                let tens = c.to_digit(10).unwrap();
+                let units = chars.next().and_then(|d| d.to_digit(10)).unwrap();
+                bytes.push((tens * 10 + units) as u8);
+            }
+            '{' => bytes.extend_from_slice(b"{{"),
+            '\u{00F7}' => bytes.push(0x7f),
+            _ => bytes.push(c as u8),
+        }
+    }
+    bytes
+}
+
+/// Width of the narrowest bar in pixels, scaled with the requested height.
+pub fn xdim(height: u32) -> u32 {
+    match height {
+        0..=100 => 1,
+        101..=200 => 2,
+        201..=300 => 3,
+        301..=400 => 4,
+        _ => 5,
+    }
+}
+
+/// Number of module rows that make up the bar height of a linear symbol.
+pub fn bar_rows(height: u32) -> usize {
+    std::cmp::max(1, height.div_ceil(xdim(height))) as usize
+}
 
 pub struct BarCode {
     height: u32,
-    xdim: u32,
     payload: String,
+    gs1: bool,
+    text: TextOptions,
 }
 
 impl BarCode {
-    pub fn new(payload: String, height: u32) -> Self {
-        let xdim: u32 = match height {
-            0..=100 => 1,
-            101..=200 => 2,
-            201..=300 => 3,
-            301..=400 => 4,
-            _ => 5,
-        };
+    pub fn new(payload: String, height: u32, text: TextOptions) -> Self {
         BarCode {
             payload,
             height,
-            xdim,
+            gs1: false,
+            text,
+        }
+    }
+
+    /// GS1-128: the payload is a GS1 string such as `(01)09501101530003(10)ABC`,
+    /// which is also shown as its human readable interpretation.
+    pub fn new_gs1(payload: String, height: u32, text: TextOptions) -> Self {
+        BarCode {
+            gs1: true,
+            ..BarCode::new(payload, height, text)
         }
     }
 
-    fn get_bytes(&self) -> Result<Vec<u8>, Error> {
-        let data = &self.payload;
-        let data = "À".to_owned() + &data.to_uppercase();
-        let code = Code128::new(data)?;
+    /// The data with its character sets in `barcoders` notation.
+    fn get_data(&self) -> Result<String, String> {
+        let data: Vec<char> = if self.gs1 {
+            let elements = gs1::parse(&self.payload)?;
+            let element_string = gs1::element_string(&elements, FNC1);
+            // Separators count as data characters, the leading FNC1 does not.
+            let length = element_string.chars().count();
+            if length > GS1_MAX_LENGTH {
+                return Err(format!(
+                    "GS1-128 holds at most {} data characters including separators, got {}, \
+                     split the data across several symbols",
+                    GS1_MAX_LENGTH, length
+                ));
+            }
+            std::iter::once(FNC1)
+                .chain(element_string.chars())
+                .collect()
+        } else {
+            self.payload.chars().collect()
+        };
+        code128_data(&data, self.gs1)
+    }
+
+    fn get_bytes(&self) -> Result<Vec<u8>, String> {
+        let code = Code128::new(self.get_data()?).map_err(|e| e.to_string())?;
         Ok(code.encode())
     }
 }
 
 impl Encode for BarCode {
-    fn encode(&self) -> Result<DynamicImage, String> {
-        let encoded = match self.get_bytes() {
-            Ok(encoded) => encoded,
-            Err(e) => return Err(e.to_string()),
-        };
-        let buffer = Image::ImageBuffer {
-            height: self.height,
-            xdim: self.xdim,
-            rotation: Rotation::Zero,
-            foreground: Color::black(),
-            background: Color::white(),
-        };
+    fn symbol(&self) -> Result<Matrix, String> {
+        let bars: Vec<bool> = self.get_bytes()?.iter().map(|&bit| bit == 1).collect();
+        Ok(Matrix::from_bars(&bars, bar_rows(self.height)))
+    }
 
-        // TODO: add quite zone.
-        match buffer.generate_buffer(&encoded[..]) {
-            Ok(image_buffer) => Ok(image::DynamicImage::ImageRgba8(image_buffer)),
-            Err(error) => Err(error.to_string()),
-        }
+    fn quiet_zone(&self) -> QuietZone {
+        QuietZone::horizontal(QUIET_ZONE, QUIET_ZONE)
     }
 
     fn payload(&self) -> &str {
         &self.payload
     }
 
+    fn is_linear(&self) -> bool {
+        true
+    }
+
     fn get_params(&self) -> OutputParams {
         OutputParams {
-            format: ImageOutputFormat::PNG,
-            append_text: false,
+            append_text: self.text.show,
+            font_size: self.text.font_size(xdim(self.height)),
+            height: self.height,
+        }
+    }
+
+    /// `^BC` in automatic mode, or in UCC/EAN mode for GS1-128, which takes the element
+    /// string with parentheses. `>` starts an invocation code in the field data.
+    fn zpl(&self, code: &Encoded) -> Result<Option<String>, String> {
+        let (module_width, module_height) = code.module_size()?;
+        if module_width > zpl::MAX_MODULE || self.payload.contains('>') {
+            return Ok(None);
         }
+        Ok(Some(format!(
+            "^BY{}^BC{},{},{},N,N,{}{}",
+            module_width,
+            zpl::orientation(code.options.orientation.rotation),
+            code.symbol.height() as u32 * module_height,
+            if self.text.show { 'Y' } else { 'N' },
+            if self.gs1 { 'D' } else { 'A' },
+            zpl::field_data(&self.payload)
+        )))
+    }
+
+    /// `GS k` CODE128 in the character sets of the raster symbol, printed upright only.
+    fn escpos(&self, code: &Encoded) -> Result<Option<Vec<u8>>, String> {
+        let (module_width, module_height) = code.module_size()?;
+        let data = escpos_code128_data(&self.get_data()?);
+        if code.options.orientation.rotation != Rotation::Zero
+            || !escpos::BAR_MODULES.contains(&module_width)
+            || data.len() > 255
+        {
+            return Ok(None);
+        }
+        Ok(Some(escpos::barcode(
+            BarcodeSystem::Code128,
+            &data,
+            module_width,
+            code.symbol.height() as u32 * module_height,
+            self.text.show,
+        )))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use image::GenericImageView;
+
     use super::*;
+    use crate::encoders::encoder::RenderOptions;
+    use crate::encoders::sizing::Sizing;
+
+    #[test]
+    fn test_escpos_code128_data() {
+        let data = code128_data(&"{ab123456".chars().collect::<Vec<char>>(), false).unwrap();
+        assert_eq!(b"{B{{ab{C\x0c\x22\x38".to_vec(), escpos_code128_data(&data));
+    }
 
     #[test]
     fn test_encode() {
-        let barcode = BarCode::new("a".to_owned(), 5);
-        let actual = barcode.encode().unwrap();
+        let barcode = BarCode::new("a".to_owned(), 5, TextOptions::default());
+        let options = RenderOptions {
+            quiet_zone: Some(QuietZone::default()),
+            ..RenderOptions::default()
+        };
+        let actual = Encoded::new(&barcode, options)
+            .and_then(|code| code.image())
+            .unwrap()
+            .to_rgba()
+            .into_raw();
         let expected = vec![
             0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255,
-            255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255,
+            255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0,
+            255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255,
+            0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255,
+            0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0,
+            0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255,
+            255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0,
+            255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255,
+            255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0,
+            255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255,
+            255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0,
             255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0,
+            255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0,
+            255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255,
+            255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0,
+            255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255,
+            255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0,
             255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0,
             255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255,
             0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255,
-            255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0,
-            0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0,
-            0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0,
-            0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255,
-            255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0,
-            0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-            0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255,
-            0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255,
-            0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255,
-            255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255,
-            255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255,
-            255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255,
-            255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255,
-            255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0,
-            0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255,
             255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255,
-            255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255,
             255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255,
-            255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255,
+            255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255,
+            255, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255,
         ];
-        assert_eq!(expected, actual.raw_pixels());
+        assert_eq!(expected, actual);
+    }
+
+    const STOP: usize = 106;
+
+    /// Module patterns of all Code 128 symbol values, taken from `barcoders` itself.
+    fn patterns() -> Vec<Vec<u8>> {
+        let symbol = |data: String, index: usize| {
+            Code128::new(data).unwrap().encode()[index * 11..(index + 1) * 11].to_vec()
+        };
+        let mut patterns: Vec<Vec<u8>> =
+            (0..100).map(|v| symbol(format!("Ć{:02}", v), 1)).collect();
+        patterns.push(symbol("ĆƁa".to_owned(), 1));
+        patterns.push(symbol("ĆÀA".to_owned(), 1));
+        patterns.push(symbol("ĆŹ".to_owned(), 1));
+        patterns.push(symbol("ÀA".to_owned(), 0));
+        patterns.push(symbol("ƁA".to_owned(), 0));
+        patterns.push(symbol("Ć00".to_owned(), 0));
+        patterns
+    }
+
+    /// Reads the bars back into text, returning the content and the number of symbols.
+    fn decode(bars: &[u8]) -> (String, usize) {
+        let patterns = patterns();
+        let mut values = Vec::new();
+        for chunk in bars.chunks(11) {
+            match patterns.iter().position(|p| &p[..] == chunk) {
+                Some(value) => values.push(value),
+                None => {
+                    values.push(STOP);
+                    break;
+                }
+            }
+        }
+        let checksum = values[..values.len() - 2]
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| v * std::cmp::max(i, 1))
+            .sum::<usize>()
+            % 103;
+        assert_eq!(checksum, values[values.len() - 2]);
+
+        let mut set = CharacterSet::A;
+        let mut text = String::new();
+        for &value in &values[..values.len() - 2] {
+            match (set, value) {
+                (_, 103) => set = CharacterSet::A,
+                (_, 104) => set = CharacterSet::B,
+                (_, 105) => set = CharacterSet::C,
+                (_, 102) => text.push(FNC1),
+                (CharacterSet::C, v) if v < 100 => text.push_str(&format!("{:02}", v)),
+                (CharacterSet::A, 101) | (CharacterSet::B, 101) => set = CharacterSet::A,
+                (CharacterSet::C, 101) => set = CharacterSet::A,
+                (_, 100) => set = CharacterSet::B,
+                (_, 99) => set = CharacterSet::C,
+                (CharacterSet::A, v) if v < 64 => text.push((v as u8 + 32) as char),
+                (CharacterSet::A, v) => text.push((v as u8 - 64) as char),
+                (CharacterSet::B, v) => text.push((v as u8 + 32) as char),
+                _ => unreachable!(),
+            }
+        }
+        (text, values.len() - 2)
+    }
+
+    fn round_trip(payload: &str) -> usize {
+        let barcode = BarCode::new(payload.to_owned(), 10, TextOptions::default());
+        let (text, symbols) = decode(&barcode.get_bytes().unwrap());
+        assert_eq!(payload, text);
+        symbols
+    }
+
+    #[test]
+    fn test_lowercase_round_trip() {
+        assert_eq!(4, round_trip("abc"));
+        round_trip("Hello, World!");
+        round_trip("tab\there\u{007F}");
+    }
+
+    #[test]
+    fn test_numeric_uses_subset_c() {
+        // Start C and three digit pairs.
+        assert_eq!(4, round_trip("123456"));
+        // Odd digit counts leave one digit to another subset.
+        assert_eq!(6, round_trip("1234567"));
+    }
+
+    #[test]
+    fn test_switching() {
+        assert_eq!(10, round_trip("ab123456cd"));
+        round_trip("\u{0001}x\u{0002}y");
+        round_trip("0");
+    }
+
+    #[test]
+    fn test_not_ascii() {
+        let barcode = BarCode::new("тест".to_owned(), 10, TextOptions::default());
+        assert!(barcode.get_bytes().is_err());
+        // The character `barcoders` reads as FNC1.
+        let barcode = BarCode::new("a\u{0179}b".to_owned(), 10, TextOptions::default());
+        assert_eq!(
+            Err("Character '\u{0179}' cannot be encoded in Code 128".to_owned()),
+            barcode.get_bytes()
+        );
+    }
+
+    #[test]
+    fn test_gs1() {
+        let barcode = BarCode::new_gs1(
+            "(01)09501101530003(10)AB(17)251231".to_owned(),
+            10,
+            TextOptions::default(),
+        );
+        let (text, symbols) = decode(&barcode.get_bytes().unwrap());
+        // FNC1 in the first position and after the variable length batch number.
+        assert_eq!("Ź010950110153000310ABŹ17251231", text);
+        // Start C, FNC1, nine pairs, Code B, AB, FNC1, Code C and four pairs.
+        assert_eq!(20, symbols);
+        assert!(
+            BarCode::new_gs1("(01)09501101530004".to_owned(), 10, TextOptions::default())
+                .get_bytes()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_gs1_length() {
+        // 16 + 8 + 3 + 1 separator + 20 = 48 characters.
+        let longest = BarCode::new_gs1(
+            "(01)09501101530003(17)251231(10)A(21)123456789012345678".to_owned(),
+            10,
+            TextOptions::default(),
+        );
+        assert!(longest.get_bytes().is_ok());
+        let barcode = BarCode::new_gs1(
+            "(01)09501101530003(17)251231(10)A(21)1234567890123456789".to_owned(),
+            10,
+            TextOptions::default(),
+        );
+        assert_eq!(
+            Err(
+                "GS1-128 holds at most 48 data characters including separators, got 49, \
+                 split the data across several symbols"
+                    .to_owned()
+            ),
+            barcode.get_bytes()
+        );
+    }
+
+    #[test]
+    fn test_text() {
+        let text = TextOptions {
+            show: true,
+            font_size: Some(20),
+        };
+        let barcode = BarCode::new("abc".to_owned(), 50, text);
+        let options = RenderOptions::default();
+        let bars = Encoded::new(&barcode, options)
+            .and_then(|code| code.image())
+            .unwrap();
+        let image = Encoded::new(&barcode, options).unwrap().render().unwrap();
+        assert_eq!(bars.width(), image.width());
+        assert!(image.height() >= 50 + 20);
+
+        let hidden = BarCode::new("abc".to_owned(), 50, TextOptions::default());
+        assert_eq!(
+            50,
+            Encoded::new(&hidden, options)
+                .unwrap()
+                .render()
+                .unwrap()
+                .height()
+        );
+    }
+
+    #[test]
+    fn test_quiet_zone() {
+        let barcode = BarCode::new("abc".to_owned(), 10, TextOptions::default());
+        let symbol = barcode.symbol().unwrap();
+        let matrix = Encoded::new(&barcode, RenderOptions::default())
+            .unwrap()
+            .matrix;
+        assert_eq!(symbol.width() + 20, matrix.width());
+        assert!(!matrix.get(9, 0));
+        assert!(matrix.get(10, 0));
+
+        let options = RenderOptions {
+            quiet_zone: Some(QuietZone {
+                top: 2,
+                right: 5,
+                bottom: 2,
+                left: 5,
+            }),
+            ..RenderOptions::default()
+        };
+        let matrix = Encoded::new(&barcode, options).unwrap().matrix;
+        assert_eq!(
+            (symbol.width() + 10, symbol.height() + 4),
+            (matrix.width(), matrix.height())
+        );
+    }
+
+    #[test]
+    fn test_module_width() {
+        let barcode = BarCode::new("abc".to_owned(), 150, TextOptions::default());
+        let modules = Encoded::new(&barcode, RenderOptions::default())
+            .unwrap()
+            .matrix
+            .width() as u32;
+        let options = RenderOptions {
+            sizing: Sizing::new(Some(3), None, None, None).unwrap(),
+            ..RenderOptions::default()
+        };
+        // The module gets wider, the bars keep their height.
+        let image = Encoded::new(&barcode, options)
+            .and_then(|code| code.image())
+            .unwrap();
+        assert_eq!((modules * 3, 150), image.dimensions());
+
+        let options = RenderOptions {
+            sizing: Sizing::new(None, None, None, Some(modules * 4 + 3)).unwrap(),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            modules * 4,
+            Encoded::new(&barcode, options)
+                .and_then(|code| code.image())
+                .unwrap()
+                .width()
+        );
     }
 }
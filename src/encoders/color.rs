@@ -0,0 +1,217 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/// Lowest symbol contrast accepted by the contrast check, grade C of ISO/IEC 15416.
+const MIN_SYMBOL_CONTRAST: f64 = 0.4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+pub const BLACK: Color = Color {
+    r: 0,
+    g: 0,
+    b: 0,
+    a: 255,
+};
+
+pub const WHITE: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+    a: 255,
+};
+
+/// Converts an sRGB component to linear light.
+fn linear(component: u8) -> f64 {
+    let c = component as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl Color {
+    /// Parses hex `RGB`, `RGBA`, `RRGGBB` or `RRGGBBAA`, with or without a leading `#`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let hex = value.trim_start_matches('#');
+        let invalid = || format!("Invalid colour {:?}, expected hex RGB(A)", value);
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let components: Vec<u8> = match hex.len() {
+            3 | 4 => hex
+                .chars()
+                .map(|c| c.to_digit(16).unwrap() as u8 * 17)
+                .collect(),
+            6 | 8 => (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect(),
+            _ => return Err(invalid()),
+        };
+        Ok(Color {
+            r: components[0],
+            g: components[1],
+            b: components[2],
+            a: components.get(3).copied().unwrap_or(255),
+        })
+    }
+
+    /// `#rrggbb` without the alpha channel, as SVG and CSS expect it.
+    pub fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    pub fn opacity(self) -> f64 {
+        self.a as f64 / 255.0
+    }
+
+    /// Components from 0 to 1, as PDF and PostScript expect them.
+    pub fn components(self) -> [f64; 3] {
+        [self.r, self.g, self.b].map(|c| c as f64 / 255.0)
+    }
+
+    /// The colour as seen on top of `under`.
+    fn over(self, under: Color) -> Color {
+        let alpha = self.opacity();
+        let mix = |top: u8, bottom: u8| {
+            (top as f64 * alpha + bottom as f64 * (1.0 - alpha)).round() as u8
+        };
+        Color {
+            r: mix(self.r, under.r),
+            g: mix(self.g, under.g),
+            b: mix(self.b, under.b),
+            a: 255,
+        }
+    }
+
+    /// Reflectance seen by a camera based imager, the relative luminance.
+    fn luminance(self) -> f64 {
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// Reflectance seen by laser and LED scanners, which light the symbol in red.
+    fn red_reflectance(self) -> f64 {
+        linear(self.r)
+    }
+}
+
+/// Rejects colours which scanners cannot tell apart: the dark modules must reflect at least
+/// 40% less light than the background, both in red scanner light and for camera based imagers.
+/// A transparent background is assumed to be printed on white.
+pub fn check_contrast(foreground: Color, background: Color) -> Result<(), String> {
+    let background = background.over(WHITE);
+    let foreground = foreground.over(background);
+    let contrast = f64::min(
+        background.red_reflectance() - foreground.red_reflectance(),
+        background.luminance() - foreground.luminance(),
+    );
+    if contrast < MIN_SYMBOL_CONTRAST {
+        return Err(format!(
+            "Symbol contrast of {:.0}% between {} and {} is below {:.0}%, scanners may not read it",
+            contrast.max(0.0) * 100.0,
+            foreground.hex(),
+            background.hex(),
+            MIN_SYMBOL_CONTRAST * 100.0
+        ));
+    }
+    Ok(())
+}
+
+/// Paints the black and white image in the given colours, grey levels of anti-aliased text
+/// become a blend of both.
+pub fn colorize(image: DynamicImage, foreground: Color, background: Color) -> DynamicImage {
+    if foreground == BLACK && background == WHITE {
+        return image;
+    }
+    let luma = image.to_luma();
+    let mut colored = RgbaImage::new(luma.width(), luma.height());
+    for (x, y, pixel) in luma.enumerate_pixels() {
+        let light = pixel[0] as f64 / 255.0;
+        let mix = |dark: u8, bright: u8| {
+            (dark as f64 * (1.0 - light) + bright as f64 * light).round() as u8
+        };
+        colored.put_pixel(
+            x,
+            y,
+            Rgba([
+                mix(foreground.r, background.r),
+                mix(foreground.g, background.g),
+                mix(foreground.b, background.b),
+                mix(foreground.a, background.a),
+            ]),
+        );
+    }
+    DynamicImage::ImageRgba8(colored)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma};
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Ok(BLACK), Color::parse("000"));
+        assert_eq!(Ok(WHITE), Color::parse("#ffffff"));
+        assert_eq!(
+            Ok(Color {
+                r: 0x12,
+                g: 0x34,
+                b: 0x56,
+                a: 0
+            }),
+            Color::parse("12345600")
+        );
+        assert_eq!(
+            Ok(Color {
+                r: 0xff,
+                g: 0,
+                b: 0,
+                a: 0x88
+            }),
+            Color::parse("f008")
+        );
+        assert!(Color::parse("12345").is_err());
+        assert!(Color::parse("gggggg").is_err());
+        assert!(Color::parse("").is_err());
+    }
+
+    #[test]
+    fn test_contrast() {
+        assert!(check_contrast(BLACK, WHITE).is_ok());
+        assert!(check_contrast(Color::parse("00008b").unwrap(), WHITE).is_ok());
+        assert!(check_contrast(BLACK, Color::parse("ffffff00").unwrap()).is_ok());
+        // Red bars vanish in the red light of laser scanners.
+        assert_eq!(
+            Err(
+                "Symbol contrast of 0% between #ff0000 and #ffffff is below 40%, \
+                 scanners may not read it"
+                    .to_owned()
+            ),
+            check_contrast(Color::parse("f00").unwrap(), WHITE)
+        );
+        // Inverted colours.
+        assert!(check_contrast(WHITE, BLACK).is_err());
+        assert!(check_contrast(Color::parse("ccc").unwrap(), WHITE).is_err());
+    }
+
+    #[test]
+    fn test_colorize() {
+        let mut image = GrayImage::from_pixel(3, 1, Luma([255]));
+        image.put_pixel(0, 0, Luma([0]));
+        image.put_pixel(1, 0, Luma([128]));
+        let foreground = Color::parse("00f").unwrap();
+        let background = Color::parse("ffffff00").unwrap();
+        let colored = colorize(DynamicImage::ImageLuma8(image), foreground, background).to_rgba();
+        assert_eq!(Rgba([0, 0, 255, 255]), *colored.get_pixel(0, 0));
+        assert_eq!(Rgba([128, 128, 255, 127]), *colored.get_pixel(1, 0));
+        assert_eq!(Rgba([255, 255, 255, 0]), *colored.get_pixel(2, 0));
+    }
+}
@@ -0,0 +1,300 @@
+use serde::{Deserialize, Serialize};
+
+/// Longest content line of a vCard before it is folded, in octets without the line break.
+const FOLD_OCTETS: usize = 75;
+
+/// Text format of the contact.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CardFormat {
+    /// vCard 3.0 of RFC 2426, read by most phones.
+    Vcard3,
+    /// vCard 4.0 of RFC 6350.
+    Vcard4,
+    /// NTT DoCoMo MeCard, much shorter than a vCard and read by Android and iOS.
+    Mecard,
+}
+
+impl CardFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            CardFormat::Vcard3 => "vCard 3.0",
+            CardFormat::Vcard4 => "vCard 4.0",
+            CardFormat::Mecard => "MeCard",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Contact {
+    /// Full name, the last word is taken as the family name.
+    pub name: String,
+    pub organisation: Option<String>,
+    pub phones: Vec<String>,
+    pub emails: Vec<String>,
+    /// Postal address, lines separated by line breaks.
+    pub address: Option<String>,
+    pub url: Option<String>,
+}
+
+/// Escapes a vCard text value: the separators of structured values and multiple values
+/// are `;` and `,`, line breaks are written as `\n`.
+fn escape_vcard(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes a MeCard value, which has no line breaks, so the lines of an address are joined by commas.
+fn escape_mecard(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.replace("\r\n", "\n").replace('\n', ", ").chars() {
+        if let '\\' | ';' | ':' | ',' | '"' = c {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Checks a URI value, which is written as is: a line break would end the property and
+/// start another one.
+fn check_uri(field: &str, value: &str) -> Result<(), String> {
+    if value.chars().any(char::is_control) {
+        return Err(format!(
+            "The {} {:?} holds a line break or control character",
+            field, value
+        ));
+    }
+    Ok(())
+}
+
+/// Phone number as a tel URI of RFC 3966, which separates digits by dashes. `;` starts a
+/// parameter and `,` is a pause, so these are percent-encoded along with `%` itself.
+fn tel_uri(phone: &str) -> Result<String, String> {
+    check_uri("phone number", phone)?;
+    let mut uri = "tel:".to_owned();
+    for (i, part) in phone.split_whitespace().enumerate() {
+        if i > 0 {
+            uri.push('-');
+        }
+        for c in part.chars() {
+            match c {
+                '%' => uri.push_str("%25"),
+                ',' => uri.push_str("%2C"),
+                ';' => uri.push_str("%3B"),
+                _ => uri.push(c),
+            }
+        }
+    }
+    Ok(uri)
+}
+
+/// Folds a content line into lines of at most `FOLD_OCTETS` octets, continued by a space
+/// on the next line. UTF-8 sequences are kept whole.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 3);
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > FOLD_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+impl Contact {
+    /// Family and given names, split at the last space of the full name.
+    fn family_and_given(&self) -> (&str, &str) {
+        match self.name.trim().rsplit_once(' ') {
+            Some((given, family)) => (family, given.trim_end()),
+            None => (self.name.trim(), ""),
+        }
+    }
+
+    fn vcard(&self, version: &str) -> Result<String, String> {
+        let v4 = version == "4.0";
+        let (family, given) = self.family_and_given();
+        let mut lines = vec![
+            "BEGIN:VCARD".to_owned(),
+            format!("VERSION:{}", version),
+            format!("N:{};{};;;", escape_vcard(family), escape_vcard(given)),
+            format!("FN:{}", escape_vcard(self.name.trim())),
+        ];
+        if let Some(organisation) = &self.organisation {
+            lines.push(format!("ORG:{}", escape_vcard(organisation)));
+        }
+        for phone in &self.phones {
+            lines.push(if v4 {
+                format!("TEL;VALUE=uri:{}", tel_uri(phone)?)
+            } else {
+                format!("TEL:{}", escape_vcard(phone))
+            });
+        }
+        for email in &self.emails {
+            lines.push(if v4 {
+                format!("EMAIL:{}", escape_vcard(email))
+            } else {
+                format!("EMAIL;TYPE=INTERNET:{}", escape_vcard(email))
+            });
+        }
+        if let Some(address) = &self.address {
+            // The whole address is the street component, readers show it as written.
+            lines.push(format!("ADR:;;{};;;;", escape_vcard(address)));
+        }
+        if let Some(url) = &self.url {
+            check_uri("URL", url)?;
+            lines.push(format!("URL:{}", url));
+        }
+        lines.push("END:VCARD".to_owned());
+        Ok(lines.iter().map(|line| fold(line)).collect())
+    }
+
+    fn mecard(&self) -> String {
+        let (family, given) = self.family_and_given();
+        let mut name = escape_mecard(family);
+        if !given.is_empty() {
+            name = format!("{},{}", name, escape_mecard(given));
+        }
+        let mut fields = vec![format!("N:{}", name)];
+        if let Some(organisation) = &self.organisation {
+            fields.push(format!("ORG:{}", escape_mecard(organisation)));
+        }
+        for phone in &self.phones {
+            fields.push(format!("TEL:{}", escape_mecard(phone)));
+        }
+        for email in &self.emails {
+            fields.push(format!("EMAIL:{}", escape_mecard(email)));
+        }
+        if let Some(address) = &self.address {
+            fields.push(format!("ADR:{}", escape_mecard(address)));
+        }
+        if let Some(url) = &self.url {
+            fields.push(format!("URL:{}", escape_mecard(url)));
+        }
+        format!("MECARD:{};;", fields.join(";"))
+    }
+
+    pub fn payload(&self, format: CardFormat) -> Result<String, String> {
+        if self.name.trim().is_empty() {
+            return Err("A contact needs a name".to_owned());
+        }
+        match format {
+            CardFormat::Vcard3 => self.vcard("3.0"),
+            CardFormat::Vcard4 => self.vcard("4.0"),
+            CardFormat::Mecard => Ok(self.mecard()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact() -> Contact {
+        Contact {
+            name: "Jane Q. Doe".to_owned(),
+            organisation: Some("Acme, Inc.".to_owned()),
+            phones: vec!["+1 555 0100".to_owned()],
+            emails: vec!["jane@example.com".to_owned()],
+            address: Some("1 Main St\nSpringfield".to_owned()),
+            url: Some("https://example.com".to_owned()),
+        }
+    }
+
+    #[test]
+    fn test_vcard3() {
+        assert_eq!(
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nN:Doe;Jane Q.;;;\r\nFN:Jane Q. Doe\r\n\
+             ORG:Acme\\, Inc.\r\nTEL:+1 555 0100\r\nEMAIL;TYPE=INTERNET:jane@example.com\r\n\
+             ADR:;;1 Main St\\nSpringfield;;;;\r\nURL:https://example.com\r\nEND:VCARD\r\n",
+            contact().payload(CardFormat::Vcard3).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_vcard4() {
+        let vcard = contact().payload(CardFormat::Vcard4).unwrap();
+        assert!(vcard.contains("\r\nVERSION:4.0\r\n"));
+        assert!(vcard.contains("\r\nTEL;VALUE=uri:tel:+1-555-0100\r\n"));
+        assert!(vcard.contains("\r\nEMAIL:jane@example.com\r\n"));
+    }
+
+    #[test]
+    fn test_uri_values() {
+        let tel = Contact {
+            phones: vec!["+1 555 0100;ext=1,2%".to_owned()],
+            ..contact()
+        };
+        assert!(tel
+            .payload(CardFormat::Vcard4)
+            .unwrap()
+            .contains("\r\nTEL;VALUE=uri:tel:+1-555-0100%3Bext=1%2C2%25\r\n"));
+        for url in &[
+            "https://example.com\nEMAIL:x@example.com",
+            "https://example.com\r",
+        ] {
+            let injected = Contact {
+                url: Some((*url).to_owned()),
+                ..contact()
+            };
+            assert!(injected.payload(CardFormat::Vcard3).is_err(), "{:?}", url);
+            assert!(injected.payload(CardFormat::Vcard4).is_err(), "{:?}", url);
+        }
+        let control = Contact {
+            phones: vec!["555\u{0}0100".to_owned()],
+            ..contact()
+        };
+        assert!(control.payload(CardFormat::Vcard4).is_err());
+    }
+
+    #[test]
+    fn test_mecard() {
+        assert_eq!(
+            "MECARD:N:Doe,Jane Q.;ORG:Acme\\, Inc.;TEL:+1 555 0100;EMAIL:jane@example.com;\
+             ADR:1 Main St\\, Springfield;URL:https\\://example.com;;",
+            contact().payload(CardFormat::Mecard).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fold() {
+        // 74 octets and a two octet character, which moves to the next line whole.
+        let line = format!("{}é", "a".repeat(74));
+        assert_eq!(format!("{}\r\n é\r\n", "a".repeat(74)), fold(&line));
+        let folded = fold(&"b".repeat(200));
+        assert!(folded.split("\r\n").all(|line| line.len() <= FOLD_OCTETS));
+        assert_eq!(200, folded.replace("\r\n ", "").trim_end().len());
+    }
+
+    #[test]
+    fn test_name() {
+        let contact = Contact {
+            name: "Cher".to_owned(),
+            ..Contact::default()
+        };
+        assert!(contact
+            .payload(CardFormat::Vcard3)
+            .unwrap()
+            .contains("\r\nN:Cher;;;;\r\n"));
+        assert_eq!(
+            "MECARD:N:Cher;;",
+            contact.payload(CardFormat::Mecard).unwrap()
+        );
+        assert!(Contact::default().payload(CardFormat::Mecard).is_err());
+    }
+}
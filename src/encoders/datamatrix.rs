@@ -0,0 +1,966 @@
+use serde::{Deserialize, Serialize};
+
+use crate::encoders::encoder::{Encode, Encoded, OutputParams};
+use crate::encoders::matrix::{Matrix, QuietZone};
+use crate::encoders::reed_solomon::GaloisField;
+use crate::encoders::zpl;
+
+const QUIET_ZONE: usize = 1;
+
+const PAD: u8 = 129;
+const LATCH_TO_C40: u8 = 230;
+const LATCH_TO_BASE256: u8 = 231;
+const UPPER_SHIFT: u8 = 235;
+const LATCH_TO_X12: u8 = 238;
+const LATCH_TO_TEXT: u8 = 239;
+const LATCH_TO_EDIFACT: u8 = 240;
+const UNLATCH: u8 = 254;
+const EDIFACT_UNLATCH: u8 = 31;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Shape {
+    Square,
+    Rectangle,
+    Any,
+}
+
+struct SymbolInfo {
+    rows: usize,
+    columns: usize,
+    region_rows: usize,
+    region_columns: usize,
+    data_capacity: usize,
+    ec_codewords: usize,
+    blocks: usize,
+}
+
+const fn symbol(
+    rows: usize,
+    columns: usize,
+    region_rows: usize,
+    region_columns: usize,
+    data_capacity: usize,
+    ec_codewords: usize,
+    blocks: usize,
+) -> SymbolInfo {
+    SymbolInfo {
+        rows,
+        columns,
+        region_rows,
+        region_columns,
+        data_capacity,
+        ec_codewords,
+        blocks,
+    }
+}
+
+// ECC 200 symbol sizes ordered by data capacity.
+const SYMBOLS: [SymbolInfo; 30] = [
+    symbol(10, 10, 8, 8, 3, 5, 1),
+    symbol(12, 12, 10, 10, 5, 7, 1),
+    symbol(8, 18, 6, 16, 5, 7, 1),
+    symbol(14, 14, 12, 12, 8, 10, 1),
+    symbol(8, 32, 6, 14, 10, 11, 1),
+    symbol(16, 16, 14, 14, 12, 12, 1),
+    symbol(12, 26, 10, 24, 16, 14, 1),
+    symbol(18, 18, 16, 16, 18, 14, 1),
+    symbol(20, 20, 18, 18, 22, 18, 1),
+    symbol(12, 36, 10, 16, 22, 18, 1),
+    symbol(22, 22, 20, 20, 30, 20, 1),
+    symbol(16, 36, 14, 16, 32, 24, 1),
+    symbol(24, 24, 22, 22, 36, 24, 1),
+    symbol(26, 26, 24, 24, 44, 28, 1),
+    symbol(16, 48, 14, 22, 49, 28, 1),
+    symbol(32, 32, 14, 14, 62, 36, 1),
+    symbol(36, 36, 16, 16, 86, 42, 1),
+    symbol(40, 40, 18, 18, 114, 48, 1),
+    symbol(44, 44, 20, 20, 144, 56, 1),
+    symbol(48, 48, 22, 22, 174, 68, 1),
+    symbol(52, 52, 24, 24, 204, 84, 2),
+    symbol(64, 64, 14, 14, 280, 112, 2),
+    symbol(72, 72, 16, 16, 368, 144, 4),
+    symbol(80, 80, 18, 18, 456, 192, 4),
+    symbol(88, 88, 20, 20, 576, 224, 4),
+    symbol(96, 96, 22, 22, 696, 272, 4),
+    symbol(104, 104, 24, 24, 816, 336, 6),
+    symbol(120, 120, 18, 18, 1050, 408, 6),
+    symbol(132, 132, 20, 20, 1304, 496, 8),
+    symbol(144, 144, 22, 22, 1558, 620, 10),
+];
+
+impl SymbolInfo {
+    fn matches(&self, shape: Shape) -> bool {
+        match shape {
+            Shape::Square => self.rows == self.columns,
+            Shape::Rectangle => self.rows != self.columns,
+            Shape::Any => true,
+        }
+    }
+
+    fn horizontal_regions(&self) -> usize {
+        self.columns / (self.region_columns + 2)
+    }
+
+    fn vertical_regions(&self) -> usize {
+        self.rows / (self.region_rows + 2)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Encodation {
+    Ascii,
+    C40,
+    Text,
+    X12,
+    Edifact,
+    Base256,
+}
+
+const ENCODATIONS: [Encodation; 6] = [
+    Encodation::Ascii,
+    Encodation::C40,
+    Encodation::Text,
+    Encodation::X12,
+    Encodation::Edifact,
+    Encodation::Base256,
+];
+
+fn is_native_c40(c: u8) -> bool {
+    c == b' ' || c.is_ascii_digit() || c.is_ascii_uppercase()
+}
+
+fn is_native_text(c: u8) -> bool {
+    c == b' ' || c.is_ascii_digit() || c.is_ascii_lowercase()
+}
+
+fn is_native_x12(c: u8) -> bool {
+    matches!(c, b'\r' | b'*' | b'>') || is_native_c40(c)
+}
+
+fn is_x12_terminator(c: u8) -> bool {
+    matches!(c, b'\r' | b'*' | b'>')
+}
+
+fn is_native_edifact(c: u8) -> bool {
+    (32..=94).contains(&c)
+}
+
+/// Look-ahead test from ISO/IEC 16022 Annex P, which picks the encodation for the rest of the data.
+fn look_ahead(data: &[u8], start: usize, current: Encodation) -> Encodation {
+    let mode = look_ahead_intern(data, start, current);
+    let native: fn(u8) -> bool = match (current, mode) {
+        (Encodation::X12, Encodation::X12) => is_native_x12,
+        (Encodation::Edifact, Encodation::Edifact) => is_native_edifact,
+        _ => return mode,
+    };
+    let window = if mode == Encodation::X12 { 3 } else { 4 };
+    let end = std::cmp::min(start + window, data.len());
+    if data[start..end].iter().all(|&c| native(c)) {
+        mode
+    } else {
+        Encodation::Ascii
+    }
+}
+
+fn look_ahead_intern(data: &[u8], start: usize, current: Encodation) -> Encodation {
+    if start >= data.len() {
+        return current;
+    }
+    let mut counts = if current == Encodation::Ascii {
+        [0.0, 1.0, 1.0, 1.0, 1.0, 1.25]
+    } else {
+        let mut counts = [1.0, 2.0, 2.0, 2.0, 2.0, 2.25];
+        counts[current as usize] = 0.0;
+        counts
+    };
+    let mut processed = 0;
+    loop {
+        if start + processed == data.len() {
+            let rounded = round_counts(&counts);
+            let min = *rounded.iter().min().unwrap();
+            let minimums: Vec<Encodation> = ENCODATIONS
+                .iter()
+                .copied()
+                .filter(|&e| rounded[e as usize] == min)
+                .collect();
+            if rounded[Encodation::Ascii as usize] == min {
+                return Encodation::Ascii;
+            }
+            if minimums.len() == 1 && minimums[0] != Encodation::C40 {
+                return minimums[0];
+            }
+            return Encodation::C40;
+        }
+
+        let c = data[start + processed];
+        processed += 1;
+        let extended = c >= 128;
+
+        let ascii = &mut counts[Encodation::Ascii as usize];
+        if c.is_ascii_digit() {
+            *ascii += 0.5;
+        } else {
+            *ascii = ascii.ceil() + if extended { 2.0 } else { 1.0 };
+        }
+        counts[Encodation::C40 as usize] += match (is_native_c40(c), extended) {
+            (true, _) => 2.0 / 3.0,
+            (false, true) => 8.0 / 3.0,
+            (false, false) => 4.0 / 3.0,
+        };
+        counts[Encodation::Text as usize] += match (is_native_text(c), extended) {
+            (true, _) => 2.0 / 3.0,
+            (false, true) => 8.0 / 3.0,
+            (false, false) => 4.0 / 3.0,
+        };
+        counts[Encodation::X12 as usize] += match (is_native_x12(c), extended) {
+            (true, _) => 2.0 / 3.0,
+            (false, true) => 13.0 / 3.0,
+            (false, false) => 10.0 / 3.0,
+        };
+        counts[Encodation::Edifact as usize] += match (is_native_edifact(c), extended) {
+            (true, _) => 3.0 / 4.0,
+            (false, true) => 17.0 / 4.0,
+            (false, false) => 13.0 / 4.0,
+        };
+        counts[Encodation::Base256 as usize] += 1.0;
+
+        if processed < 4 {
+            continue;
+        }
+        let rounded = round_counts(&counts);
+        let min_of = |modes: &[Encodation]| {
+            modes
+                .iter()
+                .map(|&mode| rounded[mode as usize])
+                .min()
+                .unwrap()
+        };
+        let [ascii, c40, text, x12, edifact, base256] = rounded;
+        use Encodation::*;
+        if ascii < min_of(&[Base256, C40, Text, X12, Edifact]) {
+            return Ascii;
+        }
+        if base256 < ascii || base256 + 1 < min_of(&[C40, Text, X12, Edifact]) {
+            return Base256;
+        }
+        if edifact + 1 < min_of(&[Base256, C40, Text, X12, Ascii]) {
+            return Edifact;
+        }
+        if text + 1 < min_of(&[Base256, C40, Edifact, X12, Ascii]) {
+            return Text;
+        }
+        if x12 + 1 < min_of(&[Base256, C40, Edifact, Text, Ascii]) {
+            return X12;
+        }
+        if c40 + 1 < min_of(&[Ascii, Base256, Edifact, Text]) {
+            if c40 < x12 {
+                return C40;
+            }
+            if c40 == x12 {
+                for &c in data.iter().skip(start + processed + 1) {
+                    if is_x12_terminator(c) {
+                        return X12;
+                    }
+                    if !is_native_x12(c) {
+                        break;
+                    }
+                }
+                return C40;
+            }
+        }
+    }
+}
+
+fn round_counts(counts: &[f32; 6]) -> [u32; 6] {
+    let mut rounded = [0; 6];
+    for (r, c) in rounded.iter_mut().zip(counts.iter()) {
+        *r = c.ceil() as u32;
+    }
+    rounded
+}
+
+/// C40 and Text values of a single character, including shifts.
+fn c40_values(c: u8, text: bool, values: &mut Vec<u8>) {
+    if c >= 128 {
+        values.extend_from_slice(&[1, 30]);
+        c40_values(c - 128, text, values);
+        return;
+    }
+    let (upper, lower) = if text {
+        (b'a'..=b'z', b'A'..=b'Z')
+    } else {
+        (b'A'..=b'Z', b'a'..=b'z')
+    };
+    match c {
+        b' ' => values.push(3),
+        b'0'..=b'9' => values.push(c - b'0' + 4),
+        c if upper.contains(&c) => values.push(c - upper.start() + 14),
+        0..=31 => values.extend_from_slice(&[0, c]),
+        b'!'..=b'/' => values.extend_from_slice(&[1, c - b'!']),
+        b':'..=b'@' => values.extend_from_slice(&[1, c - b':' + 15]),
+        b'['..=b'_' => values.extend_from_slice(&[1, c - b'[' + 22]),
+        b'`' => values.extend_from_slice(&[2, 0]),
+        c if lower.contains(&c) => values.extend_from_slice(&[2, c - lower.start() + 1]),
+        _ => values.extend_from_slice(&[2, c - b'{' + 27]),
+    }
+}
+
+fn x12_value(c: u8) -> u8 {
+    match c {
+        b'\r' => 0,
+        b'*' => 1,
+        b'>' => 2,
+        b' ' => 3,
+        b'0'..=b'9' => c - b'0' + 4,
+        _ => c - b'A' + 14,
+    }
+}
+
+/// Packs up to four 6-bit EDIFACT values into the codewords, an incomplete group is cut
+/// after its last value.
+fn edifact_group(values: &[u8]) -> Vec<u8> {
+    let bits = values
+        .iter()
+        .enumerate()
+        .fold(0u32, |acc, (i, &v)| acc | (v as u32) << (18 - 6 * i));
+    let bytes = (values.len() * 6).div_ceil(8);
+    (0..bytes).map(|i| (bits >> (16 - 8 * i)) as u8).collect()
+}
+
+/// Converts the payload into data codewords, switching encodations where it saves space.
+struct HighLevelEncoder<'a> {
+    data: &'a [u8],
+    position: usize,
+    codewords: Vec<u8>,
+    /// Data capacity of the smallest symbol holding this many codewords, if any does.
+    capacity: &'a dyn Fn(usize) -> Option<usize>,
+}
+
+impl<'a> HighLevelEncoder<'a> {
+    fn encode(
+        data: &'a [u8],
+        capacity: &'a dyn Fn(usize) -> Option<usize>,
+    ) -> (Vec<u8>, Encodation) {
+        let mut encoder = HighLevelEncoder {
+            data,
+            position: 0,
+            codewords: Vec::new(),
+            capacity,
+        };
+        let mut mode = Encodation::Ascii;
+        while encoder.position < data.len() {
+            mode = match mode {
+                Encodation::Ascii => encoder.encode_ascii(),
+                Encodation::C40 | Encodation::Text | Encodation::X12 => {
+                    encoder.encode_triplets(mode)
+                }
+                Encodation::Edifact => encoder.encode_edifact(),
+                Encodation::Base256 => encoder.encode_base256(),
+            };
+        }
+        (encoder.codewords, mode)
+    }
+
+    fn encode_ascii(&mut self) -> Encodation {
+        let c = self.data[self.position];
+        let mode = match look_ahead(self.data, self.position, Encodation::Ascii) {
+            Encodation::X12 if !is_native_x12(c) => Encodation::Ascii,
+            Encodation::Edifact if !is_native_edifact(c) => Encodation::Ascii,
+            mode => mode,
+        };
+        let latch = match mode {
+            Encodation::Ascii => {
+                self.encode_ascii_character();
+                return Encodation::Ascii;
+            }
+            Encodation::C40 => LATCH_TO_C40,
+            Encodation::Text => LATCH_TO_TEXT,
+            Encodation::X12 => LATCH_TO_X12,
+            Encodation::Edifact => LATCH_TO_EDIFACT,
+            Encodation::Base256 => LATCH_TO_BASE256,
+        };
+        self.codewords.push(latch);
+        mode
+    }
+
+    fn encode_ascii_character(&mut self) {
+        let data = &self.data[self.position..];
+        if data.len() >= 2 && data[0].is_ascii_digit() && data[1].is_ascii_digit() {
+            self.codewords
+                .push((data[0] - b'0') * 10 + (data[1] - b'0') + 130);
+            self.position += 2;
+        } else if data[0] >= 128 {
+            self.codewords.push(UPPER_SHIFT);
+            self.codewords.push(data[0] - 128 + 1);
+            self.position += 1;
+        } else {
+            self.codewords.push(data[0] + 1);
+            self.position += 1;
+        }
+    }
+
+    /// Drops a latch which turned out to encode nothing and moves on in ASCII.
+    fn cancel_latch(&mut self) -> Encodation {
+        self.codewords.pop();
+        self.encode_ascii_character();
+        Encodation::Ascii
+    }
+
+    fn encode_triplets(&mut self, mode: Encodation) -> Encodation {
+        let mut values = Vec::new();
+        // Number of values contributed by each consumed character.
+        let mut sizes = Vec::new();
+        while self.position < self.data.len() {
+            let c = self.data[self.position];
+            let before = values.len();
+            if mode == Encodation::X12 {
+                if !is_native_x12(c) {
+                    break;
+                }
+                values.push(x12_value(c));
+            } else {
+                c40_values(c, mode == Encodation::Text, &mut values);
+            }
+            sizes.push(values.len() - before);
+            self.position += 1;
+            if values.len() % 3 == 0 && look_ahead(self.data, self.position, mode) != mode {
+                break;
+            }
+        }
+        // Characters which do not complete a triplet are encoded in ASCII.
+        while values.len() % 3 != 0 {
+            let size = sizes.pop().unwrap();
+            values.truncate(values.len() - size);
+            self.position -= 1;
+        }
+        if values.is_empty() {
+            return self.cancel_latch();
+        }
+        for triplet in values.chunks(3) {
+            let value = 1600 * triplet[0] as u16 + 40 * triplet[1] as u16 + triplet[2] as u16 + 1;
+            self.codewords.push((value / 256) as u8);
+            self.codewords.push((value % 256) as u8);
+        }
+        if self.position < self.data.len() {
+            self.codewords.push(UNLATCH);
+            return Encodation::Ascii;
+        }
+        mode
+    }
+
+    fn encode_edifact(&mut self) -> Encodation {
+        let mut values = Vec::new();
+        while self.position < self.data.len() {
+            let c = self.data[self.position];
+            if !is_native_edifact(c) {
+                break;
+            }
+            values.push(c & 0x3F);
+            self.position += 1;
+            if values.len() % 4 == 0
+                && look_ahead(self.data, self.position, Encodation::Edifact) != Encodation::Edifact
+            {
+                break;
+            }
+        }
+        if values.is_empty() {
+            return self.cancel_latch();
+        }
+        let complete = values.len() - values.len() % 4;
+        for group in values[..complete].chunks(4) {
+            self.codewords.extend(edifact_group(group));
+        }
+        self.end_edifact(values[complete..].to_vec());
+        Encodation::Ascii
+    }
+
+    /// Codewords left in the smallest symbol holding `length` codewords, after those written.
+    fn available(&self, length: usize) -> usize {
+        (self.capacity)(length).map_or(usize::MAX, |capacity| capacity - self.codewords.len())
+    }
+
+    /// Writes the unlatch with the characters of an incomplete group. Decoders return to ASCII
+    /// on their own when a group starts with at most two codewords left in the symbol,
+    /// where an unlatch would be read as data, so the characters are left to ASCII instead.
+    /// Ported from `EdifactEncoder.handleEOD` of ZXing.
+    fn end_edifact(&mut self, mut values: Vec<u8>) {
+        let written = self.codewords.len();
+        if values.is_empty() {
+            let remaining = self.data.len() - self.position;
+            let mut available = self.available(written);
+            if remaining > available {
+                available = self.available(written + 1);
+            }
+            if remaining <= available && available <= 2 {
+                return;
+            }
+        }
+        let rest = values.len();
+        values.push(EDIFACT_UNLATCH);
+        let mut rest_in_ascii = self.position == self.data.len() && rest <= 2;
+        if rest <= 2 && self.available(written + rest) >= 3 {
+            rest_in_ascii = false;
+        }
+        if rest_in_ascii {
+            self.position -= rest;
+        } else {
+            self.codewords.extend(edifact_group(&values));
+        }
+    }
+
+    fn encode_base256(&mut self) -> Encodation {
+        let start = self.position;
+        self.position += 1;
+        while self.position < self.data.len()
+            && look_ahead(self.data, self.position, Encodation::Base256) == Encodation::Base256
+        {
+            self.position += 1;
+        }
+        let bytes = &self.data[start..self.position];
+        let mut field = Vec::with_capacity(bytes.len() + 2);
+        if bytes.len() <= 249 {
+            field.push(bytes.len() as u8);
+        } else {
+            field.push((bytes.len() / 250 + 249) as u8);
+            field.push((bytes.len() % 250) as u8);
+        }
+        field.extend_from_slice(bytes);
+        for byte in field {
+            let position = self.codewords.len() + 1;
+            let pseudo_random = ((149 * position) % 255) + 1;
+            self.codewords
+                .push(((byte as usize + pseudo_random) % 256) as u8);
+        }
+        Encodation::Ascii
+    }
+}
+
+/// Places the codewords into the data regions following the ECC 200 "utah" pattern.
+struct Placement<'a> {
+    codewords: &'a [u8],
+    rows: usize,
+    columns: usize,
+    bits: Vec<Option<bool>>,
+}
+
+impl<'a> Placement<'a> {
+    fn new(codewords: &'a [u8], rows: usize, columns: usize) -> Self {
+        Placement {
+            codewords,
+            rows,
+            columns,
+            bits: vec![None; rows * columns],
+        }
+    }
+
+    fn get(&self, column: usize, row: usize) -> bool {
+        self.bits[row * self.columns + column] == Some(true)
+    }
+
+    fn is_set(&self, column: usize, row: usize) -> bool {
+        self.bits[row * self.columns + column].is_some()
+    }
+
+    fn set(&mut self, column: usize, row: usize, value: bool) {
+        self.bits[row * self.columns + column] = Some(value);
+    }
+
+    fn module(&mut self, row: isize, column: isize, position: usize, bit: usize) {
+        let (rows, columns) = (self.rows as isize, self.columns as isize);
+        let (mut row, mut column) = (row, column);
+        if row < 0 {
+            row += rows;
+            column += 4 - ((rows + 4) % 8);
+        }
+        if column < 0 {
+            column += columns;
+            row += 4 - ((columns + 4) % 8);
+        }
+        let value = self.codewords[position] & (1 << (8 - bit)) != 0;
+        self.set(column as usize, row as usize, value);
+    }
+
+    fn utah(&mut self, row: isize, column: isize, position: usize) {
+        self.module(row - 2, column - 2, position, 1);
+        self.module(row - 2, column - 1, position, 2);
+        self.module(row - 1, column - 2, position, 3);
+        self.module(row - 1, column - 1, position, 4);
+        self.module(row - 1, column, position, 5);
+        self.module(row, column - 2, position, 6);
+        self.module(row, column - 1, position, 7);
+        self.module(row, column, position, 8);
+    }
+
+    fn corner(&mut self, modules: [(isize, isize); 8], position: usize) {
+        for (bit, &(row, column)) in modules.iter().enumerate() {
+            self.module(row, column, position, bit + 1);
+        }
+    }
+
+    fn place(&mut self) {
+        let (rows, columns) = (self.rows as isize, self.columns as isize);
+        let mut position = 0;
+        let mut row: isize = 4;
+        let mut column: isize = 0;
+        loop {
+            if row == rows && column == 0 {
+                let corner = [
+                    (rows - 1, 0),
+                    (rows - 1, 1),
+                    (rows - 1, 2),
+                    (0, columns - 2),
+                    (0, columns - 1),
+                    (1, columns - 1),
+                    (2, columns - 1),
+                    (3, columns - 1),
+                ];
+                self.corner(corner, position);
+                position += 1;
+            }
+            if row == rows - 2 && column == 0 && columns % 4 != 0 {
+                let corner = [
+                    (rows - 3, 0),
+                    (rows - 2, 0),
+                    (rows - 1, 0),
+                    (0, columns - 4),
+                    (0, columns - 3),
+                    (0, columns - 2),
+                    (0, columns - 1),
+                    (1, columns - 1),
+                ];
+                self.corner(corner, position);
+                position += 1;
+            }
+            if row == rows - 2 && column == 0 && columns % 8 == 4 {
+                let corner = [
+                    (rows - 3, 0),
+                    (rows - 2, 0),
+                    (rows - 1, 0),
+                    (0, columns - 2),
+                    (0, columns - 1),
+                    (1, columns - 1),
+                    (2, columns - 1),
+                    (3, columns - 1),
+                ];
+                self.corner(corner, position);
+                position += 1;
+            }
+            if row == rows + 4 && column == 2 && columns % 8 == 0 {
+                let corner = [
+                    (rows - 1, 0),
+                    (rows - 1, columns - 1),
+                    (0, columns - 3),
+                    (0, columns - 2),
+                    (0, columns - 1),
+                    (1, columns - 3),
+                    (1, columns - 2),
+                    (1, columns - 1),
+                ];
+                self.corner(corner, position);
+                position += 1;
+            }
+            // Sweep upward diagonally.
+            loop {
+                if row < rows && column >= 0 && !self.is_set(column as usize, row as usize) {
+                    self.utah(row, column, position);
+                    position += 1;
+                }
+                row -= 2;
+                column += 2;
+                if row < 0 || column >= columns {
+                    break;
+                }
+            }
+            row += 1;
+            column += 3;
+            // Sweep downward diagonally.
+            loop {
+                if row >= 0 && column < columns && !self.is_set(column as usize, row as usize) {
+                    self.utah(row, column, position);
+                    position += 1;
+                }
+                row += 2;
+                column -= 2;
+                if row >= rows || column < 0 {
+                    break;
+                }
+            }
+            row += 3;
+            column += 1;
+            if row >= rows && column >= columns {
+                break;
+            }
+        }
+        // Fill the unused corner.
+        if !self.is_set(self.columns - 1, self.rows - 1) {
+            self.set(self.columns - 1, self.rows - 1, true);
+            self.set(self.columns - 2, self.rows - 2, true);
+        }
+    }
+}
+
+pub struct DataMatrix {
+    height: u32,
+    shape: Shape,
+    size: Option<String>,
+    payload: String,
+}
+
+impl DataMatrix {
+    pub fn new(payload: String, height: u32, shape: Shape, size: Option<String>) -> Self {
+        DataMatrix {
+            payload,
+            height,
+            shape,
+            size,
+        }
+    }
+
+    fn get_symbol(&self, length: usize) -> Result<&'static SymbolInfo, String> {
+        if let Some(size) = &self.size {
+            let symbol = SYMBOLS
+                .iter()
+                .find(|s| format!("{}x{}", s.rows, s.columns) == size.to_lowercase())
+                .ok_or(format!("{} is not a Data Matrix ECC 200 symbol size", size))?;
+            if symbol.data_capacity < length {
+                return Err(format!(
+                    "Data needs {} codewords, but a {} symbol holds only {}",
+                    length, size, symbol.data_capacity
+                ));
+            }
+            return Ok(symbol);
+        }
+        SYMBOLS
+            .iter()
+            .find(|s| s.matches(self.shape) && s.data_capacity >= length)
+            .ok_or_else(|| "Data too large for a Data Matrix code".to_string())
+    }
+
+    fn get_codewords(&self) -> Result<(&'static SymbolInfo, Vec<u8>), String> {
+        if self.payload.is_empty() {
+            return Err("Payload is empty".to_string());
+        }
+        let capacity = |length| self.get_symbol(length).ok().map(|s| s.data_capacity);
+        let (mut codewords, mode) = HighLevelEncoder::encode(self.payload.as_bytes(), &capacity);
+        let symbol = self.get_symbol(codewords.len())?;
+        let unlatched = matches!(mode, Encodation::C40 | Encodation::Text | Encodation::X12);
+        if unlatched && codewords.len() < symbol.data_capacity {
+            codewords.push(UNLATCH);
+        }
+        if codewords.len() < symbol.data_capacity {
+            codewords.push(PAD);
+        }
+        while codewords.len() < symbol.data_capacity {
+            let position = codewords.len() + 1;
+            let pseudo_random = ((149 * position) % 253) + 1;
+            let value = PAD as usize + pseudo_random;
+            codewords.push(if value <= 254 { value } else { value - 254 } as u8);
+        }
+
+        let field = GaloisField::new(0x12D, 256, 1);
+        let blocks = symbol.blocks;
+        let ec_per_block = symbol.ec_codewords / blocks;
+        let mut result = codewords.clone();
+        result.resize(symbol.data_capacity + symbol.ec_codewords, 0);
+        for block in 0..blocks {
+            let data: Vec<u16> = codewords
+                .iter()
+                .skip(block)
+                .step_by(blocks)
+                .map(|&c| c as u16)
+                .collect();
+            let ec = field.encode(&data, ec_per_block);
+            for (i, word) in ec.into_iter().enumerate() {
+                result[symbol.data_capacity + block + i * blocks] = word as u8;
+            }
+        }
+        Ok((symbol, result))
+    }
+
+    pub fn get_matrix(&self) -> Result<Matrix, String> {
+        let (symbol, codewords) = self.get_codewords()?;
+        let data_rows = symbol.vertical_regions() * symbol.region_rows;
+        let data_columns = symbol.horizontal_regions() * symbol.region_columns;
+        let mut placement = Placement::new(&codewords, data_rows, data_columns);
+        placement.place();
+
+        // Surround each data region with the finder and timing patterns.
+        let mut matrix = Matrix::new(symbol.columns, symbol.rows);
+        let mut matrix_y = 0;
+        for y in 0..data_rows {
+            if y % symbol.region_rows == 0 {
+                for x in 0..symbol.columns {
+                    matrix.set(x, matrix_y, x % 2 == 0);
+                }
+                matrix_y += 1;
+            }
+            let mut matrix_x = 0;
+            for x in 0..data_columns {
+                if x % symbol.region_columns == 0 {
+                    matrix.set(matrix_x, matrix_y, true);
+                    matrix_x += 1;
+                }
+                matrix.set(matrix_x, matrix_y, placement.get(x, y));
+                matrix_x += 1;
+                if x % symbol.region_columns == symbol.region_columns - 1 {
+                    matrix.set(matrix_x, matrix_y, y % 2 == 0);
+                    matrix_x += 1;
+                }
+            }
+            matrix_y += 1;
+            if y % symbol.region_rows == symbol.region_rows - 1 {
+                for x in 0..symbol.columns {
+                    matrix.set(x, matrix_y, true);
+                }
+                matrix_y += 1;
+            }
+        }
+        Ok(matrix)
+    }
+}
+
+impl Encode for DataMatrix {
+    fn symbol(&self) -> Result<Matrix, String> {
+        self.get_matrix()
+    }
+
+    fn quiet_zone(&self) -> QuietZone {
+        QuietZone::uniform(QUIET_ZONE)
+    }
+
+    fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    fn get_params(&self) -> OutputParams {
+        OutputParams {
+            append_text: false,
+            font_size: 0,
+            height: self.height,
+        }
+    }
+
+    /// `^BX` with quality 200 and the rows and columns of the symbol, `~` starts an escape
+    /// sequence in the field data.
+    fn zpl(&self, code: &Encoded) -> Result<Option<String>, String> {
+        let (module, _) = code.module_size()?;
+        if self.payload.contains('~') {
+            return Ok(None);
+        }
+        Ok(Some(format!(
+            "^BX{},{},200,{},{}{}",
+            zpl::orientation(code.options.orientation.rotation),
+            module,
+            code.symbol.width(),
+            code.symbol.height(),
+            zpl::field_data(&self.payload)
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codewords(payload: &str) -> Vec<u8> {
+        DataMatrix::new(payload.to_owned(), 10, Shape::Square, None)
+            .get_codewords()
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn test_ascii_digits() {
+        // ISO/IEC 16022 Annex O.
+        assert_eq!(
+            vec![142, 164, 186, 114, 25, 5, 88, 102],
+            codewords("123456")
+        );
+    }
+
+    #[test]
+    fn test_c40() {
+        let codewords = codewords("AIMAIMAIM");
+        assert_eq!(
+            vec![LATCH_TO_C40, 91, 11, 91, 11, 91, 11, UNLATCH],
+            codewords[..8].to_vec()
+        );
+    }
+
+    #[test]
+    fn test_edifact_end_of_data() {
+        let groups = [LATCH_TO_EDIFACT, 184, 27, 194, 184, 27, 194];
+        // One codeword short of the 8 of a 12x12 symbol: no unlatch, the pad is read as ASCII.
+        let one_short = codewords(".A/B.A/B");
+        assert_eq!(groups.to_vec(), one_short[..7].to_vec());
+        assert_eq!(PAD, one_short[7]);
+        // The rest of an incomplete group is written in ASCII.
+        assert_eq!(
+            [&groups[..], &[b'.' + 1]].concat(),
+            codewords(".A/B.A/B.")[..8]
+        );
+        // Two short of the 12 of a 14x14 symbol.
+        let two_short = codewords(".A/B.A/B.A/B");
+        assert_eq!([&groups[..], &[184, 27, 194]].concat(), two_short[..10]);
+        assert_eq!(PAD, two_short[10]);
+        assert_eq!(
+            [&groups[..], &[184, 27, 194, b'.' + 1, b'A' + 1]].concat(),
+            codewords(".A/B.A/B.A/B.A")[..12]
+        );
+    }
+
+    #[test]
+    fn test_base256() {
+        let codewords = codewords("\u{e4}\u{e4}\u{e4}\u{e4}\u{e4}");
+        assert_eq!(LATCH_TO_BASE256, codewords[0]);
+    }
+
+    #[test]
+    fn test_encode() {
+        let datamatrix = DataMatrix::new("123456".to_owned(), 10, Shape::Square, None);
+        let matrix = datamatrix.get_matrix().unwrap();
+        assert_eq!(10, matrix.width());
+        // Solid "L" finder pattern and alternating timing pattern.
+        for i in 0..10 {
+            assert!(matrix.get(0, i));
+            assert!(matrix.get(i, 9));
+            assert_eq!(i % 2 == 0, matrix.get(i, 0));
+            assert_eq!(i % 2 == 1, matrix.get(9, i));
+        }
+    }
+
+    #[test]
+    fn test_rectangle() {
+        let datamatrix = DataMatrix::new("Hello".to_owned(), 10, Shape::Rectangle, None);
+        let matrix = datamatrix.get_matrix().unwrap();
+        assert_eq!(18, matrix.width());
+    }
+
+    #[test]
+    fn test_forced_size() {
+        let datamatrix = DataMatrix::new(
+            "Hello".to_owned(),
+            10,
+            Shape::Square,
+            Some("16x48".to_owned()),
+        );
+        assert_eq!(48, datamatrix.get_matrix().unwrap().width());
+
+        let datamatrix = DataMatrix::new(
+            "Hello".to_owned(),
+            10,
+            Shape::Square,
+            Some("10x10".to_owned()),
+        );
+        assert!(datamatrix.get_matrix().is_err());
+
+        let datamatrix = DataMatrix::new(
+            "Hello".to_owned(),
+            10,
+            Shape::Square,
+            Some("11x11".to_owned()),
+        );
+        assert!(datamatrix.get_matrix().is_err());
+    }
+}
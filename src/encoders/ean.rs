@@ -0,0 +1,650 @@
+use barcoders::sym::ean13::{EAN13, ENCODINGS, UPCA};
+use barcoders::sym::ean8::EAN8;
+use serde::{Deserialize, Serialize};
+
+use crate::encoders::barcode::{bar_rows, xdim};
+use crate::encoders::encoder::{Encode, Encoded, OutputParams};
+use crate::encoders::escpos::{self, BarcodeSystem};
+use crate::encoders::matrix::{Matrix, QuietZone};
+use crate::encoders::orientation::Rotation;
+use crate::encoders::text::{Label, TextOptions};
+
+/// Guard bars extend below the other bars by this many modules.
+const GUARD_EXTENSION: usize = 5;
+/// Space between the main symbol and the add-on, in modules.
+const ADD_ON_GAP: usize = 9;
+const ADD_ON_QUIET_ZONE: usize = 5;
+
+const LEFT_GUARD: &str = "101";
+const CENTER_GUARD: &str = "01010";
+const RIGHT_GUARD: &str = "101";
+const UPCE_RIGHT_GUARD: &str = "010101";
+const ADD_ON_GUARD: &str = "1011";
+const ADD_ON_SEPARATOR: &str = "01";
+
+/// Indices into `ENCODINGS` for the six UPC-E digits, by check digit, for number system 0.
+/// Number system 1 uses the opposite parities.
+const UPCE_PARITY: [[usize; 6]; 10] = [
+    [1, 1, 1, 0, 0, 0],
+    [1, 1, 0, 1, 0, 0],
+    [1, 1, 0, 0, 1, 0],
+    [1, 1, 0, 0, 0, 1],
+    [1, 0, 1, 1, 0, 0],
+    [1, 0, 0, 1, 1, 0],
+    [1, 0, 0, 0, 1, 1],
+    [1, 0, 1, 0, 1, 0],
+    [1, 0, 1, 0, 0, 1],
+    [1, 0, 0, 1, 0, 1],
+];
+
+/// Indices into `ENCODINGS` for the EAN-5 add-on digits, by its checksum.
+const EAN5_PARITY: [[usize; 5]; 10] = [
+    [1, 1, 0, 0, 0],
+    [1, 0, 1, 0, 0],
+    [1, 0, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+    [0, 1, 1, 0, 0],
+    [0, 0, 1, 1, 0],
+    [0, 0, 0, 1, 1],
+    [0, 1, 0, 1, 0],
+    [0, 1, 0, 0, 1],
+    [0, 0, 1, 0, 1],
+];
+
+/// Indices into `ENCODINGS` for the EAN-2 add-on digits, by its value modulo 4.
+const EAN2_PARITY: [[usize; 2]; 4] = [[0, 0], [0, 1], [1, 0], [1, 1]];
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum Symbology {
+    Ean13,
+    Ean8,
+    UpcA,
+    UpcE,
+}
+
+impl Symbology {
+    fn name(self) -> &'static str {
+        match self {
+            Symbology::Ean13 => "EAN-13",
+            Symbology::Ean8 => "EAN-8",
+            Symbology::UpcA => "UPC-A",
+            Symbology::UpcE => "UPC-E",
+        }
+    }
+
+    /// Centres of the human readable digits relative to the left guard, in modules.
+    /// The first digit of EAN-13, UPC-A and UPC-E and the last one of UPC-A and UPC-E
+    /// are printed in the quiet zones.
+    fn digit_centres(self) -> Vec<f32> {
+        let left = |range: std::ops::Range<usize>| range.map(|i| 6.5 + 7.0 * i as f32);
+        let right =
+            |start: f32, count: usize| (0..count).map(move |i| start + 3.5 + 7.0 * i as f32);
+        match self {
+            Symbology::Ean13 => std::iter::once(-4.0)
+                .chain(left(0..6))
+                .chain(right(50.0, 6))
+                .collect(),
+            Symbology::Ean8 => left(0..4).chain(right(36.0, 4)).collect(),
+            Symbology::UpcA => std::iter::once(-4.0)
+                .chain(left(1..6))
+                .chain(right(50.0, 5))
+                .chain(std::iter::once(99.0))
+                .collect(),
+            Symbology::UpcE => std::iter::once(-4.0)
+                .chain(left(0..6))
+                .chain(std::iter::once(55.0))
+                .collect(),
+        }
+    }
+
+    /// Light margins on the left and on the right of the symbol, in modules.
+    fn quiet_zones(self) -> (usize, usize) {
+        match self {
+            Symbology::Ean13 => (11, 7),
+            Symbology::Ean8 => (7, 7),
+            Symbology::UpcA => (9, 9),
+            Symbology::UpcE => (9, 7),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Module {
+    Light,
+    Dark,
+    /// Dark module which extends below the others.
+    Guard,
+    /// Dark module of the add-on, which starts lower than the main symbol.
+    AddOn,
+}
+
+/// Computes the GS1 mod-10 check digit of `digits`.
+pub fn check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| d as u32 * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    ((10 - sum % 10) % 10) as u8
+}
+
+fn parse_digits(data: &str, name: &str) -> Result<Vec<u8>, String> {
+    data.chars()
+        .map(|c| {
+            c.to_digit(10)
+                .map(|d| d as u8)
+                .ok_or_else(|| format!("{} can only contain digits, found '{}'", name, c))
+        })
+        .collect()
+}
+
+/// Appends the check digit when it is missing, or verifies it when it is present.
+pub fn with_check_digit(mut digits: Vec<u8>, length: usize, name: &str) -> Result<Vec<u8>, String> {
+    if digits.len() == length - 1 {
+        digits.push(check_digit(&digits));
+        return Ok(digits);
+    }
+    if digits.len() != length {
+        return Err(format!(
+            "{} needs {} digits, or {} with the check digit, got {}",
+            name,
+            length - 1,
+            length,
+            digits.len()
+        ));
+    }
+    let expected = check_digit(&digits[..length - 1]);
+    let actual = digits[length - 1];
+    if actual != expected {
+        return Err(format!(
+            "Invalid {} check digit {}, expected {}",
+            name, actual, expected
+        ));
+    }
+    Ok(digits)
+}
+
+/// Expands the number system and six digits of a UPC-E symbol to the eleven digits of UPC-A.
+fn upce_to_upca(digits: &[u8]) -> Vec<u8> {
+    let (ns, d) = (digits[0], &digits[1..7]);
+    let mut result = vec![ns, d[0], d[1]];
+    match d[5] {
+        0..=2 => result.extend(&[d[5], 0, 0, 0, 0, d[2], d[3], d[4]]),
+        3 => result.extend(&[d[2], 0, 0, 0, 0, 0, d[3], d[4]]),
+        4 => result.extend(&[d[2], d[3], 0, 0, 0, 0, 0, d[4]]),
+        _ => result.extend(&[d[2], d[3], d[4], 0, 0, 0, 0, d[5]]),
+    }
+    result
+}
+
+fn push_pattern(modules: &mut Vec<Module>, pattern: &str, dark: Module) {
+    modules.extend(
+        pattern
+            .chars()
+            .map(|c| if c == '1' { dark } else { Module::Light }),
+    );
+}
+
+fn push_digit(modules: &mut Vec<Module>, digit: u8, side: usize, dark: Module) {
+    modules.extend(ENCODINGS[side][digit as usize].iter().map(|&bit| {
+        if bit == 1 {
+            dark
+        } else {
+            Module::Light
+        }
+    }));
+}
+
+fn guard_extension(rows: usize) -> usize {
+    std::cmp::min(GUARD_EXTENSION, rows / 8)
+}
+
+pub struct Ean {
+    height: u32,
+    payload: String,
+    symbology: Symbology,
+    add_on: Option<String>,
+    text: TextOptions,
+}
+
+impl Ean {
+    pub fn new(
+        payload: String,
+        height: u32,
+        symbology: Symbology,
+        add_on: Option<String>,
+        text: TextOptions,
+    ) -> Self {
+        Ean {
+            payload,
+            height,
+            symbology,
+            add_on,
+            text,
+        }
+    }
+
+    /// Returns the payload digits including the check digit.
+    fn get_digits(&self) -> Result<Vec<u8>, String> {
+        let name = self.symbology.name();
+        let digits = parse_digits(&self.payload, name)?;
+        match self.symbology {
+            Symbology::Ean13 => with_check_digit(digits, 13, name),
+            Symbology::Ean8 => with_check_digit(digits, 8, name),
+            Symbology::UpcA => with_check_digit(digits, 12, name),
+            Symbology::UpcE => {
+                // Six digits imply number system 0.
+                let mut digits = digits;
+                if digits.len() == 6 {
+                    digits.insert(0, 0);
+                }
+                if digits.len() != 7 && digits.len() != 8 {
+                    return Err(format!(
+                        "UPC-E needs 6 or 7 digits, or 8 with the check digit, got {}",
+                        digits.len()
+                    ));
+                }
+                if digits[0] > 1 {
+                    return Err(format!(
+                        "UPC-E number system must be 0 or 1, got {}",
+                        digits[0]
+                    ));
+                }
+                let expected = check_digit(&upce_to_upca(&digits));
+                match digits.get(7) {
+                    Some(&actual) if actual != expected => Err(format!(
+                        "Invalid UPC-E check digit {}, expected {}",
+                        actual, expected
+                    )),
+                    Some(_) => Ok(digits),
+                    None => {
+                        digits.push(expected);
+                        Ok(digits)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Bars of the main symbol without quiet zones.
+    fn get_symbol(&self) -> Result<Vec<Module>, String> {
+        let digits = self.get_digits()?;
+        let text: String = digits
+            .iter()
+            .map(|&d| std::char::from_digit(d as u32, 10).unwrap())
+            .collect();
+        let bits = match self.symbology {
+            Symbology::Ean13 => EAN13::new(&text[..12]).map(|code| code.encode()),
+            Symbology::Ean8 => EAN8::new(&text[..7]).map(|code| code.encode()),
+            Symbology::UpcA => UPCA::new(format!("0{}", &text[..11])).map(|code| code.encode()),
+            Symbology::UpcE => {
+                let mut modules = Vec::new();
+                push_pattern(&mut modules, LEFT_GUARD, Module::Guard);
+                for (i, &digit) in digits[1..7].iter().enumerate() {
+                    let side = UPCE_PARITY[digits[7] as usize][i];
+                    let side = if digits[0] == 0 { side } else { 1 - side };
+                    push_digit(&mut modules, digit, side, Module::Dark);
+                }
+                push_pattern(&mut modules, UPCE_RIGHT_GUARD, Module::Guard);
+                return Ok(modules);
+            }
+        }
+        .map_err(|e| e.to_string())?;
+
+        let digits_per_half = match self.symbology {
+            Symbology::Ean8 => 4,
+            _ => 6,
+        };
+        let half = LEFT_GUARD.len() + 7 * digits_per_half;
+        let center = half..half + CENTER_GUARD.len();
+        let right = bits.len() - RIGHT_GUARD.len();
+        // UPC-A also extends the bars of its first and last digit.
+        let outer = if self.symbology == Symbology::UpcA {
+            7
+        } else {
+            0
+        };
+        Ok(bits
+            .iter()
+            .enumerate()
+            .map(|(i, &bit)| match bit {
+                0 => Module::Light,
+                _ if i < LEFT_GUARD.len() + outer => Module::Guard,
+                _ if center.contains(&i) => Module::Guard,
+                _ if i >= right - outer => Module::Guard,
+                _ => Module::Dark,
+            })
+            .collect())
+    }
+
+    fn get_add_on(&self) -> Result<Vec<Module>, String> {
+        let digits = match &self.add_on {
+            Some(add_on) => parse_digits(add_on, "Add-on")?,
+            None => return Ok(Vec::new()),
+        };
+        let parity: &[usize] = match digits.len() {
+            2 => &EAN2_PARITY[((digits[0] * 10 + digits[1]) % 4) as usize],
+            5 => {
+                let checksum = digits
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &d)| d as u32 * if i % 2 == 0 { 3 } else { 9 })
+                    .sum::<u32>()
+                    % 10;
+                &EAN5_PARITY[checksum as usize]
+            }
+            n => return Err(format!("Add-on needs 2 or 5 digits, got {}", n)),
+        };
+        let mut modules = Vec::new();
+        push_pattern(&mut modules, ADD_ON_GUARD, Module::AddOn);
+        for (i, (&digit, &side)) in digits.iter().zip(parity.iter()).enumerate() {
+            if i > 0 {
+                push_pattern(&mut modules, ADD_ON_SEPARATOR, Module::AddOn);
+            }
+            push_digit(&mut modules, digit, side, Module::AddOn);
+        }
+        Ok(modules)
+    }
+
+    /// All modules of the symbol from left to right, including the add-on.
+    fn get_modules(&self) -> Result<Vec<Module>, String> {
+        let add_on = self.get_add_on()?;
+        let mut modules = self.get_symbol()?;
+        if !add_on.is_empty() {
+            modules.extend(vec![Module::Light; ADD_ON_GAP]);
+            modules.extend(add_on);
+        }
+        Ok(modules)
+    }
+}
+
+impl Encode for Ean {
+    fn symbol(&self) -> Result<Matrix, String> {
+        let modules = self.get_modules()?;
+        let rows = bar_rows(self.height);
+        let extension = guard_extension(rows);
+        let mut matrix = Matrix::new(modules.len(), rows);
+        for (x, module) in modules.iter().enumerate() {
+            let dark_rows = match module {
+                Module::Light => continue,
+                Module::Dark => 0..rows - extension,
+                Module::Guard => 0..rows,
+                Module::AddOn => extension..rows,
+            };
+            for y in dark_rows {
+                matrix.set(x, y, true);
+            }
+        }
+        Ok(matrix)
+    }
+
+    fn quiet_zone(&self) -> QuietZone {
+        let (left, right) = self.symbology.quiet_zones();
+        match self.add_on {
+            Some(_) => QuietZone::horizontal(left, ADD_ON_QUIET_ZONE),
+            None => QuietZone::horizontal(left, right),
+        }
+    }
+
+    fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    /// Digits under the shortened bars, split by the guards, and the add-on below its bars.
+    fn labels(&self, code: &Encoded) -> Result<Vec<Label>, String> {
+        let digits = self.get_digits()?;
+        let symbol_len = self.get_symbol()?.len();
+        let symbol = &code.symbol;
+        let rows = symbol.height();
+        let (module_width, module_height) = code.module_size()?;
+        let quiet_zone = code.quiet_zone;
+        let left = quiet_zone.left as f32;
+        let top = quiet_zone.top;
+        let digits_top =
+            (top + rows - guard_extension(rows)) as u32 * module_height + module_height;
+
+        let mut labels: Vec<Label> = digits
+            .iter()
+            .zip(self.symbology.digit_centres())
+            .map(|(&digit, centre)| Label {
+                text: digit.to_string(),
+                x: (left + centre) * module_width as f32,
+                y: digits_top,
+            })
+            .collect();
+        if let Some(add_on) = &self.add_on {
+            let add_on_len = symbol.width() - symbol_len - ADD_ON_GAP;
+            let centre = left + (symbol_len + ADD_ON_GAP) as f32 + add_on_len as f32 / 2.0;
+            labels.push(Label {
+                text: add_on.clone(),
+                x: centre * module_width as f32,
+                y: (top + rows) as u32 * module_height + module_height,
+            });
+        }
+        Ok(labels)
+    }
+
+    fn get_params(&self) -> OutputParams {
+        OutputParams {
+            append_text: self.text.show,
+            font_size: self.text.font_size(xdim(self.height)),
+            height: self.height,
+        }
+    }
+
+    /// `GS k` of EAN-13, EAN-8 and UPC-A with the check digit, printed upright only.
+    /// Printers differ in how they expand UPC-E and cannot add an add-on, so these are
+    /// sent as an image.
+    fn escpos(&self, code: &Encoded) -> Result<Option<Vec<u8>>, String> {
+        let system = match self.symbology {
+            Symbology::Ean13 => BarcodeSystem::Ean13,
+            Symbology::Ean8 => BarcodeSystem::Ean8,
+            Symbology::UpcA => BarcodeSystem::UpcA,
+            Symbology::UpcE => return Ok(None),
+        };
+        let (module_width, module_height) = code.module_size()?;
+        if self.add_on.is_some()
+            || code.options.orientation.rotation != Rotation::Zero
+            || !escpos::BAR_MODULES.contains(&module_width)
+        {
+            return Ok(None);
+        }
+        let data: Vec<u8> = self.get_digits()?.iter().map(|d| b'0' + d).collect();
+        Ok(Some(escpos::barcode(
+            system,
+            &data,
+            module_width,
+            code.symbol.height() as u32 * module_height,
+            self.text.show,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Luma;
+
+    use super::*;
+    use crate::encoders::encoder::RenderOptions;
+
+    fn ean(payload: &str, symbology: Symbology, add_on: Option<&str>) -> Ean {
+        Ean::new(
+            payload.to_owned(),
+            100,
+            symbology,
+            add_on.map(|a| a.to_owned()),
+            TextOptions::default(),
+        )
+    }
+
+    fn bits(modules: &[Module]) -> String {
+        modules
+            .iter()
+            .map(|&m| if m == Module::Light { '0' } else { '1' })
+            .collect()
+    }
+
+    #[test]
+    fn test_check_digit() {
+        assert_eq!(1, check_digit(&[4, 0, 0, 6, 3, 8, 1, 3, 3, 3, 9, 3]));
+        assert_eq!(4, check_digit(&[9, 6, 3, 8, 5, 0, 7]));
+        assert_eq!(2, check_digit(&[0, 3, 6, 0, 0, 0, 2, 9, 1, 4, 5]));
+    }
+
+    #[test]
+    fn test_ean13() {
+        let computed = ean("400638133393", Symbology::Ean13, None);
+        let given = ean("4006381333931", Symbology::Ean13, None);
+        let symbol = computed.get_symbol().unwrap();
+        assert_eq!(95, symbol.len());
+        assert_eq!(symbol, given.get_symbol().unwrap());
+        let guards = symbol.iter().filter(|&&m| m == Module::Guard).count();
+        assert_eq!(6, guards);
+    }
+
+    #[test]
+    fn test_wrong_check_digit() {
+        let code = ean("4006381333932", Symbology::Ean13, None);
+        assert_eq!(
+            Err("Invalid EAN-13 check digit 2, expected 1".to_owned()),
+            code.get_symbol()
+        );
+        assert!(ean("96385075", Symbology::Ean8, None).get_symbol().is_err());
+        assert!(ean("40063813339", Symbology::Ean13, None)
+            .get_symbol()
+            .is_err());
+        assert!(ean("4OO6381333931", Symbology::Ean13, None)
+            .get_symbol()
+            .is_err());
+    }
+
+    #[test]
+    fn test_ean8() {
+        let symbol = ean("9638507", Symbology::Ean8, None).get_symbol().unwrap();
+        assert_eq!(67, symbol.len());
+        assert_eq!(
+            symbol,
+            ean("96385074", Symbology::Ean8, None).get_symbol().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_upca() {
+        let symbol = ean("03600029145", Symbology::UpcA, None)
+            .get_symbol()
+            .unwrap();
+        assert_eq!(95, symbol.len());
+        // Outer digits are as long as the guards.
+        assert!(symbol[3..10].contains(&Module::Guard));
+        assert!(symbol[85..92].contains(&Module::Guard));
+        assert!(!symbol[10..45].contains(&Module::Guard));
+        assert!(ean("036000291453", Symbology::UpcA, None)
+            .get_symbol()
+            .is_err());
+    }
+
+    #[test]
+    fn test_upce() {
+        let code = ean("0425261", Symbology::UpcE, None);
+        assert_eq!(vec![0, 4, 2, 5, 2, 6, 1, 4], code.get_digits().unwrap());
+        let symbol = code.get_symbol().unwrap();
+        assert_eq!(51, symbol.len());
+        // Check digit 4 selects the parity pattern EOEEOO.
+        assert_eq!(
+            "101\
+             0011101\
+             0010011\
+             0111001\
+             0011011\
+             0101111\
+             0011001\
+             010101",
+            bits(&symbol)
+        );
+        assert_eq!(
+            symbol,
+            ean("425261", Symbology::UpcE, None).get_symbol().unwrap()
+        );
+        assert!(ean("04252615", Symbology::UpcE, None).get_symbol().is_err());
+        assert!(ean("2425261", Symbology::UpcE, None).get_symbol().is_err());
+    }
+
+    #[test]
+    fn test_add_on() {
+        let ean5 = ean("400638133393", Symbology::Ean13, Some("52495"));
+        let add_on = ean5.get_add_on().unwrap();
+        assert_eq!(47, add_on.len());
+        // Checksum 1 selects the parity pattern GLGLL.
+        assert_eq!(
+            "1011\
+             0111001\
+             01\
+             0010011\
+             01\
+             0011101\
+             01\
+             0001011\
+             01\
+             0110001",
+            bits(&add_on)
+        );
+
+        let ean2 = ean("400638133393", Symbology::Ean13, Some("12"));
+        assert_eq!(20, ean2.get_add_on().unwrap().len());
+        let matrix = Encoded::new(&ean2, RenderOptions::default())
+            .unwrap()
+            .matrix;
+        assert_eq!(
+            11 + 95 + ADD_ON_GAP + 20 + ADD_ON_QUIET_ZONE,
+            matrix.width()
+        );
+
+        assert!(ean("400638133393", Symbology::Ean13, Some("123"))
+            .get_add_on()
+            .is_err());
+    }
+
+    #[test]
+    fn test_encode() {
+        let code = ean("400638133393", Symbology::Ean13, None);
+        let image = Encoded::new(&code, RenderOptions::default())
+            .and_then(|code| code.image())
+            .unwrap()
+            .to_luma();
+        assert_eq!((11 + 95 + 7, 100), image.dimensions());
+        // Guard bars reach the bottom, data bars stop above it.
+        assert_eq!(Luma([0]), *image.get_pixel(11, 99));
+        assert_eq!(Luma([255]), *image.get_pixel(11 + 6, 99));
+        assert_eq!(Luma([0]), *image.get_pixel(11 + 6, 50));
+    }
+
+    #[test]
+    fn test_labels() {
+        let code = ean("400638133393", Symbology::Ean13, Some("12"));
+        let encoded = Encoded::new(&code, RenderOptions::default()).unwrap();
+        let labels = code.labels(&encoded).unwrap();
+        let text: Vec<&str> = labels.iter().map(|label| &label.text[..]).collect();
+        assert_eq!(
+            vec!["4", "0", "0", "6", "3", "8", "1", "3", "3", "3", "9", "3", "1", "12"],
+            text
+        );
+        // The first digit sits in the quiet zone, the second under the first character.
+        assert_eq!(7.0, labels[0].x);
+        assert_eq!(11.0 + 6.5, labels[1].x);
+        assert_eq!(11.0 + 50.0 + 3.5, labels[7].x);
+        // Digits start below the shortened bars, the add-on text below the add-on.
+        assert_eq!(100 - 5 + 1, labels[0].y);
+        assert_eq!(101, labels[13].y);
+
+        let upce = ean("0425261", Symbology::UpcE, None);
+        let encoded = Encoded::new(&upce, RenderOptions::default()).unwrap();
+        let labels = upce.labels(&encoded).unwrap();
+        assert_eq!(8, labels.len());
+        assert_eq!(9.0 + 55.0, labels[7].x);
+    }
+}
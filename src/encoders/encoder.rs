@@ -1,25 +1,192 @@
-use image::{DynamicImage, ImageOutputFormat};
+use image::DynamicImage;
+
+use crate::encoders::color::{self, Color};
+use crate::encoders::matrix::{Matrix, QuietZone};
+use crate::encoders::orientation::Orientation;
+use crate::encoders::shape::{self, Point, Shape};
+use crate::encoders::sizing::{self, Sizing};
+use crate::encoders::text::{self, Label};
 
 pub struct OutputParams {
-    pub format: ImageOutputFormat,
     pub append_text: bool,
+    pub font_size: u32,
+    pub height: u32,
+}
+
+/// Options of a request which apply to every symbology.
+#[derive(Clone, Copy, Debug)]
+pub struct RenderOptions {
+    /// Replaces the quiet zone of the symbology.
+    pub quiet_zone: Option<QuietZone>,
+    pub foreground: Color,
+    pub background: Color,
+    pub orientation: Orientation,
+    pub sizing: Sizing,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            quiet_zone: None,
+            foreground: color::BLACK,
+            background: color::WHITE,
+            orientation: Orientation::default(),
+            sizing: Sizing::default(),
+        }
+    }
 }
 
 pub trait Encode {
-    fn encode(&self) -> Result<DynamicImage, String>;
+    /// Modules of the symbol without its quiet zone.
+    fn symbol(&self) -> Result<Matrix, String>;
+
+    /// Quiet zone the symbology needs around the symbol.
+    fn quiet_zone(&self) -> QuietZone;
 
     fn payload(&self) -> &str;
 
     fn get_params(&self) -> OutputParams;
 
-    fn output(&self) -> Result<Vec<u8>, String> {
+    /// Linear symbols keep the bar height of `height` when the module width is set.
+    fn is_linear(&self) -> bool {
+        false
+    }
+
+    /// Outlines of the dark modules in module units, for symbologies drawn other than as squares.
+    fn shapes(&self, _symbol: &Matrix, _orientation: Orientation) -> Option<Vec<Shape>> {
+        None
+    }
+
+    /// Human readable interpretation of the symbol.
+    fn text(&self) -> Result<String, String> {
+        Ok(self.payload().to_owned())
+    }
+
+    /// Human readable text for the raster image, centred below the symbol by default.
+    fn labels(&self, code: &Encoded) -> Result<Vec<Label>, String> {
         let params = self.get_params();
-        let img = self.encode()?;
+        let (module_width, module_height) = code.module_size()?;
+        Ok(vec![Label {
+            text: self.text()?,
+            x: (code.quiet_zone.left as f32 + code.symbol.width() as f32 / 2.0)
+                * module_width as f32,
+            y: (code.quiet_zone.top + code.symbol.height()) as u32 * module_height
+                + params.font_size / 5,
+        }])
+    }
+
+    /// Draws on the coloured raster image, before it is turned to its orientation.
+    fn decorate(&self, image: DynamicImage, _code: &Encoded) -> Result<DynamicImage, String> {
+        Ok(image)
+    }
+
+    /// Native ZPL command drawing the symbol at the field origin, when the printer has one
+    /// for the symbology which can draw it as requested.
+    fn zpl(&self, _code: &Encoded) -> Result<Option<String>, String> {
+        Ok(None)
+    }
 
-        let mut output_image_bytes: Vec<u8> = Vec::new();
-        match img.write_to(&mut output_image_bytes, params.format) {
-            Ok(_) => Ok(output_image_bytes),
-            Err(err) => Err(err.to_string()),
+    /// Native ESC/POS commands printing the symbol, when the printer has one for the symbology
+    /// which can print it as requested.
+    fn escpos(&self, _code: &Encoded) -> Result<Option<Vec<u8>>, String> {
+        Ok(None)
+    }
+}
+
+/// Symbol of a request, encoded once and passed to the renderer of the output format.
+pub struct Encoded<'a> {
+    pub encoder: &'a dyn Encode,
+    /// Modules without the quiet zone.
+    pub symbol: Matrix,
+    pub quiet_zone: QuietZone,
+    /// Modules including the quiet zone.
+    pub matrix: Matrix,
+    pub options: RenderOptions,
+}
+
+impl<'a> Encoded<'a> {
+    pub fn new(encoder: &'a dyn Encode, options: RenderOptions) -> Result<Self, String> {
+        let symbol = encoder.symbol()?;
+        let quiet_zone = options.quiet_zone.unwrap_or_else(|| encoder.quiet_zone());
+        Ok(Encoded {
+            encoder,
+            matrix: symbol.with_quiet_zone(quiet_zone),
+            symbol,
+            quiet_zone,
+            options,
+        })
+    }
+
+    /// The same symbol rendered with other options, which keep the quiet zone.
+    pub fn with_options(&self, options: RenderOptions) -> Self {
+        Encoded {
+            encoder: self.encoder,
+            symbol: self.symbol.clone(),
+            quiet_zone: self.quiet_zone,
+            matrix: self.matrix.clone(),
+            options,
+        }
+    }
+
+    /// Width and height of a module in pixels, from the sizing options or else from `height`.
+    pub fn module_size(&self) -> Result<(u32, u32), String> {
+        let default = self.matrix.module_size(self.encoder.get_params().height);
+        let width = self
+            .options
+            .sizing
+            .module_width(self.matrix.width(), default)?;
+        let size = if self.encoder.is_linear() {
+            (width, default)
+        } else {
+            (width, width)
+        };
+        sizing::check_image_size(self.matrix.width(), self.matrix.height(), size.0, size.1)?;
+        Ok(size)
+    }
+
+    /// Outlines of the symbol turned to `orientation`, in module units without the quiet zone.
+    pub fn shapes(&self, orientation: Orientation) -> Option<Vec<Shape>> {
+        self.encoder
+            .shapes(&orientation.apply_matrix(&self.symbol), orientation)
+    }
+
+    /// Raster image of the modules in black and white.
+    pub fn image(&self) -> Result<DynamicImage, String> {
+        let (module_width, module_height) = self.module_size()?;
+        let shapes = match self.shapes(Orientation::default()) {
+            Some(shapes) => shapes,
+            None => return Ok(self.matrix.to_image(module_width, module_height)),
+        };
+        let pixels: Vec<Shape> = shapes
+            .iter()
+            .map(|shape| {
+                shape.map(|p| Point {
+                    x: (p.x + self.quiet_zone.left as f64) * module_width as f64,
+                    y: (p.y + self.quiet_zone.top as f64) * module_height as f64,
+                })
+            })
+            .collect();
+        Ok(shape::rasterize(
+            &pixels,
+            self.matrix.width() as u32 * module_width,
+            self.matrix.height() as u32 * module_height,
+        ))
+    }
+
+    /// Raster image with the human readable text, when the symbology has one.
+    pub fn render(&self) -> Result<DynamicImage, String> {
+        let params = self.encoder.get_params();
+        let image = self.image()?;
+        if !params.append_text {
+            return Ok(image);
         }
+        let labels = self.encoder.labels(self)?;
+        Ok(text::draw(&image, &labels, params.font_size))
+    }
+
+    /// Raster image for label and receipt printers, which print the dark pixels in black.
+    pub fn print_image(&self) -> Result<DynamicImage, String> {
+        let image = self.encoder.decorate(self.render()?, self)?;
+        Ok(self.options.orientation.apply_image(image))
     }
 }
@@ -0,0 +1,135 @@
+use std::fmt::Write;
+
+use crate::encoders::color::Color;
+use crate::encoders::encoder::Encoded;
+use crate::encoders::print::{self, Page, PrintSize};
+use crate::encoders::shape::Segment;
+
+/// Encapsulated PostScript drawing of the symbol at the physical `size`.
+pub fn output(code: &Encoded, size: &PrintSize) -> Result<Vec<u8>, String> {
+    let page = print::page(code, size)?;
+    Ok(render(
+        &page,
+        code.options.foreground,
+        code.options.background,
+    ))
+}
+
+/// Writes an Encapsulated PostScript file with the rectangles of `page` filled in the foreground colour.
+/// The integer bounding box is rounded outwards, the exact one is given as `HiResBoundingBox`.
+/// PostScript has no transparency, a background which is not fully transparent is painted opaque.
+pub fn render(page: &Page, foreground: Color, background: Color) -> Vec<u8> {
+    let mut eps = String::from("%!PS-Adobe-3.0 EPSF-3.0\n");
+    writeln!(
+        eps,
+        "%%BoundingBox: 0 0 {} {}",
+        page.width.ceil(),
+        page.height.ceil()
+    )
+    .unwrap();
+    writeln!(
+        eps,
+        "%%HiResBoundingBox: 0 0 {:.3} {:.3}",
+        page.width, page.height
+    )
+    .unwrap();
+    eps.push_str("%%Creator: image-codes\n%%Pages: 1\n%%EndComments\n");
+    eps.push_str("gsave\n");
+    if background.a > 0 {
+        let [r, g, b] = background.components();
+        writeln!(
+            eps,
+            "{:.3} {:.3} {:.3} setrgbcolor\n0 0 {:.3} {:.3} rectfill",
+            r, g, b, page.width, page.height
+        )
+        .unwrap();
+    }
+    let [r, g, b] = foreground.components();
+    writeln!(eps, "{:.3} {:.3} {:.3} setrgbcolor", r, g, b).unwrap();
+    for rect in &page.rects {
+        writeln!(
+            eps,
+            "{:.3} {:.3} {:.3} {:.3} rectfill",
+            rect.x, rect.y, rect.width, rect.height
+        )
+        .unwrap();
+    }
+    if !page.shapes.is_empty() {
+        eps.push_str("newpath\n");
+        for subpath in page.shapes.iter().flat_map(|shape| &shape.subpaths) {
+            writeln!(eps, "{:.3} {:.3} moveto", subpath.start.x, subpath.start.y).unwrap();
+            for segment in &subpath.segments {
+                match segment {
+                    Segment::Line(p) => writeln!(eps, "{:.3} {:.3} lineto", p.x, p.y),
+                    Segment::Curve(c1, c2, p) => writeln!(
+                        eps,
+                        "{:.3} {:.3} {:.3} {:.3} {:.3} {:.3} curveto",
+                        c1.x, c1.y, c2.x, c2.y, p.x, p.y
+                    ),
+                }
+                .unwrap();
+            }
+            eps.push_str("closepath\n");
+        }
+        eps.push_str("eofill\n");
+    }
+    eps.push_str("grestore\nshowpage\n%%EOF\n");
+    eps.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoders::color::{BLACK, WHITE};
+    use crate::encoders::print::PointRect;
+    use crate::encoders::shape::Shape;
+
+    #[test]
+    fn test_render() {
+        let page = Page {
+            width: 70.5,
+            height: 20.0,
+            rects: vec![PointRect {
+                x: 10.0,
+                y: 0.0,
+                width: 1.5,
+                height: 20.0,
+            }],
+            shapes: Vec::new(),
+        };
+        assert_eq!(
+            "%!PS-Adobe-3.0 EPSF-3.0\n\
+             %%BoundingBox: 0 0 71 20\n\
+             %%HiResBoundingBox: 0 0 70.500 20.000\n\
+             %%Creator: image-codes\n\
+             %%Pages: 1\n\
+             %%EndComments\n\
+             gsave\n\
+             0.000 0.000 0.000 setrgbcolor\n\
+             10.000 0.000 1.500 20.000 rectfill\n\
+             grestore\n\
+             showpage\n\
+             %%EOF\n",
+            String::from_utf8(render(&page, BLACK, Color::parse("fff0").unwrap())).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_render_shapes() {
+        let page = Page {
+            width: 10.0,
+            height: 10.0,
+            rects: Vec::new(),
+            shapes: vec![Shape::ring(
+                Shape::rect(0.0, 0.0, 3.0, 3.0),
+                Shape::rect(1.0, 1.0, 1.0, 1.0),
+            )],
+        };
+        let eps = String::from_utf8(render(&page, BLACK, WHITE)).unwrap();
+        assert!(eps.contains(
+            "newpath\n0.000 0.000 moveto\n3.000 0.000 lineto\n3.000 3.000 lineto\n\
+             0.000 3.000 lineto\n0.000 0.000 lineto\nclosepath\n1.000 1.000 moveto\n"
+        ));
+        assert!(eps.contains("closepath\neofill\ngrestore\n"));
+    }
+}
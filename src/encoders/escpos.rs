@@ -0,0 +1,238 @@
+use image::{DynamicImage, GenericImageView};
+
+use crate::encoders::encoder::Encoded;
+use crate::encoders::qrcode::EccLevel;
+use crate::encoders::sizing::ModuleWidth;
+
+const ESC: u8 = 0x1b;
+const GS: u8 = 0x1d;
+
+/// Module widths `GS w` accepts, in dots.
+pub const BAR_MODULES: std::ops::RangeInclusive<u32> = 2..=6;
+/// Largest module of `GS ( k`, in dots.
+pub const MAX_QR_MODULE: u32 = 16;
+/// Highest bars `GS h` prints, in dots. Taller bars are cut to it.
+const MAX_BAR_HEIGHT: u32 = 255;
+/// Rows per `GS v 0` command, printers buffer a band at a time.
+const RASTER_BAND: u32 = 255;
+/// Lines fed after the code, so that it clears the tear bar.
+const FEED_LINES: u8 = 3;
+
+/// Receipt paper, with its printable width at 203 dpi.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Paper {
+    Mm58,
+    Mm80,
+}
+
+impl Paper {
+    pub fn from_mm(mm: u16) -> Result<Self, String> {
+        match mm {
+            58 => Ok(Paper::Mm58),
+            80 => Ok(Paper::Mm80),
+            _ => Err(format!("Paper must be 58 or 80 mm wide, got {}", mm)),
+        }
+    }
+
+    pub fn mm(self) -> u16 {
+        match self {
+            Paper::Mm58 => 58,
+            Paper::Mm80 => 80,
+        }
+    }
+
+    /// Printable dots per line.
+    pub fn dots(self) -> u32 {
+        match self {
+            Paper::Mm58 => 384,
+            Paper::Mm80 => 576,
+        }
+    }
+}
+
+/// Barcode systems of `GS k` function B.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BarcodeSystem {
+    UpcA = 65,
+    Ean13 = 67,
+    Ean8 = 68,
+    Code128 = 73,
+}
+
+/// ESC/POS job for receipt printers, one pixel of the raster image is one dot. Without
+/// a requested size, a symbol wider than the paper is narrowed to fit it. Symbols without
+/// a native command are sent as a raster bit image.
+pub fn output(code: &Encoded, paper: Paper) -> Result<Vec<u8>, String> {
+    let mut image = code.print_image()?;
+    let narrowed;
+    let mut code = code;
+    if image.width() > paper.dots() && code.options.sizing.module_width.is_none() {
+        let mut options = code.options;
+        options.sizing.module_width = Some(ModuleWidth::Image(paper.dots()));
+        narrowed = code.with_options(options);
+        code = &narrowed;
+        image = code.print_image()?;
+    }
+    if image.width() > paper.dots() {
+        return Err(format!(
+            "The code is {} dots wide, {} mm paper prints {}",
+            image.width(),
+            paper.mm(),
+            paper.dots()
+        ));
+    }
+    let native = if code.options.orientation.mirror {
+        None
+    } else {
+        code.encoder.escpos(code)?
+    };
+    let native = native.unwrap_or_else(|| raster(&image));
+    Ok(job(&native))
+}
+
+/// Resets the printer, centres the code and feeds the paper after it.
+pub fn job(code: &[u8]) -> Vec<u8> {
+    let mut job = vec![ESC, b'@', ESC, b'a', 1];
+    job.extend_from_slice(code);
+    job.extend_from_slice(&[ESC, b'd', FEED_LINES]);
+    job
+}
+
+/// `GS k` with the bar module width and height in dots and the human readable text below.
+pub fn barcode(
+    system: BarcodeSystem,
+    data: &[u8],
+    module: u32,
+    height: u32,
+    text: bool,
+) -> Vec<u8> {
+    let mut code = vec![
+        GS,
+        b'w',
+        module as u8,
+        GS,
+        b'h',
+        height.clamp(1, MAX_BAR_HEIGHT) as u8,
+        GS,
+        b'H',
+        if text { 2 } else { 0 },
+        GS,
+        b'k',
+        system as u8,
+        data.len() as u8,
+    ];
+    code.extend_from_slice(data);
+    code
+}
+
+/// `GS ( k` function of the QR Code symbol, cn 49.
+fn qr_function(function: u8, parameters: &[u8]) -> Vec<u8> {
+    let length = parameters.len() + 2;
+    let mut command = vec![
+        GS,
+        b'(',
+        b'k',
+        (length % 256) as u8,
+        (length / 256) as u8,
+        49,
+        function,
+    ];
+    command.extend_from_slice(parameters);
+    command
+}
+
+/// Model 2 QR Code of `module` dots, the printer picks the version and the mask.
+pub fn qr(data: &[u8], module: u32, ecc_level: EccLevel) -> Vec<u8> {
+    let ecc = match ecc_level {
+        EccLevel::L => 48,
+        EccLevel::M => 49,
+        EccLevel::Q => 50,
+        EccLevel::H => 51,
+    };
+    let mut store = vec![48];
+    store.extend_from_slice(data);
+    [
+        qr_function(65, &[50, 0]),
+        qr_function(67, &[module as u8]),
+        qr_function(69, &[ecc]),
+        qr_function(80, &store),
+        qr_function(81, &[48]),
+    ]
+    .concat()
+}
+
+/// The dark pixels as `GS v 0` raster bit images, in bands of rows.
+pub fn raster(image: &DynamicImage) -> Vec<u8> {
+    let luma = image.to_luma();
+    let (width, height) = luma.dimensions();
+    let row_bytes = width.div_ceil(8);
+    let mut code = Vec::new();
+    for top in (0..height).step_by(RASTER_BAND as usize) {
+        let rows = RASTER_BAND.min(height - top);
+        code.extend_from_slice(&[
+            GS,
+            b'v',
+            b'0',
+            0,
+            (row_bytes % 256) as u8,
+            (row_bytes / 256) as u8,
+            (rows % 256) as u8,
+            (rows / 256) as u8,
+        ]);
+        for y in top..top + rows {
+            let mut row = vec![0u8; row_bytes as usize];
+            for x in 0..width {
+                if luma.get_pixel(x, y)[0] < 128 {
+                    row[x as usize / 8] |= 0x80 >> (x % 8);
+                }
+            }
+            code.extend_from_slice(&row);
+        }
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma};
+
+    use super::*;
+
+    #[test]
+    fn test_barcode() {
+        assert_eq!(
+            vec![GS, b'w', 2, GS, b'h', 255, GS, b'H', 2, GS, b'k', 67, 3, b'1', b'2', b'3'],
+            barcode(BarcodeSystem::Ean13, b"123", 2, 300, true)
+        );
+    }
+
+    #[test]
+    fn test_qr() {
+        let code = qr(b"hi", 4, EccLevel::H);
+        assert_eq!(&[GS, b'(', b'k', 3, 0, 49, 67, 4], &code[9..17]);
+        assert_eq!(&[GS, b'(', b'k', 3, 0, 49, 69, 51], &code[17..25]);
+        assert_eq!(
+            &[GS, b'(', b'k', 5, 0, 49, 80, 48, b'h', b'i'],
+            &code[25..35]
+        );
+        assert_eq!(&[GS, b'(', b'k', 3, 0, 49, 81, 48], &code[35..]);
+    }
+
+    #[test]
+    fn test_raster() {
+        let image = GrayImage::from_fn(10, 300, |x, _| Luma([if x == 9 { 0 } else { 255 }]));
+        let code = raster(&DynamicImage::ImageLuma8(image));
+        assert_eq!(&[GS, b'v', b'0', 0, 2, 0, 255, 0, 0, 0x40], &code[..10]);
+        // The second band has the remaining 45 rows.
+        let second = 8 + 2 * 255;
+        assert_eq!(&[GS, b'v', b'0', 0, 2, 0, 45, 0], &code[second..second + 8]);
+        assert_eq!(second + 8 + 2 * 45, code.len());
+    }
+
+    #[test]
+    fn test_paper() {
+        assert_eq!(Ok(Paper::Mm58), Paper::from_mm(58));
+        assert_eq!(576, Paper::Mm80.dots());
+        assert!(Paper::from_mm(110).is_err());
+    }
+}
@@ -0,0 +1,345 @@
+use crate::encoders::ean::check_digit;
+
+/// GS1 character set 82, allowed in alphanumeric fields.
+const CSET82: &str =
+    "!\"%&'()*+,-./0123456789:;<=>?ABCDEFGHIJKLMNOPQRSTUVWXYZ_abcdefghijklmnopqrstuvwxyz";
+
+const DIGITS: &str = "0123456789";
+
+/// Two-digit prefixes of the AIs with a predefined length, which need no separator after them.
+const PREDEFINED_LENGTH: [&str; 22] = [
+    "00", "01", "02", "03", "04", "11", "12", "13", "14", "15", "16", "17", "18", "19", "20", "31",
+    "32", "33", "34", "35", "36", "41",
+];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Rule {
+    None,
+    /// The first component ends with a GS1 mod-10 check digit.
+    CheckDigit,
+    /// The first component is a YYMMDD date.
+    Date,
+}
+
+/// Ranges of Application Identifiers of the GS1 General Specifications with their format,
+/// e.g. `N13,X..17` is 13 digits followed by up to 17 alphanumeric characters.
+const AIS: [(&str, &str, &str, Rule); 95] = [
+    ("00", "00", "N18", Rule::CheckDigit),
+    ("01", "03", "N14", Rule::CheckDigit),
+    ("10", "10", "X..20", Rule::None),
+    ("11", "13", "N6", Rule::Date),
+    ("15", "17", "N6", Rule::Date),
+    ("20", "20", "N2", Rule::None),
+    ("21", "22", "X..20", Rule::None),
+    ("235", "235", "X..28", Rule::None),
+    ("240", "241", "X..30", Rule::None),
+    ("242", "242", "N..6", Rule::None),
+    ("243", "243", "X..20", Rule::None),
+    ("250", "251", "X..30", Rule::None),
+    ("253", "253", "N13,X..17", Rule::CheckDigit),
+    ("254", "254", "X..20", Rule::None),
+    ("255", "255", "N13,N..12", Rule::CheckDigit),
+    ("30", "30", "N..8", Rule::None),
+    ("3100", "3169", "N6", Rule::None),
+    ("3200", "3379", "N6", Rule::None),
+    ("3400", "3579", "N6", Rule::None),
+    ("3600", "3699", "N6", Rule::None),
+    ("37", "37", "N..8", Rule::None),
+    ("3900", "3909", "N..15", Rule::None),
+    ("3910", "3919", "N3,N..15", Rule::None),
+    ("3920", "3929", "N..15", Rule::None),
+    ("3930", "3939", "N3,N..15", Rule::None),
+    ("3940", "3943", "N4", Rule::None),
+    ("3950", "3955", "N6", Rule::None),
+    ("400", "401", "X..30", Rule::None),
+    ("402", "402", "N17", Rule::CheckDigit),
+    ("403", "403", "X..30", Rule::None),
+    ("410", "417", "N13", Rule::CheckDigit),
+    ("420", "420", "X..20", Rule::None),
+    ("421", "421", "N3,X..9", Rule::None),
+    ("422", "422", "N3", Rule::None),
+    ("423", "423", "N3,N..12", Rule::None),
+    ("424", "424", "N3", Rule::None),
+    ("425", "425", "N3,N..12", Rule::None),
+    ("426", "426", "N3", Rule::None),
+    ("427", "427", "X..3", Rule::None),
+    ("4300", "4301", "X..35", Rule::None),
+    ("4302", "4306", "X..70", Rule::None),
+    ("4307", "4307", "X2", Rule::None),
+    ("4308", "4308", "X..30", Rule::None),
+    ("4309", "4309", "N20", Rule::None),
+    ("4310", "4311", "X..35", Rule::None),
+    ("4312", "4316", "X..70", Rule::None),
+    ("4317", "4317", "X2", Rule::None),
+    ("4318", "4318", "X..20", Rule::None),
+    ("4319", "4319", "X..30", Rule::None),
+    ("4320", "4320", "X..35", Rule::None),
+    ("4321", "4323", "N1", Rule::None),
+    ("4324", "4325", "N10", Rule::Date),
+    ("4326", "4326", "N6", Rule::Date),
+    ("7001", "7001", "N13", Rule::None),
+    ("7002", "7002", "X..30", Rule::None),
+    ("7003", "7003", "N10", Rule::Date),
+    ("7004", "7004", "N..4", Rule::None),
+    ("7005", "7005", "X..12", Rule::None),
+    ("7006", "7006", "N6", Rule::Date),
+    ("7007", "7007", "N6,N..6", Rule::Date),
+    ("7008", "7008", "X..3", Rule::None),
+    ("7009", "7009", "X..10", Rule::None),
+    ("7010", "7010", "X..2", Rule::None),
+    ("7011", "7011", "N6,N..4", Rule::Date),
+    ("7020", "7022", "X..20", Rule::None),
+    ("7023", "7023", "X..30", Rule::None),
+    ("7030", "7039", "N3,X..27", Rule::None),
+    ("7040", "7040", "N1,X3", Rule::None),
+    ("710", "716", "X..20", Rule::None),
+    ("7230", "7239", "X2,X..28", Rule::None),
+    ("7240", "7240", "X..20", Rule::None),
+    ("8001", "8001", "N14", Rule::None),
+    ("8002", "8002", "X..20", Rule::None),
+    ("8003", "8003", "N14,X..16", Rule::CheckDigit),
+    ("8004", "8004", "X..30", Rule::None),
+    ("8005", "8005", "N6", Rule::None),
+    ("8006", "8006", "N14,N2,N2", Rule::CheckDigit),
+    ("8007", "8007", "X..34", Rule::None),
+    ("8008", "8008", "N8,N..4", Rule::Date),
+    ("8009", "8009", "X..50", Rule::None),
+    ("8010", "8010", "X..30", Rule::None),
+    ("8011", "8011", "N..12", Rule::None),
+    ("8012", "8012", "X..20", Rule::None),
+    ("8013", "8014", "X..25", Rule::None),
+    ("8017", "8018", "N18", Rule::CheckDigit),
+    ("8019", "8019", "N..10", Rule::None),
+    ("8020", "8020", "X..25", Rule::None),
+    ("8026", "8026", "N14,N2,N2", Rule::CheckDigit),
+    ("8030", "8030", "X..90", Rule::None),
+    ("8110", "8110", "X..70", Rule::None),
+    ("8111", "8111", "N4", Rule::None),
+    ("8112", "8112", "X..70", Rule::None),
+    ("8200", "8200", "X..70", Rule::None),
+    ("90", "90", "X..30", Rule::None),
+    ("91", "99", "X..90", Rule::None),
+];
+
+/// Application Identifier with its data field.
+#[derive(Debug, PartialEq)]
+pub struct Element {
+    pub ai: String,
+    pub data: String,
+}
+
+impl Element {
+    /// Whether the data field has a predefined length, so no separator is needed after it.
+    pub fn is_predefined_length(&self) -> bool {
+        PREDEFINED_LENGTH.contains(&&self.ai[..2])
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let (_, _, format, rule) = AIS
+            .iter()
+            .find(|&&(first, last, _, _)| {
+                first.len() == self.ai.len() && first <= &self.ai[..] && &self.ai[..] <= last
+            })
+            .ok_or_else(|| format!("Unknown GS1 Application Identifier ({})", self.ai))?;
+
+        let mut rest = &self.data[..];
+        for (i, component) in format.split(',').enumerate() {
+            let numeric = component.starts_with('N');
+            let (variable, length) = match component[1..].strip_prefix("..") {
+                Some(length) => (true, length.parse::<usize>().unwrap()),
+                None => (false, component[1..].parse::<usize>().unwrap()),
+            };
+            let available = rest.chars().count();
+            let taken = std::cmp::min(available, length);
+            if taken == 0 || (!variable && taken < length) {
+                let expected = if variable {
+                    format!("1 to {}", length)
+                } else {
+                    format!("exactly {}", length)
+                };
+                return Err(format!("AI ({}) expects {} characters", self.ai, expected));
+            }
+            let end = rest
+                .char_indices()
+                .nth(taken)
+                .map_or(rest.len(), |(index, _)| index);
+            let (value, remaining) = rest.split_at(end);
+            rest = remaining;
+
+            let allowed = if numeric { DIGITS } else { CSET82 };
+            if let Some(c) = value.chars().find(|&c| !allowed.contains(c)) {
+                return Err(format!("AI ({}) cannot contain '{}'", self.ai, c));
+            }
+            if i == 0 {
+                validate_rule(&self.ai, value, *rule)?;
+            }
+        }
+        if !rest.is_empty() {
+            return Err(format!("AI ({}) data is too long", self.ai));
+        }
+        Ok(())
+    }
+}
+
+fn validate_rule(ai: &str, value: &str, rule: Rule) -> Result<(), String> {
+    if rule == Rule::None {
+        return Ok(());
+    }
+    // Rules only apply to numeric components.
+    let digits: Vec<u8> = value.bytes().map(|b| b - b'0').collect();
+    match rule {
+        Rule::None => Ok(()),
+        Rule::CheckDigit => {
+            let (data, actual) = digits.split_at(digits.len() - 1);
+            let expected = check_digit(data);
+            if actual[0] != expected {
+                return Err(format!(
+                    "AI ({}) has invalid check digit {}, expected {}",
+                    ai, actual[0], expected
+                ));
+            }
+            Ok(())
+        }
+        Rule::Date => {
+            let month = digits[2] * 10 + digits[3];
+            let day = digits[4] * 10 + digits[5];
+            // Day 00 stands for the last day of the month.
+            if !(1..=12).contains(&month) || day > 31 {
+                return Err(format!("AI ({}) has invalid date {}", ai, value));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Parses and validates a human readable GS1 string, such as `(01)09501101530003(10)ABC`.
+/// Data fields cannot contain parentheses, they always start a new AI.
+pub fn parse(input: &str) -> Result<Vec<Element>, String> {
+    if !input.starts_with('(') {
+        return Err("GS1 data must start with an Application Identifier in parentheses".to_owned());
+    }
+    let mut elements = Vec::new();
+    for part in input[1..].split('(') {
+        let (ai, data) = match part.find(')') {
+            Some(index) => (&part[..index], &part[index + 1..]),
+            None => return Err(format!("Missing ')' after AI ({}", part)),
+        };
+        if ai.len() < 2 || ai.len() > 4 || !ai.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("Invalid GS1 Application Identifier ({})", ai));
+        }
+        let element = Element {
+            ai: ai.to_owned(),
+            data: data.to_owned(),
+        };
+        element.validate()?;
+        elements.push(element);
+    }
+    Ok(elements)
+}
+
+/// Concatenates the elements, placing `separator` after each variable length field but the last.
+/// Symbologies add their own FNC1 in the first position.
+pub fn element_string(elements: &[Element], separator: char) -> String {
+    let mut result = String::new();
+    for (i, element) in elements.iter().enumerate() {
+        result.push_str(&element.ai);
+        result.push_str(&element.data);
+        if !element.is_predefined_length() && i < elements.len() - 1 {
+            result.push(separator);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(ai: &str, data: &str) -> Element {
+        Element {
+            ai: ai.to_owned(),
+            data: data.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Ok(vec![
+                element("01", "09501101530003"),
+                element("17", "251231"),
+                element("10", "ABC"),
+            ]),
+            parse("(01)09501101530003(17)251231(10)ABC")
+        );
+        assert_eq!(
+            Ok(vec![
+                element("3103", "000189"),
+                element("253", "9501101530003AB")
+            ]),
+            parse("(3103)000189(253)9501101530003AB")
+        );
+    }
+
+    #[test]
+    fn test_ais() {
+        assert!(parse("(7003)2512311530").is_ok());
+        assert!(parse("(8200)https://example.com/p").is_ok());
+        assert!(parse("(4321)1(7240)ABC").is_ok());
+        assert!(parse("(8026)095011015300030102").is_ok());
+        assert_eq!(
+            Err("AI (7003) has invalid date 2513311530".to_owned()),
+            parse("(7003)2513311530")
+        );
+    }
+
+    #[test]
+    fn test_element_string() {
+        let elements = parse("(01)09501101530003(17)251231(10)ABC").unwrap();
+        assert_eq!(
+            "01095011015300031725123110ABC",
+            element_string(&elements, '|')
+        );
+        let elements = parse("(10)ABC(21)123(17)251231").unwrap();
+        assert_eq!("10ABC|21123|17251231", element_string(&elements, '|'));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            Err("AI (01) has invalid check digit 4, expected 3".to_owned()),
+            parse("(01)09501101530004")
+        );
+        assert_eq!(
+            Err("AI (17) has invalid date 251331".to_owned()),
+            parse("(17)251331")
+        );
+        assert_eq!(
+            Err("Unknown GS1 Application Identifier (05)".to_owned()),
+            parse("(05)1")
+        );
+        assert_eq!(
+            Err("AI (01) expects exactly 14 characters".to_owned()),
+            parse("(01)0950110153000")
+        );
+        assert_eq!(
+            Err("AI (10) data is too long".to_owned()),
+            parse("(10)ABCDEFGHIJKLMNOPQRSTU")
+        );
+        assert_eq!(
+            Err("AI (10) cannot contain '#'".to_owned()),
+            parse("(10)AB#")
+        );
+        assert_eq!(
+            Err("AI (01) cannot contain 'A'".to_owned()),
+            parse("(01)0950110153000A")
+        );
+        assert_eq!(
+            Err("Unknown GS1 Application Identifier (7199)".to_owned()),
+            parse("(7199)1")
+        );
+        assert!(parse("01)09501101530003").is_err());
+        assert!(parse("(01").is_err());
+        assert!(parse("(10)").is_err());
+    }
+}
@@ -0,0 +1,366 @@
+use barcoders::sym::codabar::Codabar;
+use barcoders::sym::code39::Code39;
+use barcoders::sym::code93::Code93;
+use barcoders::sym::tf::TF;
+
+use crate::encoders::barcode::{bar_rows, xdim};
+use crate::encoders::ean::{check_digit, with_check_digit};
+use crate::encoders::encoder::{Encode, OutputParams};
+use crate::encoders::matrix::{Matrix, QuietZone};
+use crate::encoders::text::TextOptions;
+
+/// Light margin on both sides of the symbol, in modules.
+const QUIET_ZONE: usize = 10;
+/// Thickness of the ITF-14 bearer bars, in modules.
+const BEARER_WIDTH: usize = 3;
+
+const CODE39_CHARS: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ-. $/+%";
+const CODABAR_CHARS: &str = "0123456789-$:/.+";
+const CODABAR_START_STOP: &str = "ABCD";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symbology {
+    Code39 {
+        checksum: bool,
+        full_ascii: bool,
+    },
+    Code93,
+    Codabar {
+        start: char,
+        stop: char,
+    },
+    /// With `checksum`, a mod-10 check digit completes an odd number of digits.
+    Interleaved2of5 {
+        checksum: bool,
+    },
+    Itf14,
+}
+
+/// Writes an ASCII character with the Code 39 Full ASCII shift pairs.
+fn code39_full_ascii(c: char) -> Result<String, String> {
+    let code = c as u32;
+    let shifted = |shift: char, first: char, offset: u32| {
+        let letter = std::char::from_u32(first as u32 + code - offset).unwrap();
+        format!("{}{}", shift, letter)
+    };
+    Ok(match c {
+        '\u{0000}' => "%U".to_owned(),
+        '\u{0001}'..='\u{001A}' => shifted('$', 'A', 1),
+        '\u{001B}'..='\u{001F}' => shifted('%', 'A', 27),
+        ' ' | '-' | '.' | '0'..='9' | 'A'..='Z' => c.to_string(),
+        '!'..=',' => shifted('/', 'A', 33),
+        '/' => "/O".to_owned(),
+        ':' => "/Z".to_owned(),
+        ';'..='?' => shifted('%', 'F', 59),
+        '@' => "%V".to_owned(),
+        '['..='_' => shifted('%', 'K', 91),
+        '`' => "%W".to_owned(),
+        'a'..='z' => shifted('+', 'A', 97),
+        '{'..='\u{007F}' => shifted('%', 'P', 123),
+        _ => return Err(format!("Character '{}' cannot be encoded in Code 39", c)),
+    })
+}
+
+fn check_characters(data: &str, allowed: &str, name: &str) -> Result<(), String> {
+    match data.chars().find(|&c| !allowed.contains(c)) {
+        Some(c) => Err(format!("Character '{}' cannot be encoded in {}", c, name)),
+        None if data.is_empty() => Err("Payload is empty".to_owned()),
+        None => Ok(()),
+    }
+}
+
+pub struct Linear {
+    height: u32,
+    payload: String,
+    symbology: Symbology,
+    text: TextOptions,
+}
+
+impl Linear {
+    pub fn new(payload: String, height: u32, symbology: Symbology, text: TextOptions) -> Self {
+        Linear {
+            payload,
+            height,
+            symbology,
+            text,
+        }
+    }
+
+    /// The 14 digits of an ITF-14 symbol, the check digit is added to 13 digits
+    /// and verified otherwise.
+    fn get_itf14_digits(&self) -> Result<String, String> {
+        check_characters(&self.payload, "0123456789", "ITF-14")?;
+        let digits = self.payload.bytes().map(|b| b - b'0').collect();
+        Ok(with_check_digit(digits, 14, "ITF-14")?
+            .iter()
+            .map(|&d| (b'0' + d) as char)
+            .collect())
+    }
+
+    /// Digits of an Interleaved 2 of 5 symbol, including the check digit when one is added.
+    fn get_interleaved_digits(&self, checksum: bool) -> Result<String, String> {
+        check_characters(&self.payload, "0123456789", "Interleaved 2 of 5")?;
+        let mut data = self.payload.clone();
+        // Digits are encoded in pairs.
+        let length = data.len() + checksum as usize;
+        if length % 2 == 1 {
+            return Err(if checksum {
+                format!(
+                    "Interleaved 2 of 5 encodes digits in pairs, {} digits and the check digit \
+                     are odd, add a leading zero",
+                    data.len()
+                )
+            } else {
+                format!(
+                    "Interleaved 2 of 5 encodes digits in pairs, got {} digits, \
+                     add a leading zero or set checksum=true",
+                    data.len()
+                )
+            });
+        }
+        if checksum {
+            let digits: Vec<u8> = data.bytes().map(|b| b - b'0').collect();
+            data.push((b'0' + check_digit(&digits)) as char);
+        }
+        Ok(data)
+    }
+
+    fn get_bytes(&self) -> Result<Vec<u8>, String> {
+        let payload = &self.payload;
+        let encoded = match self.symbology {
+            Symbology::Code39 {
+                checksum,
+                full_ascii,
+            } => {
+                let data = if full_ascii {
+                    payload
+                        .chars()
+                        .map(code39_full_ascii)
+                        .collect::<Result<String, String>>()?
+                } else {
+                    payload.clone()
+                };
+                check_characters(&data, CODE39_CHARS, "Code 39")?;
+                if checksum {
+                    Code39::with_checksum(data)
+                } else {
+                    Code39::new(data)
+                }
+                .map(|code| code.encode())
+            }
+            Symbology::Code93 => {
+                // Only the characters shared with Code 39, the Full ASCII shifts are not accepted.
+                check_characters(payload, CODE39_CHARS, "Code 93")?;
+                Code93::new(payload).map(|code| code.encode())
+            }
+            Symbology::Codabar { start, stop } => {
+                check_characters(payload, CODABAR_CHARS, "Codabar")?;
+                for &c in &[start, stop] {
+                    if !CODABAR_START_STOP.contains(c) {
+                        return Err(format!(
+                            "Codabar start and stop characters are A, B, C or D, got '{}'",
+                            c
+                        ));
+                    }
+                }
+                Codabar::new(format!("{}{}{}", start, payload, stop)).map(|code| code.encode())
+            }
+            Symbology::Interleaved2of5 { checksum } => {
+                TF::interleaved(self.get_interleaved_digits(checksum)?).map(|code| code.encode())
+            }
+            Symbology::Itf14 => TF::interleaved(self.get_itf14_digits()?).map(|code| code.encode()),
+        };
+        encoded.map_err(|e| e.to_string())
+    }
+}
+
+impl Encode for Linear {
+    fn symbol(&self) -> Result<Matrix, String> {
+        let mut bars: Vec<bool> = self.get_bytes()?.iter().map(|&bit| bit == 1).collect();
+        let rows = bar_rows(self.height);
+        if self.symbology != Symbology::Itf14 {
+            return Ok(Matrix::from_bars(&bars, rows));
+        }
+
+        // Bearer bars frame the symbol and its quiet zone, so that a skewed scan line
+        // cannot read a partial code.
+        let bearer = std::cmp::min(BEARER_WIDTH, rows / 4);
+        bars.splice(0..0, vec![false; QUIET_ZONE]);
+        bars.extend(vec![false; QUIET_ZONE]);
+        let mut matrix =
+            Matrix::from_bars(&bars, rows).with_quiet_zone(QuietZone::horizontal(bearer, bearer));
+        let width = matrix.width();
+        for y in 0..rows {
+            for x in 0..width {
+                if y < bearer || y >= rows - bearer || x < bearer || x >= width - bearer {
+                    matrix.set(x, y, true);
+                }
+            }
+        }
+        Ok(matrix)
+    }
+
+    fn quiet_zone(&self) -> QuietZone {
+        match self.symbology {
+            // The quiet zone is already inside the bearer bars.
+            Symbology::Itf14 => QuietZone::default(),
+            _ => QuietZone::horizontal(QUIET_ZONE, QUIET_ZONE),
+        }
+    }
+
+    fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn text(&self) -> Result<String, String> {
+        match self.symbology {
+            Symbology::Interleaved2of5 { checksum } => self.get_interleaved_digits(checksum),
+            Symbology::Itf14 => self.get_itf14_digits(),
+            _ => Ok(self.payload.clone()),
+        }
+    }
+
+    fn get_params(&self) -> OutputParams {
+        OutputParams {
+            append_text: self.text.show,
+            font_size: self.text.font_size(xdim(self.height)),
+            height: self.height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Luma;
+
+    use super::*;
+    use crate::encoders::encoder::{Encoded, RenderOptions};
+
+    const CODE39: Symbology = Symbology::Code39 {
+        checksum: false,
+        full_ascii: false,
+    };
+
+    const ITF: Symbology = Symbology::Interleaved2of5 { checksum: false };
+    const ITF_CHECKSUM: Symbology = Symbology::Interleaved2of5 { checksum: true };
+
+    fn bytes(payload: &str, symbology: Symbology) -> Result<Vec<u8>, String> {
+        Linear::new(payload.to_owned(), 10, symbology, TextOptions::default()).get_bytes()
+    }
+
+    #[test]
+    fn test_code39() {
+        // Start and stop of 12 modules, a leading gap and three characters with their gaps.
+        assert_eq!(12 + 1 + 3 * 13 + 12, bytes("ABC", CODE39).unwrap().len());
+        assert!(bytes("abc", CODE39).is_err());
+        assert!(bytes("", CODE39).is_err());
+    }
+
+    #[test]
+    fn test_code39_checksum() {
+        let with_checksum = Symbology::Code39 {
+            checksum: true,
+            full_ascii: false,
+        };
+        // "CODE39" sums to 12 + 24 + 13 + 14 + 3 + 9 = 75, 75 mod 43 is 32, which is 'W'.
+        assert_eq!(
+            bytes("CODE39W", CODE39).unwrap(),
+            bytes("CODE39", with_checksum).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_code39_full_ascii() {
+        let full_ascii = Symbology::Code39 {
+            checksum: false,
+            full_ascii: true,
+        };
+        assert_eq!(
+            bytes("+C+O+D+E/A%U%T", CODE39).unwrap(),
+            bytes("code!\u{0000}\u{007F}", full_ascii).unwrap()
+        );
+        assert_eq!(
+            bytes("$M/O%V%K%W", CODE39).unwrap(),
+            bytes("\r/@[`", full_ascii).unwrap()
+        );
+        assert!(bytes("тест", full_ascii).is_err());
+    }
+
+    #[test]
+    fn test_code93() {
+        // Start, two characters, two check characters, stop and termination bar.
+        assert_eq!(6 * 9 + 1, bytes("AB", Symbology::Code93).unwrap().len());
+        assert!(bytes("A(B", Symbology::Code93).is_err());
+    }
+
+    #[test]
+    fn test_codabar() {
+        let codabar = |start, stop| Symbology::Codabar { start, stop };
+        assert_eq!(
+            Codabar::new("B123D").unwrap().encode(),
+            bytes("123", codabar('B', 'D')).unwrap()
+        );
+        assert!(bytes("12A3", codabar('A', 'A')).is_err());
+        assert!(bytes("123", codabar('E', 'A')).is_err());
+    }
+
+    #[test]
+    fn test_interleaved() {
+        // Start, two pairs of 18 modules and stop.
+        assert_eq!(4 + 2 * 18 + 4, bytes("1234", ITF).unwrap().len());
+        // The digits are scanned as requested, whatever their number.
+        assert!(bytes("123", ITF).unwrap_err().contains("leading zero"));
+        assert_eq!(
+            bytes("1236", ITF).unwrap(),
+            bytes("123", ITF_CHECKSUM).unwrap()
+        );
+        assert!(bytes("1234", ITF_CHECKSUM)
+            .unwrap_err()
+            .contains("leading zero"));
+    }
+
+    #[test]
+    fn test_itf14() {
+        assert_eq!(
+            bytes("00012345678905", ITF).unwrap(),
+            bytes("0001234567890", Symbology::Itf14).unwrap()
+        );
+        assert_eq!(
+            Err("Invalid ITF-14 check digit 6, expected 5".to_owned()),
+            bytes("00012345678906", Symbology::Itf14)
+        );
+    }
+
+    #[test]
+    fn test_bearer_bars() {
+        let itf14 = Linear::new(
+            "0001234567890".to_owned(),
+            100,
+            Symbology::Itf14,
+            TextOptions::default(),
+        );
+        let image = Encoded::new(&itf14, RenderOptions::default())
+            .and_then(|code| code.image())
+            .unwrap()
+            .to_luma();
+        let (width, height) = image.dimensions();
+        assert_eq!(Luma([0]), *image.get_pixel(width / 2, 0));
+        assert_eq!(Luma([0]), *image.get_pixel(width / 2, height - 1));
+        assert_eq!(Luma([0]), *image.get_pixel(0, height / 2));
+        assert_eq!(Luma([255]), *image.get_pixel(5, height / 2));
+    }
+
+    #[test]
+    fn test_text() {
+        let itf = Linear::new("1234".to_owned(), 10, ITF, TextOptions::default());
+        assert_eq!(Ok("1234".to_owned()), itf.text());
+        let itf = Linear::new("123".to_owned(), 10, ITF_CHECKSUM, TextOptions::default());
+        assert_eq!(Ok("1236".to_owned()), itf.text());
+        let code39 = Linear::new("ABC".to_owned(), 10, CODE39, TextOptions::default());
+        assert_eq!(Ok("ABC".to_owned()), code39.text());
+    }
+}
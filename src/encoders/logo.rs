@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use image::{imageops, DynamicImage, FilterType, GenericImageView};
+use qrcode::canvas::{Canvas, Module};
+use qrcode::{EcLevel, Version};
+
+/// Directory of the pre-registered logos, `name` is looked up as `name.png`.
+const LOGO_DIR: &str = "static/logos";
+pub const DEFAULT_SIZE_PERCENT: u32 = 20;
+
+/// Error correction blocks at ECC level H for versions 1 to 40: data codewords of the short
+/// blocks, number of short blocks, number of long blocks with one more data codeword,
+/// and error correction codewords per block. ISO/IEC 18004, table 9.
+const EC_BLOCKS_H: [(usize, usize, usize, usize); 40] = [
+    (9, 1, 0, 17),
+    (16, 1, 0, 28),
+    (13, 2, 0, 22),
+    (9, 4, 0, 16),
+    (11, 2, 2, 22),
+    (15, 4, 0, 28),
+    (13, 4, 1, 26),
+    (14, 4, 2, 26),
+    (12, 4, 4, 24),
+    (15, 6, 2, 28),
+    (12, 3, 8, 24),
+    (14, 7, 4, 28),
+    (11, 12, 4, 22),
+    (12, 11, 5, 24),
+    (12, 11, 7, 24),
+    (15, 3, 13, 30),
+    (14, 2, 17, 28),
+    (14, 2, 19, 28),
+    (13, 9, 16, 26),
+    (15, 15, 10, 28),
+    (16, 19, 6, 30),
+    (13, 34, 0, 24),
+    (15, 16, 14, 30),
+    (16, 30, 2, 30),
+    (15, 22, 13, 30),
+    (16, 33, 4, 30),
+    (15, 12, 28, 30),
+    (15, 11, 31, 30),
+    (15, 19, 26, 30),
+    (15, 23, 25, 30),
+    (15, 23, 28, 30),
+    (15, 19, 35, 30),
+    (15, 11, 46, 30),
+    (16, 59, 1, 30),
+    (15, 22, 41, 30),
+    (15, 2, 64, 30),
+    (15, 24, 46, 30),
+    (15, 42, 32, 30),
+    (15, 10, 67, 30),
+    (15, 20, 61, 30),
+];
+
+/// Square of modules in the centre of a symbol, in modules from the top left corner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Area {
+    pub start: usize,
+    pub side: usize,
+}
+
+impl Area {
+    fn contains(&self, x: usize, y: usize) -> bool {
+        (self.start..self.start + self.side).contains(&x)
+            && (self.start..self.start + self.side).contains(&y)
+    }
+}
+
+/// Image drawn over the centre of a QR code, whose error correction restores the covered modules.
+pub struct Logo {
+    image: DynamicImage,
+    /// Side of the covered square, in percent of the symbol width.
+    size_percent: u32,
+}
+
+impl Logo {
+    pub fn new(image: DynamicImage, size_percent: u32) -> Result<Self, String> {
+        if !(1..=100).contains(&size_percent) {
+            return Err(format!(
+                "Logo size must be between 1 and 100 percent, got {}",
+                size_percent
+            ));
+        }
+        Ok(Logo {
+            image,
+            size_percent,
+        })
+    }
+
+    /// Decodes an uploaded PNG, JPEG, GIF or BMP image.
+    pub fn from_bytes(bytes: &[u8], size_percent: u32) -> Result<Self, String> {
+        let image = image::load_from_memory(bytes).map_err(|e| format!("Invalid logo: {}", e))?;
+        Logo::new(image, size_percent)
+    }
+
+    /// Loads a logo from the directory of pre-registered logos.
+    pub fn registered(name: &str, size_percent: u32) -> Result<Self, String> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!("Invalid logo name {:?}", name));
+        }
+        let image = image::open(format!("{}/{}.png", LOGO_DIR, name))
+            .map_err(|_| format!("Logo {:?} is not registered", name))?;
+        Logo::new(image, size_percent)
+    }
+
+    /// Centred square covered by the logo in a symbol `width` modules wide.
+    pub fn area(&self, width: usize) -> Area {
+        let side = std::cmp::max(1, (width * self.size_percent as usize + 50) / 100);
+        // Same parity as the width, so that the square is exactly in the centre.
+        let side = std::cmp::min(width, side + (width - side) % 2);
+        Area {
+            start: (width - side) / 2,
+            side,
+        }
+    }
+
+    /// Draws the logo scaled into the rectangle in pixels, keeping its aspect ratio.
+    pub fn draw(
+        &self,
+        image: DynamicImage,
+        (x, y, width, height): (u32, u32, u32, u32),
+    ) -> DynamicImage {
+        let logo = self.image.resize(width, height, FilterType::Lanczos3);
+        let mut canvas = image.to_rgba();
+        imageops::overlay(
+            &mut canvas,
+            &logo.to_rgba(),
+            x + (width - logo.width()) / 2,
+            y + (height - logo.height()) / 2,
+        );
+        DynamicImage::ImageRgba8(canvas)
+    }
+}
+
+/// Modules which hold the bits of the data and error correction codewords, in placement order.
+fn data_modules(version: i16) -> Vec<(usize, usize)> {
+    let mut canvas = Canvas::new(Version::Normal(version), EcLevel::H);
+    canvas.draw_all_functional_patterns();
+    let width = Version::Normal(version).width();
+    let mut modules = Vec::new();
+    let mut upward = true;
+    let mut right = width - 1;
+    while right > 0 {
+        // Skips the vertical timing pattern.
+        if right == 6 {
+            right = 5;
+        }
+        for i in 0..width {
+            let y = if upward { width - 1 - i } else { i };
+            for x in &[right, right - 1] {
+                if canvas.get(*x, y) == Module::Empty {
+                    modules.push((*x as usize, y as usize));
+                }
+            }
+        }
+        upward = !upward;
+        right -= 2;
+    }
+    modules
+}
+
+/// Block of every codeword, in the interleaved order the codewords are placed in.
+fn codeword_blocks(version: i16) -> (Vec<usize>, usize) {
+    let (short_size, short_count, long_count, ec_size) = EC_BLOCKS_H[version as usize - 1];
+    let blocks = short_count + long_count;
+    let mut result = Vec::new();
+    for i in 0..=short_size {
+        for block in 0..blocks {
+            if i < short_size || block >= short_count {
+                result.push(block);
+            }
+        }
+    }
+    for _ in 0..ec_size {
+        result.extend(0..blocks);
+    }
+    (result, ec_size)
+}
+
+/// Finder patterns with their separators, timing patterns, format and version information,
+/// which a reader needs to find and sample the symbol.
+fn is_structural(version: i16, width: usize, x: usize, y: usize) -> bool {
+    let finder = (x < 9 && (y < 9 || y >= width - 8)) || (x >= width - 8 && y < 9);
+    let version_info = version >= 7 && ((x < 6 && y >= width - 11) || (y < 6 && x >= width - 11));
+    finder || version_info || x == 6 || y == 6
+}
+
+/// Checks that every error correction block of a symbol at ECC level H can restore
+/// the codewords under the logo.
+pub fn check_budget(version: i16, area: Area) -> Result<(), String> {
+    let width = Version::Normal(version).width() as usize;
+    for y in area.start..area.start + area.side {
+        for x in area.start..area.start + area.side {
+            if is_structural(version, width, x, y) {
+                return Err("Logo covers the finder or timing patterns, \
+                            make it smaller or raise min_version"
+                    .to_owned());
+            }
+        }
+    }
+
+    let (blocks, ec_size) = codeword_blocks(version);
+    // Version 1 keeps one error correction codeword to detect misdecoding.
+    let misdecode_protection = if version == 1 { 1 } else { 0 };
+    let correctable = (ec_size - misdecode_protection) / 2;
+    let mut damaged: HashMap<usize, usize> = HashMap::new();
+    let mut codewords: Vec<usize> = data_modules(version)
+        .iter()
+        .enumerate()
+        .filter(|(bit, &(x, y))| bit / 8 < blocks.len() && area.contains(x, y))
+        .map(|(bit, _)| bit / 8)
+        .collect();
+    codewords.dedup();
+    for codeword in codewords {
+        *damaged.entry(blocks[codeword]).or_insert(0) += 1;
+    }
+    match damaged.values().max() {
+        Some(&worst) if worst > correctable => Err(format!(
+            "Logo covers {} codewords of an error correction block which restores {}, \
+             make it smaller or raise min_version",
+            worst, correctable
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma, Rgba, RgbaImage};
+
+    use super::*;
+
+    fn logo(size_percent: u32) -> Logo {
+        let image = RgbaImage::from_pixel(4, 2, Rgba([255, 0, 0, 255]));
+        Logo::new(DynamicImage::ImageRgba8(image), size_percent).unwrap()
+    }
+
+    #[test]
+    fn test_area() {
+        assert_eq!(Area { start: 8, side: 5 }, logo(20).area(21));
+        assert_eq!(Area { start: 11, side: 7 }, logo(25).area(29));
+        assert_eq!(Area { start: 0, side: 21 }, logo(100).area(21));
+        assert!(Logo::new(DynamicImage::new_luma8(1, 1), 0).is_err());
+    }
+
+    #[test]
+    fn test_tables() {
+        for version in 1..=40 {
+            let (blocks, _) = codeword_blocks(version);
+            // Up to seven remainder bits follow the codewords.
+            let bits = data_modules(version).len();
+            assert_eq!(bits / 8, blocks.len(), "version {}", version);
+        }
+    }
+
+    #[test]
+    fn test_check_budget() {
+        assert!(check_budget(2, logo(20).area(25)).is_ok());
+        // 27 of 57 modules, a fifth of the area.
+        assert!(check_budget(10, logo(45).area(57)).is_ok());
+        assert_eq!(
+            Err(
+                "Logo covers 15 codewords of an error correction block which restores 14, \
+                 make it smaller or raise min_version"
+                    .to_owned()
+            ),
+            check_budget(10, logo(50).area(57))
+        );
+        // The corner of the top left finder pattern's separator.
+        assert!(check_budget(2, logo(30).area(25)).is_err());
+    }
+
+    #[test]
+    fn test_registered() {
+        assert!(Logo::registered("example", 20).is_ok());
+        assert!(Logo::registered("missing", 20).is_err());
+        assert!(Logo::registered("../index", 20).is_err());
+    }
+
+    #[test]
+    fn test_draw() {
+        let image = DynamicImage::ImageLuma8(GrayImage::from_pixel(20, 20, Luma([255])));
+        let drawn = logo(20).draw(image, (5, 5, 8, 8)).to_rgba();
+        // The logo is twice as wide as high and centred vertically.
+        assert_eq!(Rgba([255, 0, 0, 255]), *drawn.get_pixel(5, 9));
+        assert_eq!(Rgba([255, 255, 255, 255]), *drawn.get_pixel(5, 6));
+        assert_eq!(Rgba([255, 255, 255, 255]), *drawn.get_pixel(14, 9));
+    }
+}
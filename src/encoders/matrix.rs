@@ -0,0 +1,189 @@
+use image::{DynamicImage, GrayImage, Luma};
+
+/// Widest quiet zone accepted from a request, in modules.
+const MAX_QUIET_ZONE: usize = 200;
+
+/// Light margin around a symbol, in modules.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QuietZone {
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+    pub left: usize,
+}
+
+impl QuietZone {
+    pub fn uniform(size: usize) -> Self {
+        QuietZone {
+            top: size,
+            right: size,
+            bottom: size,
+            left: size,
+        }
+    }
+
+    /// Parses `N` for the same margin on every side, or `H,V` for the left and right
+    /// and the top and bottom margins.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let sizes = value
+            .split(',')
+            .map(|size| size.trim().parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| format!("Invalid quiet zone {:?}", value))?;
+        if sizes.iter().any(|&size| size > MAX_QUIET_ZONE) {
+            return Err(format!(
+                "Quiet zone is at most {} modules, got {:?}",
+                MAX_QUIET_ZONE, value
+            ));
+        }
+        match sizes[..] {
+            [size] => Ok(QuietZone::uniform(size)),
+            [horizontal, vertical] => Ok(QuietZone {
+                top: vertical,
+                right: horizontal,
+                bottom: vertical,
+                left: horizontal,
+            }),
+            _ => Err(format!(
+                "Quiet zone takes one or two sizes in modules, got {:?}",
+                value
+            )),
+        }
+    }
+
+    /// Margins on the sides only, as linear symbols need.
+    pub fn horizontal(left: usize, right: usize) -> Self {
+        QuietZone {
+            left,
+            right,
+            ..QuietZone::default()
+        }
+    }
+}
+
+/// Grid of dark and light modules, produced by the two-dimensional symbologies.
+/// Linear symbologies use one row per module of bar height.
+#[derive(Clone)]
+pub struct Matrix {
+    width: usize,
+    height: usize,
+    modules: Vec<bool>,
+}
+
+impl Matrix {
+    pub fn new(width: usize, height: usize) -> Self {
+        Matrix {
+            width,
+            height,
+            modules: vec![false; width * height],
+        }
+    }
+
+    /// Repeats the bars of a linear symbol over `rows` rows.
+    pub fn from_bars(bars: &[bool], rows: usize) -> Self {
+        Matrix {
+            width: bars.len(),
+            height: rows,
+            modules: bars.repeat(rows),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.width + x] = dark;
+    }
+
+    /// Surrounds the matrix with light modules.
+    pub fn with_quiet_zone(&self, quiet_zone: QuietZone) -> Matrix {
+        let mut matrix = Matrix::new(
+            self.width + quiet_zone.left + quiet_zone.right,
+            self.height + quiet_zone.top + quiet_zone.bottom,
+        );
+        for y in 0..self.height {
+            for x in 0..self.width {
+                matrix.set(x + quiet_zone.left, y + quiet_zone.top, self.get(x, y));
+            }
+        }
+        matrix
+    }
+
+    /// Size of a square module in pixels, so that the symbol is at least `min_height` pixels high.
+    pub fn module_size(&self, min_height: u32) -> u32 {
+        std::cmp::max(1, min_height.div_ceil(self.height as u32))
+    }
+
+    /// Draws every module as a rectangle of `module_width` by `module_height` pixels.
+    pub fn to_image(&self, module_width: u32, module_height: u32) -> DynamicImage {
+        let mut image = GrayImage::from_pixel(
+            self.width as u32 * module_width,
+            self.height as u32 * module_height,
+            Luma([255]),
+        );
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !self.get(x, y) {
+                    continue;
+                }
+                let left = x as u32 * module_width;
+                let top = y as u32 * module_height;
+                for py in top..top + module_height {
+                    for px in left..left + module_width {
+                        image.put_pixel(px, py, Luma([0]));
+                    }
+                }
+            }
+        }
+        DynamicImage::ImageLuma8(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_zone() {
+        let mut matrix = Matrix::new(2, 1);
+        matrix.set(1, 0, true);
+        let matrix = matrix.with_quiet_zone(QuietZone::uniform(2));
+        assert_eq!((6, 5), (matrix.width(), matrix.height()));
+        assert!(matrix.get(3, 2));
+        assert!(!matrix.get(2, 2));
+        assert_eq!(3, matrix.module_size(15));
+
+        let matrix = matrix.with_quiet_zone(QuietZone::horizontal(1, 3));
+        assert_eq!((10, 5), (matrix.width(), matrix.height()));
+        assert!(matrix.get(4, 2));
+    }
+
+    #[test]
+    fn test_parse_quiet_zone() {
+        assert_eq!(Ok(QuietZone::uniform(4)), QuietZone::parse("4"));
+        assert_eq!(
+            Ok(QuietZone {
+                top: 2,
+                right: 10,
+                bottom: 2,
+                left: 10
+            }),
+            QuietZone::parse("10, 2")
+        );
+        assert!(QuietZone::parse("-1").is_err());
+        assert!(QuietZone::parse("1,2,3").is_err());
+        assert!(QuietZone::parse("").is_err());
+        assert_eq!(Ok(QuietZone::uniform(200)), QuietZone::parse("200"));
+        assert!(QuietZone::parse("201").is_err());
+        assert!(QuietZone::parse("4,18446744073709551615").is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use qrcode::bits::Bits;
+use qrcode::optimize::{total_encoded_len, Parser, Segment};
+use qrcode::types::{Mode, QrError};
+use qrcode::{EcLevel, Version};
+
+use crate::encoders::encoder::{Encode, OutputParams};
+use crate::encoders::matrix::{Matrix, QuietZone};
+use crate::encoders::qrcode::{bits_to_matrix, EccLevel};
+
+const QUIET_ZONE: usize = 2;
+const MAX_VERSION: i16 = 4;
+
+/// Whether Micro QR version `M<version>` has the data mode.
+fn supports(version: i16, mode: Mode) -> bool {
+    match mode {
+        Mode::Numeric => true,
+        Mode::Alphanumeric => version >= 2,
+        Mode::Byte | Mode::Kanji => version >= 3,
+    }
+}
+
+pub struct MicroQR {
+    height: u32,
+    payload: String,
+    ecc_level: EccLevel,
+    version: Option<i16>,
+}
+
+impl MicroQR {
+    /// `version` from 1 to 4 fixes the symbol to M1-M4, otherwise the smallest one is used.
+    pub fn new(payload: String, height: u32, ecc_level: EccLevel, version: Option<i16>) -> Self {
+        MicroQR {
+            payload,
+            height,
+            ecc_level,
+            version,
+        }
+    }
+
+    /// M1 only has error detection, so the requested level does not apply to it.
+    fn ec_level(&self, version: i16) -> EcLevel {
+        if version == 1 {
+            EcLevel::L
+        } else {
+            self.ecc_level.to_qrcode()
+        }
+    }
+
+    fn get_bits(&self) -> Result<Bits, String> {
+        let versions = match self.version {
+            Some(version) if (1..=MAX_VERSION).contains(&version) => version..=version,
+            Some(version) => {
+                return Err(format!(
+                    "Micro QR version must be between 1 and {}, got {}",
+                    MAX_VERSION, version
+                ))
+            }
+            // M1 is only chosen automatically when no error correction was asked for.
+            None if self.ecc_level == EccLevel::L => 1..=MAX_VERSION,
+            None => 2..=MAX_VERSION,
+        };
+
+        let mut last_version = None;
+        for version in versions {
+            let ec_level = self.ec_level(version);
+            let mut bits = Bits::new(Version::Micro(version));
+            if bits.max_len(ec_level).is_err() {
+                continue;
+            }
+            last_version = Some(version);
+            let pushed = bits
+                .push_optimal_data(self.payload.as_bytes())
+                .and_then(|_| bits.push_terminator(ec_level));
+            match pushed {
+                Ok(_) => return Ok(bits),
+                Err(QrError::DataTooLong) | Err(QrError::UnsupportedCharacterSet) => continue,
+                Err(error) => return Err(error.to_string()),
+            }
+        }
+        match last_version {
+            Some(version) => Err(self.capacity_error(version)),
+            None => Err(format!(
+                "Micro QR does not support ECC level {:?}",
+                self.ecc_level
+            )),
+        }
+    }
+
+    /// Explains why the payload does not fit in `M<version>`.
+    fn capacity_error(&self, version: i16) -> String {
+        let micro = Version::Micro(version);
+        let data = self.payload.as_bytes();
+        let segments: Vec<Segment> = Parser::new(data).optimize(micro).collect();
+        if let Some(segment) = segments.iter().find(|s| !supports(version, s.mode)) {
+            return format!(
+                "Micro QR M{} cannot encode {:?} data such as {:?}",
+                version,
+                segment.mode,
+                String::from_utf8_lossy(&data[segment.begin..segment.end])
+            );
+        }
+        let needed = total_encoded_len(&segments, micro);
+        let capacity = Bits::new(micro).max_len(self.ec_level(version)).unwrap();
+        let level = if version == 1 {
+            "error detection only".to_owned()
+        } else {
+            format!("ECC level {:?}", self.ecc_level)
+        };
+        format!(
+            "Payload needs {} bits, but Micro QR M{} with {} holds {}, exceeded by {} bits",
+            needed,
+            version,
+            level,
+            capacity,
+            needed - capacity
+        )
+    }
+
+    pub fn get_matrix(&self) -> Result<Matrix, String> {
+        let bits = self.get_bits()?;
+        let version = match bits.version() {
+            Version::Micro(version) | Version::Normal(version) => version,
+        };
+        bits_to_matrix(bits, self.ec_level(version), None)
+    }
+}
+
+impl Encode for MicroQR {
+    fn symbol(&self) -> Result<Matrix, String> {
+        self.get_matrix()
+    }
+
+    fn quiet_zone(&self) -> QuietZone {
+        QuietZone::uniform(QUIET_ZONE)
+    }
+
+    fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    fn get_params(&self) -> OutputParams {
+        OutputParams {
+            append_text: false,
+            font_size: 0,
+            height: self.height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn width(payload: &str, ecc_level: EccLevel, version: Option<i16>) -> Result<usize, String> {
+        MicroQR::new(payload.to_owned(), 10, ecc_level, version)
+            .get_matrix()
+            .map(|matrix| matrix.width())
+    }
+
+    #[test]
+    fn test_versions() {
+        assert_eq!(Ok(11), width("12345", EccLevel::L, None));
+        assert_eq!(Ok(13), width("12345", EccLevel::M, None));
+        assert_eq!(Ok(13), width("HELLO", EccLevel::L, None));
+        assert_eq!(Ok(15), width("hello", EccLevel::L, None));
+        assert_eq!(Ok(17), width("hello", EccLevel::L, Some(4)));
+        assert_eq!(Ok(17), width("hello", EccLevel::Q, None));
+    }
+
+    #[test]
+    fn test_capacity_exceeded() {
+        // M1 holds 20 bits, six digits take 3 for the length and 20 for the data.
+        assert_eq!(
+            Err(
+                "Payload needs 23 bits, but Micro QR M1 with error detection only holds 20, \
+                 exceeded by 3 bits"
+                    .to_owned()
+            ),
+            width("123456", EccLevel::L, Some(1))
+        );
+        assert_eq!(
+            Err("Micro QR M2 cannot encode Byte data such as \"hello\"".to_owned()),
+            width("hello", EccLevel::L, Some(2))
+        );
+        let error = width(&"a".repeat(30), EccLevel::M, None).unwrap_err();
+        assert!(error.starts_with("Payload needs 248 bits, but Micro QR M4 with ECC level M"));
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(width("1", EccLevel::H, None).is_err());
+        assert!(width("1", EccLevel::Q, Some(3)).is_err());
+        assert!(width("1", EccLevel::L, Some(5)).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use image::DynamicImage;
+
+use crate::encoders::matrix::{Matrix, QuietZone};
+
+/// Clockwise rotation of the output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rotation {
+    Zero,
+    Ninety,
+    OneEighty,
+    TwoSeventy,
+}
+
+impl Rotation {
+    pub fn from_degrees(degrees: u16) -> Result<Self, String> {
+        match degrees {
+            0 => Ok(Rotation::Zero),
+            90 => Ok(Rotation::Ninety),
+            180 => Ok(Rotation::OneEighty),
+            270 => Ok(Rotation::TwoSeventy),
+            _ => Err(format!(
+                "Rotation must be 0, 90, 180 or 270 degrees, got {}",
+                degrees
+            )),
+        }
+    }
+}
+
+/// Post-processing shared by every symbology: the symbol is mirrored horizontally first,
+/// then rotated clockwise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orientation {
+    pub rotation: Rotation,
+    pub mirror: bool,
+}
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Orientation {
+            rotation: Rotation::Zero,
+            mirror: false,
+        }
+    }
+}
+
+impl Orientation {
+    pub fn apply_image(&self, image: DynamicImage) -> DynamicImage {
+        let image = if self.mirror { image.fliph() } else { image };
+        match self.rotation {
+            Rotation::Zero => image,
+            Rotation::Ninety => image.rotate90(),
+            Rotation::OneEighty => image.rotate180(),
+            Rotation::TwoSeventy => image.rotate270(),
+        }
+    }
+
+    pub fn apply_matrix(&self, matrix: &Matrix) -> Matrix {
+        let (width, height) = (matrix.width(), matrix.height());
+        let (new_width, new_height) = match self.rotation {
+            Rotation::Zero | Rotation::OneEighty => (width, height),
+            Rotation::Ninety | Rotation::TwoSeventy => (height, width),
+        };
+        let mut result = Matrix::new(new_width, new_height);
+        for y in 0..height {
+            for x in 0..width {
+                let mx = if self.mirror { width - 1 - x } else { x };
+                let (nx, ny) = match self.rotation {
+                    Rotation::Zero => (mx, y),
+                    Rotation::Ninety => (height - 1 - y, mx),
+                    Rotation::OneEighty => (width - 1 - mx, height - 1 - y),
+                    Rotation::TwoSeventy => (y, width - 1 - mx),
+                };
+                result.set(nx, ny, matrix.get(x, y));
+            }
+        }
+        result
+    }
+
+    /// Width and height of a module after the rotation.
+    pub fn apply_size(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        match self.rotation {
+            Rotation::Zero | Rotation::OneEighty => (width, height),
+            Rotation::Ninety | Rotation::TwoSeventy => (height, width),
+        }
+    }
+
+    pub fn apply_quiet_zone(&self, quiet_zone: QuietZone) -> QuietZone {
+        let QuietZone {
+            top,
+            right,
+            bottom,
+            left,
+        } = quiet_zone;
+        let (right, left) = if self.mirror {
+            (left, right)
+        } else {
+            (right, left)
+        };
+        match self.rotation {
+            Rotation::Zero => QuietZone {
+                top,
+                right,
+                bottom,
+                left,
+            },
+            Rotation::Ninety => QuietZone {
+                top: left,
+                right: top,
+                bottom: right,
+                left: bottom,
+            },
+            Rotation::OneEighty => QuietZone {
+                top: bottom,
+                right: left,
+                bottom: top,
+                left: right,
+            },
+            Rotation::TwoSeventy => QuietZone {
+                top: right,
+                right: bottom,
+                bottom: left,
+                left: top,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GenericImageView, GrayImage, Luma};
+
+    use super::*;
+
+    /// Asymmetric 3x2 matrix, which looks different in every orientation.
+    fn matrix() -> Matrix {
+        let mut matrix = Matrix::new(3, 2);
+        matrix.set(0, 0, true);
+        matrix.set(2, 0, true);
+        matrix.set(2, 1, true);
+        matrix
+    }
+
+    fn image(matrix: &Matrix) -> DynamicImage {
+        matrix.to_image(1, 1)
+    }
+
+    #[test]
+    fn test_matrix_matches_image() {
+        for &rotation in &[
+            Rotation::Zero,
+            Rotation::Ninety,
+            Rotation::OneEighty,
+            Rotation::TwoSeventy,
+        ] {
+            for &mirror in &[false, true] {
+                let orientation = Orientation { rotation, mirror };
+                let rotated = orientation.apply_matrix(&matrix());
+                let expected: GrayImage = image(&rotated).to_luma();
+                let actual = orientation.apply_image(image(&matrix())).to_luma();
+                assert_eq!(expected.into_raw(), actual.into_raw(), "{:?}", orientation);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rotate_ninety() {
+        let orientation = Orientation {
+            rotation: Rotation::Ninety,
+            mirror: false,
+        };
+        let rotated = orientation.apply_matrix(&matrix());
+        assert_eq!((2, 3), (rotated.width(), rotated.height()));
+        // The top left corner moves to the top right.
+        assert!(rotated.get(1, 0));
+        assert!(!rotated.get(0, 0));
+        let image = orientation.apply_image(image(&matrix()));
+        assert_eq!((2, 3), image.dimensions());
+        assert_eq!(Luma([0]), image.to_luma().get_pixel(1, 0).to_owned());
+    }
+
+    #[test]
+    fn test_quiet_zone() {
+        let quiet_zone = QuietZone {
+            top: 1,
+            right: 2,
+            bottom: 3,
+            left: 4,
+        };
+        let orientation = Orientation {
+            rotation: Rotation::Ninety,
+            mirror: true,
+        };
+        // Mirrored to 1, 4, 3, 2, then the left side becomes the top.
+        assert_eq!(
+            QuietZone {
+                top: 2,
+                right: 1,
+                bottom: 4,
+                left: 3
+            },
+            orientation.apply_quiet_zone(quiet_zone)
+        );
+    }
+
+    #[test]
+    fn test_rotation_degrees() {
+        assert_eq!(Ok(Rotation::TwoSeventy), Rotation::from_degrees(270));
+        assert!(Rotation::from_degrees(45).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt::Write;
+
+use crate::encoders::color::Color;
+use crate::encoders::encoder::Encoded;
+use crate::encoders::print::{self, Page, PrintSize};
+use crate::encoders::shape::Segment;
+
+/// PDF page of the symbol at the physical `size`.
+pub fn output(code: &Encoded, size: &PrintSize) -> Result<Vec<u8>, String> {
+    let page = print::page(code, size)?;
+    Ok(render(
+        &page,
+        code.options.foreground,
+        code.options.background,
+    ))
+}
+
+/// Writes a one page PDF with the rectangles of `page` filled in the foreground colour.
+/// Print has no partial transparency, a background which is not fully transparent is painted opaque.
+pub fn render(page: &Page, foreground: Color, background: Color) -> Vec<u8> {
+    let mut content = String::new();
+    if background.a > 0 {
+        let [r, g, b] = background.components();
+        writeln!(
+            content,
+            "{:.3} {:.3} {:.3} rg\n0 0 {:.3} {:.3} re\nf",
+            r, g, b, page.width, page.height
+        )
+        .unwrap();
+    }
+    let [r, g, b] = foreground.components();
+    writeln!(content, "{:.3} {:.3} {:.3} rg", r, g, b).unwrap();
+    for rect in &page.rects {
+        writeln!(
+            content,
+            "{:.3} {:.3} {:.3} {:.3} re",
+            rect.x, rect.y, rect.width, rect.height
+        )
+        .unwrap();
+    }
+    content.push_str("f\n");
+    if !page.shapes.is_empty() {
+        for subpath in page.shapes.iter().flat_map(|shape| &shape.subpaths) {
+            writeln!(content, "{:.3} {:.3} m", subpath.start.x, subpath.start.y).unwrap();
+            for segment in &subpath.segments {
+                match segment {
+                    Segment::Line(p) => writeln!(content, "{:.3} {:.3} l", p.x, p.y),
+                    Segment::Curve(c1, c2, p) => writeln!(
+                        content,
+                        "{:.3} {:.3} {:.3} {:.3} {:.3} {:.3} c",
+                        c1.x, c1.y, c2.x, c2.y, p.x, p.y
+                    ),
+                }
+                .unwrap();
+            }
+            content.push_str("h\n");
+        }
+        content.push_str("f*\n");
+    }
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_owned(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.3} {:.3}] /Contents 4 0 R >>",
+            page.width, page.height
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ),
+    ];
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        write!(pdf, "{} 0 obj\n{}\nendobj\n", i + 1, object).unwrap();
+    }
+    let xref = pdf.len();
+    write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).unwrap();
+    for offset in offsets {
+        writeln!(pdf, "{:010} 00000 n ", offset).unwrap();
+    }
+    write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    )
+    .unwrap();
+    pdf.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoders::color::{BLACK, WHITE};
+    use crate::encoders::print::PointRect;
+    use crate::encoders::shape::Shape;
+
+    #[test]
+    fn test_render() {
+        let page = Page {
+            width: 144.0,
+            height: 144.0,
+            rects: vec![PointRect {
+                x: 36.0,
+                y: 72.0,
+                width: 36.0,
+                height: 36.0,
+            }],
+            shapes: Vec::new(),
+        };
+        let pdf = String::from_utf8(render(&page, BLACK, WHITE)).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.contains(
+            "stream\n1.000 1.000 1.000 rg\n0 0 144.000 144.000 re\nf\n0.000 0.000 0.000 rg\n"
+        ));
+        assert!(pdf.contains("/MediaBox [0 0 144.000 144.000]"));
+        assert!(pdf.contains("36.000 72.000 36.000 36.000 re\nf\n"));
+
+        let xref = pdf.find("xref\n").unwrap();
+        assert!(pdf.ends_with(&format!("startxref\n{}\n%%EOF\n", xref)));
+        let offset = pdf.find("4 0 obj").unwrap();
+        assert!(pdf.contains(&format!("{:010} 00000 n \ntrailer", offset)));
+    }
+
+    #[test]
+    fn test_render_shapes() {
+        let page = Page {
+            width: 10.0,
+            height: 10.0,
+            rects: Vec::new(),
+            shapes: vec![Shape::rounded_rect(0.0, 0.0, 2.0, 2.0, 1.0)],
+        };
+        let pdf = String::from_utf8(render(&page, BLACK, WHITE)).unwrap();
+        assert!(pdf
+            .contains("f\n1.000 0.000 m\n1.000 0.000 l\n1.552 0.000 2.000 0.448 2.000 1.000 c\n"));
+        assert!(pdf.contains("0.448 0.000 1.000 0.000 c\nh\nf*\nendstream"));
+    }
+}
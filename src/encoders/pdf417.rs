@@ -0,0 +1,697 @@
+use crate::encoders::encoder::{Encode, Encoded, OutputParams};
+use crate::encoders::matrix::{Matrix, QuietZone};
+use crate::encoders::zpl;
+
+mod clusters;
+
+use clusters::CLUSTERS;
+
+const QUIET_ZONE: usize = 2;
+/// Height of a row in modules, ISO/IEC 15438 recommends at least three.
+const ROW_HEIGHT: usize = 3;
+const MAX_COLUMNS: usize = 30;
+const MIN_ROWS: usize = 3;
+const MAX_ROWS: usize = 90;
+const MAX_CODEWORDS: usize = 928;
+const MAX_SECURITY_LEVEL: u8 = 8;
+/// Width over height of the symbol aimed at when neither columns nor rows are given.
+const PREFERRED_RATIO: f64 = 3.0;
+
+const START_PATTERN: u32 = 0x1fea8;
+/// The stop pattern is one module longer than the codewords, ending with a bar.
+const STOP_PATTERN: u32 = 0x3fa29;
+
+const TEXT_LATCH: u32 = 900;
+const BYTE_LATCH: u32 = 901;
+const NUMERIC_LATCH: u32 = 902;
+const BYTE_SHIFT: u32 = 913;
+/// Byte compaction latch when the number of bytes is a multiple of 6.
+const BYTE_LATCH_6: u32 = 924;
+const ECI: u32 = 927;
+const ECI_UTF8: u32 = 26;
+const PAD: u32 = 900;
+
+/// Digits which are worth a switch to numeric compaction.
+const MIN_NUMERIC: usize = 13;
+/// Text characters which are worth a switch to text compaction.
+const MIN_TEXT: usize = 5;
+/// Digits encoded in a single numeric compaction group.
+const NUMERIC_GROUP: usize = 44;
+
+const MIXED: &[u8] = b"0123456789&\r\t,:#-.$/+%*=^";
+const PUNCTUATION: &[u8] = b";<>@[\\]_`~!\r\t,:\n-.$/\"|*()?{}'";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Compaction {
+    Text,
+    Byte,
+    Numeric,
+}
+
+/// Sub-modes of text compaction, each with 30 values of which the last ones switch to the others.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum SubMode {
+    Alpha,
+    Lower,
+    Mixed,
+    Punctuation,
+}
+
+fn is_alpha(byte: u8) -> bool {
+    byte == b' ' || byte.is_ascii_uppercase()
+}
+
+fn is_lower(byte: u8) -> bool {
+    byte == b' ' || byte.is_ascii_lowercase()
+}
+
+fn is_mixed(byte: u8) -> bool {
+    byte == b' ' || MIXED.contains(&byte)
+}
+
+fn is_punctuation(byte: u8) -> bool {
+    PUNCTUATION.contains(&byte)
+}
+
+fn is_text(byte: u8) -> bool {
+    byte == b'\t' || byte == b'\n' || byte == b'\r' || (b' '..=b'~').contains(&byte)
+}
+
+fn position(table: &[u8], byte: u8) -> u32 {
+    table.iter().position(|&b| b == byte).unwrap() as u32
+}
+
+fn mixed_value(byte: u8) -> u32 {
+    if byte == b' ' {
+        26
+    } else {
+        position(MIXED, byte)
+    }
+}
+
+/// Text compaction of `text`, two values of 0 to 29 per codeword. Returns the sub-mode
+/// at the end, which the next text compaction continues from.
+fn encode_text(text: &[u8], mut sub_mode: SubMode, codewords: &mut Vec<u32>) -> SubMode {
+    let mut values = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        let byte = text[i];
+        match sub_mode {
+            SubMode::Alpha => {
+                if is_alpha(byte) {
+                    values.push(if byte == b' ' {
+                        26
+                    } else {
+                        (byte - b'A') as u32
+                    });
+                } else if is_lower(byte) {
+                    sub_mode = SubMode::Lower;
+                    values.push(27);
+                    continue;
+                } else if is_mixed(byte) {
+                    sub_mode = SubMode::Mixed;
+                    values.push(28);
+                    continue;
+                } else {
+                    values.extend(&[29, position(PUNCTUATION, byte)]);
+                }
+            }
+            SubMode::Lower => {
+                if is_lower(byte) {
+                    values.push(if byte == b' ' {
+                        26
+                    } else {
+                        (byte - b'a') as u32
+                    });
+                } else if is_alpha(byte) {
+                    // Shift to alpha for a single capital.
+                    values.extend(&[27, (byte - b'A') as u32]);
+                } else if is_mixed(byte) {
+                    sub_mode = SubMode::Mixed;
+                    values.push(28);
+                    continue;
+                } else {
+                    values.extend(&[29, position(PUNCTUATION, byte)]);
+                }
+            }
+            SubMode::Mixed => {
+                if is_mixed(byte) {
+                    values.push(mixed_value(byte));
+                } else if is_alpha(byte) {
+                    sub_mode = SubMode::Alpha;
+                    values.push(28);
+                    continue;
+                } else if is_lower(byte) {
+                    sub_mode = SubMode::Lower;
+                    values.push(27);
+                    continue;
+                } else if text.get(i + 1).is_some_and(|&next| is_punctuation(next)) {
+                    sub_mode = SubMode::Punctuation;
+                    values.push(25);
+                    continue;
+                } else {
+                    values.extend(&[29, position(PUNCTUATION, byte)]);
+                }
+            }
+            SubMode::Punctuation => {
+                if is_punctuation(byte) {
+                    values.push(position(PUNCTUATION, byte));
+                } else {
+                    sub_mode = SubMode::Alpha;
+                    values.push(29);
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+    // An odd value is completed by a shift to punctuation, which nothing follows.
+    if values.len() % 2 == 1 {
+        values.push(29);
+    }
+    codewords.extend(values.chunks(2).map(|pair| pair[0] * 30 + pair[1]));
+    sub_mode
+}
+
+/// Byte compaction, six bytes in five base 900 codewords and the rest one per codeword.
+fn encode_bytes(bytes: &[u8], compaction: Compaction, codewords: &mut Vec<u32>) {
+    if bytes.len() == 1 && compaction == Compaction::Text {
+        codewords.push(BYTE_SHIFT);
+    } else if bytes.len().is_multiple_of(6) {
+        codewords.push(BYTE_LATCH_6);
+    } else {
+        codewords.push(BYTE_LATCH);
+    }
+    let groups = bytes.chunks_exact(6);
+    let rest = groups.remainder();
+    for group in groups {
+        let mut value = group.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+        let mut base900 = [0u32; 5];
+        for digit in base900.iter_mut().rev() {
+            *digit = (value % 900) as u32;
+            value /= 900;
+        }
+        codewords.extend(&base900);
+    }
+    codewords.extend(rest.iter().map(|&b| b as u32));
+}
+
+/// Numeric compaction, groups of up to 44 digits with a leading 1 written in base 900.
+fn encode_numeric(digits: &[u8], codewords: &mut Vec<u32>) {
+    codewords.push(NUMERIC_LATCH);
+    for group in digits.chunks(NUMERIC_GROUP) {
+        let mut decimal: Vec<u32> = std::iter::once(1)
+            .chain(group.iter().map(|&d| (d - b'0') as u32))
+            .collect();
+        let mut base900 = Vec::new();
+        while !decimal.is_empty() {
+            // Long division of the decimal digits by 900.
+            let mut quotient = Vec::with_capacity(decimal.len());
+            let mut remainder = 0;
+            for &digit in &decimal {
+                let value = remainder * 10 + digit;
+                if !quotient.is_empty() || value >= 900 {
+                    quotient.push(value / 900);
+                }
+                remainder = value % 900;
+            }
+            base900.push(remainder);
+            decimal = quotient;
+        }
+        codewords.extend(base900.iter().rev());
+    }
+}
+
+fn count_digits(data: &[u8]) -> usize {
+    data.iter().take_while(|b| b.is_ascii_digit()).count()
+}
+
+/// Text characters from the start of `data`, up to a run of digits worth numeric compaction.
+fn count_text(data: &[u8]) -> usize {
+    let mut i = 0;
+    while i < data.len() {
+        let digits = count_digits(&data[i..]);
+        if digits >= MIN_NUMERIC {
+            break;
+        }
+        if digits > 0 {
+            i += digits;
+        } else if is_text(data[i]) {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    i
+}
+
+/// Bytes from the start of `data`, up to a run worth numeric or text compaction.
+fn count_bytes(data: &[u8]) -> usize {
+    let mut i = 0;
+    while i < data.len()
+        && count_digits(&data[i..]) < MIN_NUMERIC
+        && count_text(&data[i..]) < MIN_TEXT
+    {
+        i += 1;
+    }
+    std::cmp::max(1, i)
+}
+
+/// Data codewords of the payload, following the compaction mode selection of ISO/IEC 15438 Annex P.
+/// Text which is not ASCII is written as UTF-8 bytes after an ECI.
+fn high_level_encode(payload: &str) -> Vec<u32> {
+    let data = payload.as_bytes();
+    let mut codewords = Vec::new();
+    if !payload.is_ascii() {
+        codewords.extend(&[ECI, ECI_UTF8]);
+    }
+    // Symbols start in text compaction.
+    let mut compaction = Compaction::Text;
+    let mut sub_mode = SubMode::Alpha;
+    let mut i = 0;
+    while i < data.len() {
+        let rest = &data[i..];
+        let digits = count_digits(rest);
+        if digits >= MIN_NUMERIC {
+            encode_numeric(&rest[..digits], &mut codewords);
+            compaction = Compaction::Numeric;
+            sub_mode = SubMode::Alpha;
+            i += digits;
+            continue;
+        }
+        let text = count_text(rest);
+        if text >= MIN_TEXT || text == rest.len() {
+            if compaction != Compaction::Text {
+                codewords.push(TEXT_LATCH);
+                compaction = Compaction::Text;
+                sub_mode = SubMode::Alpha;
+            }
+            sub_mode = encode_text(&rest[..text], sub_mode, &mut codewords);
+            i += text;
+            continue;
+        }
+        let bytes = count_bytes(rest);
+        encode_bytes(&rest[..bytes], compaction, &mut codewords);
+        // A shifted byte stays in text compaction.
+        if bytes > 1 || compaction != Compaction::Text {
+            compaction = Compaction::Byte;
+            sub_mode = SubMode::Alpha;
+        }
+        i += bytes;
+    }
+    codewords
+}
+
+/// Security level recommended by ISO/IEC 15438 for the number of data codewords.
+fn recommended_security_level(data_codewords: usize) -> u8 {
+    match data_codewords {
+        0..=40 => 2,
+        41..=160 => 3,
+        161..=320 => 4,
+        _ => 5,
+    }
+}
+
+/// Reed-Solomon error correction codewords over the prime field GF(929), whose generator
+/// polynomial has the roots 3, 3^2, ... 3^`count`.
+fn error_correction(data: &[u32], count: usize) -> Vec<u32> {
+    const PRIME: u32 = 929;
+    let mut generator = vec![1u32];
+    let mut root = 1;
+    for _ in 0..count {
+        root = root * 3 % PRIME;
+        let mut next = generator.clone();
+        next.push(0);
+        for (j, &coefficient) in generator.iter().enumerate() {
+            next[j + 1] = (next[j + 1] + PRIME - coefficient * root % PRIME) % PRIME;
+        }
+        generator = next;
+    }
+    let mut remainder = vec![0u32; count];
+    for &word in data {
+        let factor = (word + remainder[0]) % PRIME;
+        remainder.remove(0);
+        remainder.push(0);
+        for (value, &coefficient) in remainder.iter_mut().zip(generator[1..].iter()) {
+            *value = (*value + PRIME - factor * coefficient % PRIME) % PRIME;
+        }
+    }
+    // The codewords are the negated remainder, so that the generator divides the symbol.
+    remainder.iter().map(|&r| (PRIME - r) % PRIME).collect()
+}
+
+fn push_pattern(row: &mut Vec<bool>, pattern: u32, length: usize) {
+    row.extend((0..length).rev().map(|bit| pattern & (1 << bit) != 0));
+}
+
+pub struct Pdf417 {
+    height: u32,
+    payload: String,
+    security_level: Option<u8>,
+    columns: Option<usize>,
+    rows: Option<usize>,
+}
+
+impl Pdf417 {
+    /// Without a `security_level`, the one recommended for the data is used. Without
+    /// `columns` or `rows`, they are chosen for a symbol about three times as wide as high.
+    pub fn new(
+        payload: String,
+        height: u32,
+        security_level: Option<u8>,
+        columns: Option<usize>,
+        rows: Option<usize>,
+    ) -> Self {
+        Pdf417 {
+            payload,
+            height,
+            security_level,
+            columns,
+            rows,
+        }
+    }
+
+    fn ratio(columns: usize, rows: usize) -> f64 {
+        // Start, stop and the row indicators take 69 modules.
+        (17 * columns + 69) as f64 / (rows * ROW_HEIGHT) as f64
+    }
+
+    /// Columns and rows of data codewords holding `codewords` codewords.
+    fn dimensions(&self, codewords: usize) -> Result<(usize, usize), String> {
+        if let Some(columns) = self.columns {
+            if !(1..=MAX_COLUMNS).contains(&columns) {
+                return Err(format!(
+                    "PDF417 has 1 to {} columns, got {}",
+                    MAX_COLUMNS, columns
+                ));
+            }
+        }
+        if let Some(rows) = self.rows {
+            if !(MIN_ROWS..=MAX_ROWS).contains(&rows) {
+                return Err(format!(
+                    "PDF417 has {} to {} rows, got {}",
+                    MIN_ROWS, MAX_ROWS, rows
+                ));
+            }
+        }
+        let rows_for = |columns: usize| std::cmp::max(MIN_ROWS, codewords.div_ceil(columns));
+        let (columns, rows) = match (self.columns, self.rows) {
+            (Some(columns), Some(rows)) => (columns, rows),
+            (Some(columns), None) => (columns, rows_for(columns)),
+            (None, Some(rows)) => (std::cmp::min(MAX_COLUMNS, codewords.div_ceil(rows)), rows),
+            (None, None) => (1..=MAX_COLUMNS)
+                .map(|columns| (columns, rows_for(columns)))
+                .filter(|&(columns, rows)| rows <= MAX_ROWS && columns * rows <= MAX_CODEWORDS)
+                .min_by(|&(c1, r1), &(c2, r2)| {
+                    let d1 = (Self::ratio(c1, r1) - PREFERRED_RATIO).abs();
+                    let d2 = (Self::ratio(c2, r2) - PREFERRED_RATIO).abs();
+                    d1.partial_cmp(&d2).unwrap()
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "Data needs {} codewords, a PDF417 symbol holds at most {}",
+                        codewords, MAX_CODEWORDS
+                    )
+                })?,
+        };
+        if columns * rows < codewords || rows > MAX_ROWS {
+            return Err(format!(
+                "Data needs {} codewords, but {} columns and {} rows hold only {}",
+                codewords,
+                columns,
+                std::cmp::min(rows, MAX_ROWS),
+                columns * std::cmp::min(rows, MAX_ROWS)
+            ));
+        }
+        Ok((columns, rows))
+    }
+
+    fn get_security_level(&self, data_codewords: usize) -> Result<u8, String> {
+        match self.security_level {
+            Some(level) if level > MAX_SECURITY_LEVEL => Err(format!(
+                "PDF417 security levels are 0 to {}, got {}",
+                MAX_SECURITY_LEVEL, level
+            )),
+            Some(level) => Ok(level),
+            None => Ok(recommended_security_level(data_codewords)),
+        }
+    }
+
+    /// Codewords of the symbol row by row with its columns, rows and security level.
+    fn get_codewords(&self) -> Result<(Vec<u32>, usize, usize, u8), String> {
+        let data = high_level_encode(&self.payload);
+        let level = self.get_security_level(data.len())?;
+        let ec_count = 2 << level;
+        // The symbol length descriptor comes first.
+        let needed = 1 + data.len() + ec_count;
+        if needed > MAX_CODEWORDS {
+            return Err(format!(
+                "Data needs {} codewords with security level {}, a PDF417 symbol holds at most {}",
+                needed, level, MAX_CODEWORDS
+            ));
+        }
+        let (columns, rows) = self.dimensions(needed)?;
+        if columns * rows > MAX_CODEWORDS {
+            return Err(format!(
+                "{} columns and {} rows exceed the {} codewords of a PDF417 symbol",
+                columns, rows, MAX_CODEWORDS
+            ));
+        }
+        let length = columns * rows - ec_count;
+        let mut codewords = Vec::with_capacity(columns * rows);
+        codewords.push(length as u32);
+        codewords.extend(&data);
+        codewords.resize(length, PAD);
+        let ec = error_correction(&codewords, ec_count);
+        codewords.extend(ec);
+        Ok((codewords, columns, rows, level))
+    }
+
+    /// Modules of the symbol, rows of codewords between the start and stop patterns.
+    pub fn get_matrix(&self) -> Result<Matrix, String> {
+        let (codewords, columns, rows, level) = self.get_codewords()?;
+        let level = level as usize;
+        let width = 17 * (columns + 4) + 1;
+        let mut matrix = Matrix::new(width, rows * ROW_HEIGHT);
+        for (y, data) in codewords.chunks(columns).enumerate() {
+            let cluster = y % 3;
+            // Row indicators hold the number of rows, of columns and the security level.
+            let base = 30 * (y / 3);
+            let rows_value = base + (rows - 1) / 3;
+            let columns_value = base + columns - 1;
+            let level_value = base + level * 3 + (rows - 1) % 3;
+            let (left, right) = match cluster {
+                0 => (rows_value, columns_value),
+                1 => (level_value, rows_value),
+                _ => (columns_value, level_value),
+            };
+            let table = &CLUSTERS[cluster];
+            let mut row = Vec::with_capacity(width);
+            push_pattern(&mut row, START_PATTERN, 17);
+            push_pattern(&mut row, table[left], 17);
+            for &codeword in data {
+                push_pattern(&mut row, table[codeword as usize], 17);
+            }
+            push_pattern(&mut row, table[right], 17);
+            push_pattern(&mut row, STOP_PATTERN, 18);
+            for dy in 0..ROW_HEIGHT {
+                for (x, &dark) in row.iter().enumerate() {
+                    matrix.set(x, y * ROW_HEIGHT + dy, dark);
+                }
+            }
+        }
+        Ok(matrix)
+    }
+}
+
+impl Encode for Pdf417 {
+    fn symbol(&self) -> Result<Matrix, String> {
+        self.get_matrix()
+    }
+
+    fn quiet_zone(&self) -> QuietZone {
+        QuietZone::uniform(QUIET_ZONE)
+    }
+
+    fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    fn get_params(&self) -> OutputParams {
+        OutputParams {
+            append_text: false,
+            font_size: 0,
+            height: self.height,
+        }
+    }
+
+    /// `^B7` with the columns and security level of the raster symbol, the printer
+    /// picks the compaction and so the rows itself.
+    fn zpl(&self, code: &Encoded) -> Result<Option<String>, String> {
+        let (_, columns, _, level) = self.get_codewords()?;
+        let (module, _) = code.module_size()?;
+        if module > zpl::MAX_MODULE {
+            return Ok(None);
+        }
+        Ok(Some(format!(
+            "^BY{}^B7{},{},{},{},,N{}",
+            module,
+            zpl::orientation(code.options.orientation.rotation),
+            ROW_HEIGHT as u32 * module,
+            level,
+            columns,
+            zpl::field_data(&self.payload)
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element_widths(pattern: u32) -> Vec<usize> {
+        let mut widths = vec![1];
+        for bit in (0..16).rev() {
+            let dark = pattern & (1 << bit) != 0;
+            let previous = pattern & (1 << (bit + 1)) != 0;
+            if dark == previous {
+                *widths.last_mut().unwrap() += 1;
+            } else {
+                widths.push(1);
+            }
+        }
+        widths
+    }
+
+    #[test]
+    fn test_clusters() {
+        for (i, table) in CLUSTERS.iter().enumerate() {
+            let mut patterns = table.to_vec();
+            patterns.sort_unstable();
+            patterns.dedup();
+            assert_eq!(929, patterns.len());
+            for &pattern in table.iter() {
+                // Four bars and four spaces of 1 to 6 modules, starting with a bar.
+                let widths = element_widths(pattern);
+                assert_eq!(8, widths.len(), "{:x}", pattern);
+                assert!(pattern & (1 << 16) != 0 && pattern & 1 == 0);
+                assert!(widths.iter().all(|&w| w <= 6), "{:x}", pattern);
+                let bars: Vec<isize> = widths.iter().step_by(2).map(|&w| w as isize).collect();
+                let cluster = (bars[0] - bars[1] + bars[2] - bars[3] + 9) % 9;
+                assert_eq!(i as isize * 3, cluster, "{:x}", pattern);
+            }
+        }
+    }
+
+    #[test]
+    fn test_text() {
+        let mut codewords = Vec::new();
+        // "PDF417" from ISO/IEC 15438 Annex D: P D F, latch to mixed, 4 1 7, padded.
+        assert_eq!(
+            SubMode::Mixed,
+            encode_text(b"PDF417", SubMode::Alpha, &mut codewords)
+        );
+        assert_eq!(vec![453, 178, 121, 239], codewords);
+
+        let mut codewords = Vec::new();
+        encode_text(b"aB;", SubMode::Alpha, &mut codewords);
+        // Latch to lower, a, shift to alpha, B, shift to punctuation, ';'.
+        assert_eq!(vec![27 * 30, 27 * 30 + 1, 29 * 30], codewords);
+    }
+
+    #[test]
+    fn test_numeric() {
+        let mut codewords = Vec::new();
+        // Example of ISO/IEC 15438 5.4.4.3.
+        encode_numeric(b"000213298174000", &mut codewords);
+        assert_eq!(vec![NUMERIC_LATCH, 1, 624, 434, 632, 282, 200], codewords);
+    }
+
+    #[test]
+    fn test_bytes() {
+        let mut codewords = Vec::new();
+        encode_bytes(b"alcool", Compaction::Text, &mut codewords);
+        assert_eq!(BYTE_LATCH_6, codewords[0]);
+        assert_eq!(6, codewords.len());
+        let mut codewords = Vec::new();
+        encode_bytes(&[0xff], Compaction::Text, &mut codewords);
+        assert_eq!(vec![BYTE_SHIFT, 0xff], codewords);
+        let mut codewords = Vec::new();
+        encode_bytes(&[1, 2], Compaction::Numeric, &mut codewords);
+        assert_eq!(vec![BYTE_LATCH, 1, 2], codewords);
+    }
+
+    #[test]
+    fn test_high_level() {
+        assert_eq!(
+            // H E, L L, O and a shift to punctuation.
+            vec![7 * 30 + 4, 11 * 30 + 11, 14 * 30 + 29],
+            high_level_encode("HELLO")
+        );
+        // Latch to lower, "hello ", then the digits.
+        let codewords = high_level_encode("hello 12345678901234567890");
+        assert_eq!(NUMERIC_LATCH, codewords[4]);
+        // Text too short for text compaction goes in byte compaction.
+        assert_eq!(
+            vec![BYTE_LATCH, b'a' as u32, b'b' as u32, NUMERIC_LATCH],
+            high_level_encode("ab1234567890123")[..4]
+        );
+        assert_eq!(
+            vec![ECI, ECI_UTF8, BYTE_LATCH],
+            high_level_encode("тест")[..3]
+        );
+    }
+
+    #[test]
+    fn test_error_correction() {
+        let data = vec![5, 453, 178, 121, 239];
+        let mut codewords = data.clone();
+        codewords.extend(error_correction(&data, 8));
+        // The symbol evaluates to zero at every root of the generator polynomial.
+        let mut root = 1;
+        for _ in 0..8 {
+            root = root * 3 % 929;
+            let value = codewords
+                .iter()
+                .fold(0, |acc, &word| (acc * root + word) % 929);
+            assert_eq!(0, value);
+        }
+    }
+
+    #[test]
+    fn test_symbol() {
+        let pdf417 = Pdf417::new("PDF417".to_owned(), 100, Some(1), Some(2), None);
+        let (codewords, columns, rows, level) = pdf417.get_codewords().unwrap();
+        assert_eq!((2, 5, 1), (columns, rows, level));
+        assert_eq!(vec![6, 453, 178, 121, 239, 900], codewords[..6]);
+        let symbol = pdf417.get_matrix().unwrap();
+        assert_eq!(
+            (17 * 6 + 1, 5 * ROW_HEIGHT),
+            (symbol.width(), symbol.height())
+        );
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let pdf417 = |columns, rows| Pdf417::new("hello".to_owned(), 100, None, columns, rows);
+        assert_eq!(Ok((4, 3)), pdf417(Some(4), None).dimensions(12));
+        assert_eq!(Ok((4, 30)), pdf417(None, Some(30)).dimensions(100));
+        assert!(pdf417(Some(31), None).dimensions(12).is_err());
+        assert!(pdf417(None, Some(2)).dimensions(12).is_err());
+        assert!(pdf417(Some(1), None).dimensions(100).is_err());
+        assert!(pdf417(Some(2), Some(3)).dimensions(12).is_err());
+        let (columns, rows) = pdf417(None, None).dimensions(100).unwrap();
+        assert!(columns * rows >= 100);
+        assert!(pdf417(None, None).dimensions(929).is_err());
+    }
+
+    #[test]
+    fn test_security_level() {
+        let pdf417 = Pdf417::new("hello".to_owned(), 100, Some(9), None, None);
+        assert!(pdf417.get_matrix().is_err());
+        let long = "x".repeat(1000);
+        assert!(Pdf417::new(long, 100, Some(8), None, None)
+            .get_matrix()
+            .is_err());
+    }
+}
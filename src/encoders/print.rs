@@ -0,0 +1,226 @@
+use crate::encoders::encoder::Encoded;
+use crate::encoders::matrix::{Matrix, QuietZone};
+use crate::encoders::orientation::Orientation;
+use crate::encoders::shape::{Point, Shape};
+use crate::encoders::svg;
+
+const POINTS_PER_MM: f64 = 72.0 / 25.4;
+
+/// Physical size of a printable document holding one symbol.
+pub struct PrintSize {
+    /// Height of the symbol without its quiet zone.
+    pub height_mm: f64,
+    /// Margin on every side, when not given the symbology's own quiet zone is used.
+    pub quiet_zone_mm: Option<f64>,
+}
+
+impl PrintSize {
+    fn validate(&self) -> Result<(), String> {
+        if !(self.height_mm.is_finite() && self.height_mm > 0.0) {
+            return Err(format!(
+                "height_mm must be positive, got {}",
+                self.height_mm
+            ));
+        }
+        match self.quiet_zone_mm {
+            Some(quiet_zone) if !(quiet_zone.is_finite() && quiet_zone >= 0.0) => Err(format!(
+                "quiet_zone_mm must not be negative, got {}",
+                quiet_zone
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Rectangle in points, from its bottom left corner.
+#[derive(Debug, PartialEq)]
+pub struct PointRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Symbol laid out in PostScript and PDF space: points, with the origin at the bottom left corner.
+pub struct Page {
+    pub width: f64,
+    pub height: f64,
+    pub rects: Vec<PointRect>,
+    /// Outlines filled with the even-odd rule, for symbols not drawn as rectangles.
+    pub shapes: Vec<Shape>,
+}
+
+/// Symbol of a request laid out on a page of `size`, for the printable formats.
+pub fn page(code: &Encoded, size: &PrintSize) -> Result<Page, String> {
+    let orientation = code.options.orientation;
+    layout(
+        &code.symbol,
+        code.quiet_zone,
+        size,
+        orientation,
+        code.shapes(orientation),
+    )
+}
+
+/// Scales the dark modules of `symbol` to `size`, surrounded by the quiet zone.
+/// The size applies to the symbol before it is turned to `orientation`.
+/// `shapes` of the turned symbol in module units replace the rectangles of the modules.
+pub fn layout(
+    symbol: &Matrix,
+    quiet_zone: QuietZone,
+    size: &PrintSize,
+    orientation: Orientation,
+    shapes: Option<Vec<Shape>>,
+) -> Result<Page, String> {
+    size.validate()?;
+    let module = size.height_mm / symbol.height() as f64 * POINTS_PER_MM;
+    let symbol = &orientation.apply_matrix(symbol);
+    let quiet_zone = orientation.apply_quiet_zone(quiet_zone);
+    let margin = |modules: usize| match size.quiet_zone_mm {
+        Some(quiet_zone) => quiet_zone * POINTS_PER_MM,
+        None => modules as f64 * module,
+    };
+    let (left, right) = (margin(quiet_zone.left), margin(quiet_zone.right));
+    let (top, bottom) = (margin(quiet_zone.top), margin(quiet_zone.bottom));
+    let width = left + symbol.width() as f64 * module + right;
+    let height = top + symbol.height() as f64 * module + bottom;
+
+    // Rows are counted from the top, points from the bottom.
+    if let Some(shapes) = shapes {
+        let shapes = shapes
+            .iter()
+            .map(|shape| {
+                shape.map(|p| Point {
+                    x: left + p.x * module,
+                    y: height - top - p.y * module,
+                })
+            })
+            .collect();
+        return Ok(Page {
+            width,
+            height,
+            rects: Vec::new(),
+            shapes,
+        });
+    }
+    let rects = svg::rectangles(symbol)
+        .iter()
+        .map(|rect| PointRect {
+            x: left + rect.x as f64 * module,
+            y: height - top - (rect.y + rect.height) as f64 * module,
+            width: rect.width as f64 * module,
+            height: rect.height as f64 * module,
+        })
+        .collect();
+    Ok(Page {
+        width,
+        height,
+        rects,
+        shapes: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoders::orientation::Rotation;
+
+    fn page(quiet_zone_mm: Option<f64>) -> Page {
+        let mut symbol = Matrix::new(2, 2);
+        symbol.set(0, 0, true);
+        let size = PrintSize {
+            height_mm: 25.4,
+            quiet_zone_mm,
+        };
+        layout(
+            &symbol,
+            QuietZone::uniform(1),
+            &size,
+            Orientation::default(),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_layout() {
+        let page = page(None);
+        assert_eq!((144.0, 144.0), (page.width, page.height));
+        assert_eq!(
+            vec![PointRect {
+                x: 36.0,
+                y: 72.0,
+                width: 36.0,
+                height: 36.0
+            }],
+            page.rects
+        );
+    }
+
+    #[test]
+    fn test_orientation() {
+        let symbol = Matrix::from_bars(&[true, false, false, false], 2);
+        let size = PrintSize {
+            height_mm: 25.4,
+            quiet_zone_mm: None,
+        };
+        let orientation = Orientation {
+            rotation: Rotation::Ninety,
+            mirror: false,
+        };
+        let page = layout(
+            &symbol,
+            QuietZone::horizontal(1, 0),
+            &size,
+            orientation,
+            None,
+        )
+        .unwrap();
+        // Modules keep the size of the upright symbol, the quiet zone moves to the top.
+        assert_eq!((72.0, 180.0), (page.width, page.height));
+        assert_eq!(
+            vec![PointRect {
+                x: 0.0,
+                y: 108.0,
+                width: 72.0,
+                height: 36.0
+            }],
+            page.rects
+        );
+    }
+
+    #[test]
+    fn test_quiet_zone_mm() {
+        let page = page(Some(0.0));
+        assert_eq!((72.0, 72.0), (page.width, page.height));
+        assert_eq!((0.0, 36.0), (page.rects[0].x, page.rects[0].y));
+    }
+
+    #[test]
+    fn test_invalid_size() {
+        let size = PrintSize {
+            height_mm: 0.0,
+            quiet_zone_mm: None,
+        };
+        assert!(layout(
+            &Matrix::new(1, 1),
+            QuietZone::default(),
+            &size,
+            Orientation::default(),
+            None
+        )
+        .is_err());
+        let size = PrintSize {
+            height_mm: 10.0,
+            quiet_zone_mm: Some(-1.0),
+        };
+        assert!(layout(
+            &Matrix::new(1, 1),
+            QuietZone::default(),
+            &size,
+            Orientation::default(),
+            None
+        )
+        .is_err());
+    }
+}
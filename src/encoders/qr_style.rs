@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+use crate::encoders::matrix::Matrix;
+use crate::encoders::orientation::Orientation;
+use crate::encoders::shape::Shape;
+
+/// Side of a finder pattern in modules.
+const EYE_SIZE: usize = 7;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleStyle {
+    Square,
+    Dots,
+    Rounded,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EyeStyle {
+    Square,
+    Rounded,
+    Circle,
+}
+
+/// Shapes of the data modules and of the finder patterns, the eyes, of a QR code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QrStyle {
+    pub modules: ModuleStyle,
+    pub eyes: EyeStyle,
+}
+
+impl QrStyle {
+    pub fn is_plain(&self) -> bool {
+        self.modules == ModuleStyle::Square && self.eyes == EyeStyle::Square
+    }
+
+    /// Outlines of the dark modules in module units, for a symbol turned to `orientation`.
+    pub fn shapes(&self, symbol: &Matrix, orientation: Orientation) -> Vec<Shape> {
+        let eyes = eye_corners(symbol, orientation);
+        let in_eye = |x: usize, y: usize| {
+            eyes.iter().any(|&(ex, ey)| {
+                (ex..ex + EYE_SIZE).contains(&x) && (ey..ey + EYE_SIZE).contains(&y)
+            })
+        };
+        let mut shapes: Vec<Shape> = eyes.iter().flat_map(|&eye| self.eye(eye)).collect();
+        for y in 0..symbol.height() {
+            for x in 0..symbol.width() {
+                if symbol.get(x, y) && !in_eye(x, y) {
+                    shapes.push(self.module(x as f64, y as f64));
+                }
+            }
+        }
+        shapes
+    }
+
+    fn module(&self, x: f64, y: f64) -> Shape {
+        match self.modules {
+            ModuleStyle::Square => Shape::rect(x, y, 1.0, 1.0),
+            ModuleStyle::Dots => Shape::rounded_rect(x + 0.05, y + 0.05, 0.9, 0.9, 0.45),
+            ModuleStyle::Rounded => Shape::rounded_rect(x + 0.05, y + 0.05, 0.9, 0.9, 0.25),
+        }
+    }
+
+    /// The frame of an eye and its centre, three modules wide.
+    fn eye(&self, (x, y): (usize, usize)) -> Vec<Shape> {
+        let (x, y) = (x as f64, y as f64);
+        let size = EYE_SIZE as f64;
+        let (outer, inner, centre) = match self.eyes {
+            EyeStyle::Square => (0.0, 0.0, 0.0),
+            EyeStyle::Rounded => (2.0, 1.0, 1.0),
+            EyeStyle::Circle => (size / 2.0, size / 2.0 - 1.0, 1.5),
+        };
+        vec![
+            Shape::ring(
+                Shape::rounded_rect(x, y, size, size, outer),
+                Shape::rounded_rect(x + 1.0, y + 1.0, size - 2.0, size - 2.0, inner),
+            ),
+            Shape::rounded_rect(x + 2.0, y + 2.0, 3.0, 3.0, centre),
+        ]
+    }
+}
+
+/// Top left corners of the three finder patterns, which move with the orientation.
+fn eye_corners(symbol: &Matrix, orientation: Orientation) -> Vec<(usize, usize)> {
+    let (width, height) = (symbol.width(), symbol.height());
+    if width < EYE_SIZE || height < EYE_SIZE {
+        return Vec::new();
+    }
+    // Upright, the eyes sit in every corner but the bottom right one.
+    let upright = orientation.apply_size((width as u32, height as u32));
+    let mut marks = Matrix::new(upright.0 as usize, upright.1 as usize);
+    marks.set(0, 0, true);
+    marks.set(upright.0 as usize - 1, 0, true);
+    marks.set(0, upright.1 as usize - 1, true);
+    let marks = orientation.apply_matrix(&marks);
+    let far_x = width - EYE_SIZE;
+    let far_y = height - EYE_SIZE;
+    [
+        (0, 0, 0, 0),
+        (width - 1, 0, far_x, 0),
+        (0, height - 1, 0, far_y),
+        (width - 1, height - 1, far_x, far_y),
+    ]
+    .iter()
+    .filter(|&&(mx, my, _, _)| marks.get(mx, my))
+    .map(|&(_, _, x, y)| (x, y))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoders::orientation::Rotation;
+
+    #[test]
+    fn test_eye_corners() {
+        let symbol = Matrix::new(21, 21);
+        assert_eq!(
+            vec![(0, 0), (14, 0), (0, 14)],
+            eye_corners(&symbol, Orientation::default())
+        );
+        let orientation = Orientation {
+            rotation: Rotation::Ninety,
+            mirror: false,
+        };
+        assert_eq!(
+            vec![(0, 0), (14, 0), (14, 14)],
+            eye_corners(&symbol, orientation)
+        );
+    }
+
+    #[test]
+    fn test_shapes() {
+        let mut symbol = Matrix::new(21, 21);
+        // Modules of the top left eye and one data module.
+        symbol.set(0, 0, true);
+        symbol.set(10, 10, true);
+        let style = QrStyle {
+            modules: ModuleStyle::Dots,
+            eyes: EyeStyle::Circle,
+        };
+        let shapes = style.shapes(&symbol, Orientation::default());
+        // Frame and centre of three eyes, and the dot.
+        assert_eq!(7, shapes.len());
+        assert_eq!(2, shapes[0].subpaths.len());
+        assert_eq!(Shape::rounded_rect(10.05, 10.05, 0.9, 0.9, 0.45), shapes[6]);
+    }
+}
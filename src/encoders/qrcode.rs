@@ -1,33 +1,210 @@
-use image::{DynamicImage, ImageOutputFormat, Luma};
-use qrcode::QrCode;
+use image::DynamicImage;
+use qrcode::bits::Bits;
+use qrcode::canvas::{Canvas, MaskPattern};
+use qrcode::ec::construct_codewords;
+use qrcode::types::QrError;
+use qrcode::{Color, EcLevel, Version};
+use serde::{Deserialize, Serialize};
 
-use crate::encoders::encoder::{Encode, OutputParams};
+use crate::encoders::encoder::{Encode, Encoded, OutputParams};
+use crate::encoders::escpos;
+use crate::encoders::logo::{self, Logo};
+use crate::encoders::matrix::{Matrix, QuietZone};
+use crate::encoders::orientation::{Orientation, Rotation};
+use crate::encoders::qr_style::QrStyle;
+use crate::encoders::shape::Shape;
+use crate::encoders::zpl;
+
+const QUIET_ZONE: usize = 4;
+const MAX_VERSION: i16 = 40;
+
+/// Mask patterns in the order of their QR code pattern number.
+const MASK_PATTERNS: [MaskPattern; 8] = [
+    MaskPattern::Checkerboard,
+    MaskPattern::HorizontalLines,
+    MaskPattern::VerticalLines,
+    MaskPattern::DiagonalLines,
+    MaskPattern::LargeCheckerboard,
+    MaskPattern::Fields,
+    MaskPattern::Diamonds,
+    MaskPattern::Meadow,
+];
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum EccLevel {
+    L,
+    M,
+    Q,
+    H,
+}
+
+impl EccLevel {
+    pub fn to_qrcode(self) -> EcLevel {
+        match self {
+            EccLevel::L => EcLevel::L,
+            EccLevel::M => EcLevel::M,
+            EccLevel::Q => EcLevel::Q,
+            EccLevel::H => EcLevel::H,
+        }
+    }
+}
 
 pub struct QRCode {
     height: u32,
     payload: String,
+    ecc_level: EccLevel,
+    version: Option<i16>,
+    min_version: Option<i16>,
+    mask: Option<u8>,
+    logo: Option<Logo>,
+    style: Option<QrStyle>,
 }
 
 impl QRCode {
-    pub fn new(payload: String, height: u32) -> Self {
-        QRCode { payload, height }
+    /// `version` fixes the symbol size, otherwise the smallest version from `min_version` is used.
+    pub fn new(
+        payload: String,
+        height: u32,
+        ecc_level: EccLevel,
+        version: Option<i16>,
+        min_version: Option<i16>,
+        mask: Option<u8>,
+    ) -> Self {
+        QRCode {
+            payload,
+            height,
+            ecc_level,
+            version,
+            min_version,
+            mask,
+            logo: None,
+            style: None,
+        }
     }
-}
 
-impl Encode for QRCode {
-    fn encode(&self) -> Result<DynamicImage, String> {
-        let code = QrCode::new(self.payload.as_bytes());
-        match code {
-            Ok(code) => {
-                let mut renderer = code.render::<Luma<u8>>();
-                let image = renderer
-                    .min_dimensions(self.height, self.height)
-                    .quiet_zone(true)
-                    .build();
-                Ok(image::DynamicImage::ImageLuma8(image))
+    /// Draws the modules and the finder patterns in other shapes than squares.
+    pub fn with_style(self, style: QrStyle) -> Self {
+        QRCode {
+            style: if style.is_plain() { None } else { Some(style) },
+            ..self
+        }
+    }
+
+    /// Covers the centre with the logo, at ECC level H to restore as many modules as possible.
+    pub fn with_logo(self, logo: Logo) -> Self {
+        QRCode {
+            ecc_level: EccLevel::H,
+            logo: Some(logo),
+            ..self
+        }
+    }
+
+    fn get_bits(&self) -> Result<Bits, String> {
+        let ec_level = self.ecc_level.to_qrcode();
+        let versions = match (self.version, self.min_version) {
+            (Some(_), Some(_)) => return Err("Use either version or min_version".to_owned()),
+            (Some(version), None) => version..=version,
+            (None, min_version) => min_version.unwrap_or(1)..=MAX_VERSION,
+        };
+        if !(1..=MAX_VERSION).contains(versions.start()) {
+            return Err(format!(
+                "QR code version must be between 1 and {}, got {}",
+                MAX_VERSION,
+                versions.start()
+            ));
+        }
+        for version in versions.clone() {
+            let mut bits = Bits::new(Version::Normal(version));
+            let pushed = bits
+                .push_optimal_data(self.payload.as_bytes())
+                .and_then(|_| bits.push_terminator(ec_level));
+            match pushed {
+                Ok(_) => return Ok(bits),
+                Err(QrError::DataTooLong) => continue,
+                Err(error) => return Err(error.to_string()),
+            }
+        }
+        Err(if versions.start() == versions.end() {
+            format!(
+                "Payload does not fit in version {} with ECC level {:?}",
+                versions.start(),
+                self.ecc_level
+            )
+        } else {
+            format!(
+                "Payload does not fit in a QR code with ECC level {:?}",
+                self.ecc_level
+            )
+        })
+    }
+
+    /// Whether the payload fits the fixed version, or any version from the minimum one.
+    pub fn fits(&self) -> bool {
+        self.get_bits().is_ok()
+    }
+
+    pub fn get_matrix(&self) -> Result<Matrix, String> {
+        let mask =
+            match self.mask {
+                Some(mask) => Some(*MASK_PATTERNS.get(mask as usize).ok_or_else(|| {
+                    format!("QR code mask must be between 0 and 7, got {}", mask)
+                })?),
+                None => None,
+            };
+        let bits = self.get_bits()?;
+        let version = bits.version();
+        let mut matrix = bits_to_matrix(bits, self.ecc_level.to_qrcode(), mask)?;
+        if let (Some(logo), Version::Normal(version)) = (&self.logo, version) {
+            let area = logo.area(matrix.width());
+            logo::check_budget(version, area)?;
+            for y in area.start..area.start + area.side {
+                for x in area.start..area.start + area.side {
+                    matrix.set(x, y, false);
+                }
             }
-            Err(error) => Err(error.to_string()),
         }
+        Ok(matrix)
+    }
+}
+
+/// Adds error correction to the encoded data and places it in a QR or Micro QR symbol.
+/// Without a `mask` the pattern with the lowest penalty is chosen.
+pub fn bits_to_matrix(
+    bits: Bits,
+    ec_level: EcLevel,
+    mask: Option<MaskPattern>,
+) -> Result<Matrix, String> {
+    let version = bits.version();
+    let data = bits.into_bytes();
+    let (encoded_data, ec_data) =
+        construct_codewords(&data, version, ec_level).map_err(|e| e.to_string())?;
+
+    let mut canvas = Canvas::new(version, ec_level);
+    canvas.draw_all_functional_patterns();
+    canvas.draw_data(&encoded_data, &ec_data);
+    let canvas = match mask {
+        Some(pattern) => {
+            canvas.apply_mask(pattern);
+            canvas
+        }
+        None => canvas.apply_best_mask(),
+    };
+
+    let width = version.width() as usize;
+    let mut matrix = Matrix::new(width, width);
+    for (i, color) in canvas.into_colors().into_iter().enumerate() {
+        matrix.set(i % width, i / width, color == Color::Dark);
+    }
+    Ok(matrix)
+}
+
+impl Encode for QRCode {
+    fn symbol(&self) -> Result<Matrix, String> {
+        self.get_matrix()
+    }
+
+    fn quiet_zone(&self) -> QuietZone {
+        QuietZone::uniform(QUIET_ZONE)
     }
 
     fn payload(&self) -> &str {
@@ -36,20 +213,92 @@ impl Encode for QRCode {
 
     fn get_params(&self) -> OutputParams {
         OutputParams {
-            format: ImageOutputFormat::PNG,
-            append_text: true,
+            append_text: false,
+            font_size: 0,
+            height: self.height,
         }
     }
+
+    fn shapes(&self, symbol: &Matrix, orientation: Orientation) -> Option<Vec<Shape>> {
+        self.style.map(|style| style.shapes(symbol, orientation))
+    }
+
+    fn decorate(&self, image: DynamicImage, code: &Encoded) -> Result<DynamicImage, String> {
+        let logo = match &self.logo {
+            Some(logo) => logo,
+            None => return Ok(image),
+        };
+        let area = logo.area(code.symbol.width());
+        let quiet_zone = code.quiet_zone;
+        let (module_width, module_height) = code.module_size()?;
+        let rect = (
+            (quiet_zone.left + area.start) as u32 * module_width,
+            (quiet_zone.top + area.start) as u32 * module_height,
+            area.side as u32 * module_width,
+            area.side as u32 * module_height,
+        );
+        Ok(logo.draw(image, rect))
+    }
+
+    /// `^BQ` picks the version itself, draws square modules only and cannot rotate.
+    fn zpl(&self, code: &Encoded) -> Result<Option<String>, String> {
+        let (module, _) = code.module_size()?;
+        let native = self.logo.is_none()
+            && self.style.is_none()
+            && self.version.is_none()
+            && self.min_version.is_none()
+            && code.options.orientation.rotation == Rotation::Zero
+            && module <= zpl::MAX_MODULE;
+        if !native {
+            return Ok(None);
+        }
+        let mask = self
+            .mask
+            .map(|mask| format!(",{}", mask))
+            .unwrap_or_default();
+        Ok(Some(format!(
+            "^BQN,2,{},{:?}{}{}",
+            module,
+            self.ecc_level,
+            mask,
+            // Error correction level and automatic data input.
+            zpl::field_data(&format!("{:?}A,{}", self.ecc_level, self.payload))
+        )))
+    }
+
+    /// `GS ( k` picks the version and the mask itself, draws square modules only and
+    /// cannot rotate.
+    fn escpos(&self, code: &Encoded) -> Result<Option<Vec<u8>>, String> {
+        let (module, _) = code.module_size()?;
+        let native = self.logo.is_none()
+            && self.style.is_none()
+            && self.version.is_none()
+            && self.min_version.is_none()
+            && self.mask.is_none()
+            && code.options.orientation.rotation == Rotation::Zero
+            && module <= escpos::MAX_QR_MODULE;
+        if !native {
+            return Ok(None);
+        }
+        Ok(Some(escpos::qr(
+            self.payload.as_bytes(),
+            module,
+            self.ecc_level,
+        )))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::encoders::encoder::RenderOptions;
 
     #[test]
     fn test_encode() {
-        let qrcode = QRCode::new("a".to_owned(), 10);
-        let image = qrcode.encode().unwrap();
+        let qrcode = QRCode::new("a".to_owned(), 10, EccLevel::M, None, None, None);
+        let image = Encoded::new(&qrcode, RenderOptions::default())
+            .and_then(|code| code.image())
+            .unwrap();
         let expected_bytes = vec![
             255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
             255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
@@ -98,4 +347,55 @@ mod tests {
         ];
         assert_eq!(expected_bytes, image.raw_pixels());
     }
+
+    fn qrcode(payload: &str, ecc_level: EccLevel) -> QRCode {
+        QRCode::new(payload.to_owned(), 10, ecc_level, None, None, None)
+    }
+
+    #[test]
+    fn test_ecc_level() {
+        // Fits version 1 at level L, but needs version 3 at level H.
+        let payload = "0123456789abcdefghij";
+        assert_eq!(
+            21,
+            qrcode(payload, EccLevel::L).get_matrix().unwrap().width()
+        );
+        assert_eq!(
+            29,
+            qrcode(payload, EccLevel::H).get_matrix().unwrap().width()
+        );
+        assert!(qrcode(&"a".repeat(2000), EccLevel::H).get_matrix().is_err());
+    }
+
+    #[test]
+    fn test_version() {
+        let fixed = QRCode::new("a".to_owned(), 10, EccLevel::M, Some(5), None, None);
+        assert_eq!(37, fixed.get_matrix().unwrap().width());
+        let minimum = QRCode::new("a".to_owned(), 10, EccLevel::M, None, Some(3), None);
+        assert_eq!(29, minimum.get_matrix().unwrap().width());
+        let too_small = QRCode::new("a".repeat(100), 10, EccLevel::M, Some(1), None, None);
+        assert_eq!(
+            Err("Payload does not fit in version 1 with ECC level M".to_owned()),
+            too_small.get_matrix().map(|_| ())
+        );
+        let invalid = QRCode::new("a".to_owned(), 10, EccLevel::M, Some(41), None, None);
+        assert!(invalid.get_matrix().is_err());
+    }
+
+    #[test]
+    fn test_mask() {
+        let matrices: Vec<Vec<bool>> = (0..8)
+            .map(|mask| {
+                let matrix = QRCode::new("a".to_owned(), 10, EccLevel::M, None, None, Some(mask))
+                    .get_matrix()
+                    .unwrap();
+                (0..21 * 21).map(|i| matrix.get(i % 21, i / 21)).collect()
+            })
+            .collect();
+        for (i, matrix) in matrices.iter().enumerate() {
+            assert!(matrices[i + 1..].iter().all(|other| other != matrix));
+        }
+        let invalid = QRCode::new("a".to_owned(), 10, EccLevel::M, None, None, Some(8));
+        assert!(invalid.get_matrix().is_err());
+    }
 }
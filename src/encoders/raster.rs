@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use image::tiff::TiffEncoder;
+use image::{ColorType, DynamicImage, ImageOutputFormat, Rgb, RgbImage, RgbaImage};
+use image_webp::WebPEncoder;
+
+use crate::encoders::color::{self, Color};
+use crate::encoders::encoder::Encoded;
+use crate::encoders::sizing;
+
+/// Sharp module edges need a high quality, lower ones blur them with ringing.
+const JPEG_QUALITY: u8 = 95;
+/// Speed of the colour quantization for GIF images of more than 256 colours, from 1 to 30.
+const GIF_QUANTIZATION_SPEED: i32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RasterFormat {
+    Png,
+    /// PNG with a palette of the foreground and background colours, one bit per pixel.
+    MonochromePng,
+    Jpeg,
+    Gif,
+    Bmp,
+    Tiff,
+    /// Lossless WebP, which keeps the module edges sharp.
+    Webp,
+}
+
+impl RasterFormat {
+    /// Format named by the `format` query parameter.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "png" => Some(RasterFormat::Png),
+            "jpeg" | "jpg" => Some(RasterFormat::Jpeg),
+            "gif" => Some(RasterFormat::Gif),
+            "bmp" => Some(RasterFormat::Bmp),
+            "tiff" | "tif" => Some(RasterFormat::Tiff),
+            "webp" => Some(RasterFormat::Webp),
+            _ => None,
+        }
+    }
+
+    /// Format of an `image/*` media type of the `Accept` header.
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "image/*" | "image/png" | "image/apng" => Some(RasterFormat::Png),
+            "image/jpeg" | "image/jpg" | "image/pjpeg" => Some(RasterFormat::Jpeg),
+            "image/gif" => Some(RasterFormat::Gif),
+            "image/bmp" | "image/x-bmp" | "image/x-ms-bmp" => Some(RasterFormat::Bmp),
+            "image/tiff" | "image/tiff-fx" => Some(RasterFormat::Tiff),
+            "image/webp" => Some(RasterFormat::Webp),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            RasterFormat::Png | RasterFormat::MonochromePng => "image/png",
+            RasterFormat::Jpeg => "image/jpeg",
+            RasterFormat::Gif => "image/gif",
+            RasterFormat::Bmp => "image/bmp",
+            RasterFormat::Tiff => "image/tiff",
+            RasterFormat::Webp => "image/webp",
+        }
+    }
+}
+
+/// Coloured image of the symbol turned to its orientation, PNG images carry the requested DPI.
+pub fn output(code: &Encoded, format: RasterFormat) -> Result<Vec<u8>, String> {
+    let options = &code.options;
+    let image = color::colorize(code.render()?, options.foreground, options.background);
+    let image = options
+        .orientation
+        .apply_image(code.encoder.decorate(image, code)?);
+
+    let bytes = write(&image, format, options.foreground, options.background)?;
+    match (options.sizing.dpi, format) {
+        (Some(dpi), RasterFormat::Png) | (Some(dpi), RasterFormat::MonochromePng) => {
+            Ok(sizing::set_png_dpi(&bytes, dpi))
+        }
+        _ => Ok(bytes),
+    }
+}
+
+/// Encodes the coloured image, the monochrome PNG keeps only `foreground` and `background`.
+pub fn write(
+    image: &DynamicImage,
+    format: RasterFormat,
+    foreground: Color,
+    background: Color,
+) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let result = match format {
+        RasterFormat::Png => image.write_to(&mut bytes, ImageOutputFormat::PNG),
+        RasterFormat::MonochromePng => return write_monochrome_png(image, foreground, background),
+        // JPEG has no alpha channel, transparent pixels would show their colour.
+        RasterFormat::Jpeg => DynamicImage::ImageRgb8(flatten(image))
+            .write_to(&mut bytes, ImageOutputFormat::JPEG(JPEG_QUALITY)),
+        RasterFormat::Gif => return write_gif(image),
+        RasterFormat::Bmp => image.write_to(&mut bytes, ImageOutputFormat::BMP),
+        RasterFormat::Tiff => {
+            let mut cursor = Cursor::new(Vec::new());
+            let result = match image {
+                DynamicImage::ImageLuma8(gray) => TiffEncoder::new(&mut cursor).encode(
+                    gray,
+                    gray.width(),
+                    gray.height(),
+                    ColorType::Gray(8),
+                ),
+                _ => {
+                    let rgba = image.to_rgba();
+                    TiffEncoder::new(&mut cursor).encode(
+                        &rgba,
+                        rgba.width(),
+                        rgba.height(),
+                        ColorType::RGBA(8),
+                    )
+                }
+            };
+            bytes = cursor.into_inner();
+            result
+        }
+        RasterFormat::Webp => return write_webp(image),
+    };
+    result.map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+fn write_webp(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let result = match image {
+        DynamicImage::ImageLuma8(gray) => WebPEncoder::new(&mut bytes).encode(
+            gray,
+            gray.width(),
+            gray.height(),
+            image_webp::ColorType::L8,
+        ),
+        _ => {
+            let rgba = image.to_rgba();
+            WebPEncoder::new(&mut bytes).encode(
+                &rgba,
+                rgba.width(),
+                rgba.height(),
+                image_webp::ColorType::Rgba8,
+            )
+        }
+    };
+    result.map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// GIF with the colours of the image as its palette, pixels are either opaque or transparent.
+/// Only images of more than 256 colours, such as those with a photo as logo, are quantized.
+fn write_gif(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut rgba = image.to_rgba();
+    let (width, height) = rgba.dimensions();
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(format!(
+            "GIF images are at most {} pixels wide and high",
+            u16::MAX
+        ));
+    }
+    let (width, height) = (width as u16, height as u16);
+
+    let frame = match palette_frame(&rgba, width, height) {
+        Some(frame) => frame,
+        None => gif::Frame::from_rgba_speed(width, height, &mut rgba, GIF_QUANTIZATION_SPEED),
+    };
+
+    let mut bytes = Vec::new();
+    {
+        let mut encoder =
+            gif::Encoder::new(&mut bytes, width, height, &[]).map_err(|e| e.to_string())?;
+        encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+    }
+    Ok(bytes)
+}
+
+/// Frame indexing the colours of the image, none when there are more than 256.
+fn palette_frame(rgba: &RgbaImage, width: u16, height: u16) -> Option<gif::Frame<'static>> {
+    let mut indices: HashMap<[u8; 4], u8> = HashMap::new();
+    let mut palette = Vec::new();
+    let mut transparent = None;
+    let mut buffer = Vec::with_capacity(rgba.len() / 4);
+    for pixel in rgba.pixels() {
+        let key = if pixel[3] == 0 {
+            [0, 0, 0, 0]
+        } else {
+            [pixel[0], pixel[1], pixel[2], 255]
+        };
+        let index = match indices.get(&key) {
+            Some(&index) => index,
+            None if indices.len() < 256 => {
+                let index = indices.len() as u8;
+                indices.insert(key, index);
+                palette.extend_from_slice(&key[..3]);
+                if key[3] == 0 {
+                    transparent = Some(index);
+                }
+                index
+            }
+            None => return None,
+        };
+        buffer.push(index);
+    }
+    Some(gif::Frame::from_palette_pixels(
+        width,
+        height,
+        &buffer,
+        &palette,
+        transparent,
+    ))
+}
+
+/// The image on a white page.
+fn flatten(image: &DynamicImage) -> RgbImage {
+    let rgba = image.to_rgba();
+    let mut flat = RgbImage::new(rgba.width(), rgba.height());
+    for (x, y, pixel) in rgba.enumerate_pixels() {
+        let alpha = pixel[3] as u32;
+        let mix = |c: u8| ((c as u32 * alpha + 255 * (255 - alpha) + 127) / 255) as u8;
+        flat.put_pixel(x, y, Rgb([mix(pixel[0]), mix(pixel[1]), mix(pixel[2])]));
+    }
+    flat
+}
+
+/// Indexed PNG of bit depth 1, every pixel becomes the nearer of the two colours.
+/// Anti-aliased edges, text and logos are snapped to them.
+fn write_monochrome_png(
+    image: &DynamicImage,
+    foreground: Color,
+    background: Color,
+) -> Result<Vec<u8>, String> {
+    let rgba = image.to_rgba();
+    let (width, height) = rgba.dimensions();
+    let distance = |pixel: &[u8], color: Color| -> u32 {
+        [color.r, color.g, color.b, color.a]
+            .iter()
+            .zip(pixel)
+            .map(|(&c, &p)| (c as i32 - p as i32).pow(2) as u32)
+            .sum()
+    };
+    let row_bytes = (width as usize).div_ceil(8);
+    let mut data = vec![0u8; row_bytes * height as usize];
+    for (x, y, pixel) in rgba.enumerate_pixels() {
+        if distance(&pixel.0, foreground) < distance(&pixel.0, background) {
+            data[y as usize * row_bytes + x as usize / 8] |= 0x80 >> (x % 8);
+        }
+    }
+
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::One);
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        // Index 0 is the background, 1 the foreground.
+        let palette = [
+            background.r,
+            background.g,
+            background.b,
+            foreground.r,
+            foreground.g,
+            foreground.b,
+        ];
+        writer
+            .write_chunk(*b"PLTE", &palette)
+            .map_err(|e| e.to_string())?;
+        if background.a < 255 || foreground.a < 255 {
+            writer
+                .write_chunk(*b"tRNS", &[background.a, foreground.a])
+                .map_err(|e| e.to_string())?;
+        }
+        writer.write_image_data(&data).map_err(|e| e.to_string())?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GenericImageView, GrayImage, Luma};
+
+    use super::*;
+    use crate::encoders::color::{BLACK, WHITE};
+
+    fn checkerboard() -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(10, 3, |x, y| {
+            Luma([if (x + y) % 2 == 0 { 0 } else { 255 }])
+        }))
+    }
+
+    #[test]
+    fn test_signatures() {
+        let image = checkerboard();
+        for (format, signature) in &[
+            (RasterFormat::Png, &b"\x89PNG"[..]),
+            (RasterFormat::MonochromePng, &b"\x89PNG"[..]),
+            (RasterFormat::Jpeg, &b"\xff\xd8\xff"[..]),
+            (RasterFormat::Gif, &b"GIF8"[..]),
+            (RasterFormat::Bmp, &b"BM"[..]),
+            (RasterFormat::Tiff, &b"II*\0"[..]),
+            (RasterFormat::Webp, &b"RIFF"[..]),
+        ] {
+            let bytes = write(&image, *format, BLACK, WHITE).unwrap();
+            assert!(bytes.starts_with(signature), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_monochrome_png() {
+        let foreground = Color::parse("1a237e").unwrap();
+        let background = Color::parse("ffffff00").unwrap();
+        let image = crate::encoders::color::colorize(checkerboard(), foreground, background);
+        let bytes = write(&image, RasterFormat::MonochromePng, foreground, background).unwrap();
+        // Bit depth 1 and colour type 3, indexed.
+        assert_eq!([1, 3], bytes[24..26]);
+        let decoded = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((10, 3), decoded.dimensions());
+        let decoded = decoded.to_rgba();
+        assert_eq!([0x1a, 0x23, 0x7e, 255], decoded.get_pixel(0, 0).0);
+        assert_eq!(0, decoded.get_pixel(1, 0)[3]);
+        assert_eq!([0x1a, 0x23, 0x7e, 255], decoded.get_pixel(8, 2).0);
+    }
+
+    #[test]
+    fn test_gif_palette() {
+        let background = Color::parse("ffffff00").unwrap();
+        let image = crate::encoders::color::colorize(checkerboard(), BLACK, background);
+        let bytes = write(&image, RasterFormat::Gif, BLACK, background).unwrap();
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgba();
+        assert_eq!([0, 0, 0, 255], decoded.get_pixel(0, 0).0);
+        assert_eq!(0, decoded.get_pixel(1, 0)[3]);
+
+        // Every grey level and the transparent pixel do not fit a palette.
+        let gradient = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(257, 1, |x, _| {
+            let level = x.min(255) as u8;
+            image::Rgba([level, level, level, if x == 256 { 0 } else { 255 }])
+        }));
+        let bytes = write(&gradient, RasterFormat::Gif, BLACK, WHITE).unwrap();
+        assert!(image::load_from_memory(&bytes).is_ok());
+    }
+
+    #[test]
+    fn test_webp_lossless() {
+        let background = Color::parse("ffffff00").unwrap();
+        let image = crate::encoders::color::colorize(checkerboard(), BLACK, background);
+        let bytes = write(&image, RasterFormat::Webp, BLACK, background).unwrap();
+        let mut decoder = image_webp::WebPDecoder::new(Cursor::new(bytes)).unwrap();
+        assert_eq!((10, 3), decoder.dimensions());
+        let mut decoded = vec![0; decoder.output_buffer_size().unwrap()];
+        decoder.read_image(&mut decoded).unwrap();
+        assert_eq!(image.to_rgba().into_raw(), decoded);
+    }
+
+    #[test]
+    fn test_jpeg_flattens_transparency() {
+        let background = Color::parse("00000000").unwrap();
+        let image = crate::encoders::color::colorize(checkerboard(), BLACK, background);
+        let flat = flatten(&image);
+        assert_eq!(Rgb([0, 0, 0]), *flat.get_pixel(0, 0));
+        assert_eq!(Rgb([255, 255, 255]), *flat.get_pixel(1, 0));
+    }
+
+    #[test]
+    fn test_media_types() {
+        assert_eq!(
+            Some(RasterFormat::Jpeg),
+            RasterFormat::from_media_type("image/jpeg")
+        );
+        assert_eq!(
+            Some(RasterFormat::Webp),
+            RasterFormat::from_media_type("image/webp")
+        );
+        assert_eq!(None, RasterFormat::from_media_type("image/avif"));
+        assert_eq!(Some(RasterFormat::Tiff), RasterFormat::from_name("tif"));
+        assert_eq!("image/png", RasterFormat::MonochromePng.content_type());
+    }
+}
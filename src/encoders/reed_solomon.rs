@@ -0,0 +1,103 @@
+/// Galois field GF(2^m) used to compute Reed-Solomon error correction words.
+pub struct GaloisField {
+    exp: Vec<u16>,
+    log: Vec<u16>,
+    base: usize,
+}
+
+impl GaloisField {
+    /// Creates a field of `size` elements generated by the `primitive` polynomial.
+    /// `base` is the exponent of the first root of the generator polynomial.
+    pub fn new(primitive: u32, size: usize, base: usize) -> Self {
+        let mut exp = vec![0u16; size];
+        let mut log = vec![0u16; size];
+        let mut x: u32 = 1;
+        for value in exp.iter_mut() {
+            *value = x as u16;
+            x <<= 1;
+            if x as usize >= size {
+                x ^= primitive;
+                x &= size as u32 - 1;
+            }
+        }
+        for (i, &value) in exp.iter().enumerate().take(size - 1) {
+            log[value as usize] = i as u16;
+        }
+        GaloisField { exp, log, base }
+    }
+
+    pub fn multiply(&self, a: u16, b: u16) -> u16 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        let order = self.exp.len() - 1;
+        let index = (self.log[a as usize] as usize + self.log[b as usize] as usize) % order;
+        self.exp[index]
+    }
+
+    /// Returns `alpha^power`.
+    pub fn exp(&self, power: usize) -> u16 {
+        self.exp[power % (self.exp.len() - 1)]
+    }
+
+    /// Coefficients of the generator polynomial, highest degree first.
+    fn generator(&self, degree: usize) -> Vec<u16> {
+        let mut generator = vec![1u16];
+        for i in 0..degree {
+            let root = self.exp(self.base + i);
+            let mut next = vec![0u16; generator.len() + 1];
+            for (j, &coefficient) in generator.iter().enumerate() {
+                next[j] ^= coefficient;
+                next[j + 1] ^= self.multiply(coefficient, root);
+            }
+            generator = next;
+        }
+        generator
+    }
+
+    /// Computes `ec_count` error correction words for `data`.
+    pub fn encode(&self, data: &[u16], ec_count: usize) -> Vec<u16> {
+        let generator = self.generator(ec_count);
+        let mut remainder = vec![0u16; ec_count];
+        for &word in data {
+            let factor = word ^ remainder[0];
+            remainder.remove(0);
+            remainder.push(0);
+            for (value, &coefficient) in remainder.iter_mut().zip(generator[1..].iter()) {
+                *value ^= self.multiply(coefficient, factor);
+            }
+        }
+        remainder
+    }
+
+    /// Evaluates the syndromes of a complete codeword, they are all zero for valid data.
+    #[cfg(test)]
+    pub fn syndromes(&self, codeword: &[u16], ec_count: usize) -> Vec<u16> {
+        (0..ec_count)
+            .map(|i| {
+                let root = self.exp(self.base + i);
+                codeword
+                    .iter()
+                    .fold(0, |acc, &word| self.multiply(acc, root) ^ word)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let field = GaloisField::new(0x12D, 256, 1);
+        // Example from ISO/IEC 16022 Annex O: "123456" in a 10x10 Data Matrix.
+        let data = vec![142, 164, 186];
+        let ec = field.encode(&data, 5);
+        assert_eq!(vec![114, 25, 5, 88, 102], ec);
+
+        let mut codeword = data;
+        codeword.extend(ec);
+        assert!(field.syndromes(&codeword, 5).iter().all(|&s| s == 0));
+    }
+}
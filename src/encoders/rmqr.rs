@@ -0,0 +1,586 @@
+use qrcode::optimize::{Parser, Segment};
+use qrcode::types::Mode;
+
+use crate::encoders::encoder::{Encode, OutputParams};
+use crate::encoders::matrix::{Matrix, QuietZone};
+use crate::encoders::qrcode::EccLevel;
+use crate::encoders::reed_solomon::GaloisField;
+
+const QUIET_ZONE: usize = 2;
+const VERSIONS: usize = 32;
+const ALPHANUMERIC: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+const PAD: [u8; 2] = [0xEC, 0x11];
+
+/// Generator of the BCH(18, 6) code of the format information.
+const FORMAT_GENERATOR: u32 = 0x1F25;
+/// Masks of the format information next to the finder and to the sub-finder pattern.
+const FORMAT_MASK_LEFT: u32 = 0x1FAB2;
+const FORMAT_MASK_RIGHT: u32 = 0x20A7B;
+
+struct SymbolInfo {
+    height: usize,
+    width: usize,
+    codewords: usize,
+    /// Data codewords and error correction blocks at ECC level M.
+    data_m: usize,
+    blocks_m: usize,
+    /// Data codewords and error correction blocks at ECC level H.
+    data_h: usize,
+    blocks_h: usize,
+}
+
+const fn symbol(
+    height: usize,
+    width: usize,
+    codewords: usize,
+    data_m: usize,
+    blocks_m: usize,
+    data_h: usize,
+    blocks_h: usize,
+) -> SymbolInfo {
+    SymbolInfo {
+        height,
+        width,
+        codewords,
+        data_m,
+        blocks_m,
+        data_h,
+        blocks_h,
+    }
+}
+
+/// Symbol sizes R7x43 to R17x139 of ISO/IEC 23941 Table 6 and Table 8, in version order.
+const SYMBOLS: [SymbolInfo; VERSIONS] = [
+    symbol(7, 43, 13, 6, 1, 3, 1),
+    symbol(7, 59, 21, 12, 1, 7, 1),
+    symbol(7, 77, 32, 20, 1, 10, 1),
+    symbol(7, 99, 44, 28, 1, 14, 1),
+    symbol(7, 139, 68, 44, 1, 24, 2),
+    symbol(9, 43, 21, 12, 1, 7, 1),
+    symbol(9, 59, 33, 21, 1, 11, 1),
+    symbol(9, 77, 49, 31, 1, 17, 2),
+    symbol(9, 99, 66, 42, 1, 22, 2),
+    symbol(9, 139, 99, 63, 2, 33, 3),
+    symbol(11, 27, 15, 7, 1, 5, 1),
+    symbol(11, 43, 31, 19, 1, 11, 1),
+    symbol(11, 59, 47, 31, 1, 15, 2),
+    symbol(11, 77, 67, 43, 1, 23, 2),
+    symbol(11, 99, 89, 57, 2, 29, 2),
+    symbol(11, 139, 132, 84, 2, 42, 3),
+    symbol(13, 27, 21, 12, 1, 7, 1),
+    symbol(13, 43, 41, 27, 1, 13, 1),
+    symbol(13, 59, 60, 38, 1, 20, 2),
+    symbol(13, 77, 85, 53, 2, 29, 2),
+    symbol(13, 99, 113, 73, 2, 35, 3),
+    symbol(13, 139, 166, 106, 3, 54, 4),
+    symbol(15, 43, 51, 33, 1, 15, 2),
+    symbol(15, 59, 74, 48, 1, 26, 2),
+    symbol(15, 77, 103, 67, 2, 31, 3),
+    symbol(15, 99, 136, 88, 2, 48, 4),
+    symbol(15, 139, 199, 127, 3, 69, 5),
+    symbol(17, 43, 61, 39, 1, 21, 2),
+    symbol(17, 59, 88, 56, 2, 28, 2),
+    symbol(17, 77, 122, 78, 2, 38, 3),
+    symbol(17, 99, 160, 100, 3, 56, 4),
+    symbol(17, 139, 232, 152, 4, 76, 6),
+];
+
+/// Lengths of the character count indicator in numeric, alphanumeric and byte mode, by version.
+const COUNT_BITS: [[usize; VERSIONS]; 3] = [
+    [
+        4, 5, 6, 7, 7, 5, 6, 7, 7, 8, 4, 6, 7, 7, 8, 8, 5, 6, 7, 7, 8, 8, 7, 7, 8, 8, 9, 7, 8, 8,
+        8, 9,
+    ],
+    [
+        3, 5, 5, 6, 6, 5, 5, 6, 6, 7, 4, 5, 6, 6, 7, 7, 5, 6, 6, 7, 7, 8, 6, 7, 7, 7, 8, 6, 7, 7,
+        8, 8,
+    ],
+    [
+        3, 4, 5, 5, 6, 4, 5, 5, 6, 6, 3, 5, 5, 6, 6, 7, 4, 5, 6, 6, 7, 7, 6, 6, 7, 7, 7, 6, 6, 7,
+        7, 8,
+    ],
+];
+
+impl SymbolInfo {
+    fn name(&self) -> String {
+        format!("R{}x{}", self.height, self.width)
+    }
+
+    fn data_codewords(&self, ecc_level: EccLevel) -> usize {
+        if ecc_level == EccLevel::H {
+            self.data_h
+        } else {
+            self.data_m
+        }
+    }
+
+    fn blocks(&self, ecc_level: EccLevel) -> usize {
+        if ecc_level == EccLevel::H {
+            self.blocks_h
+        } else {
+            self.blocks_m
+        }
+    }
+
+    /// Columns of the alignment patterns, which only depend on the width.
+    fn alignment_columns(&self) -> &'static [usize] {
+        match self.width {
+            43 => &[21],
+            59 => &[19, 39],
+            77 => &[25, 51],
+            99 => &[23, 49, 75],
+            139 => &[27, 55, 83, 111],
+            _ => &[],
+        }
+    }
+}
+
+fn append_bits(bits: &mut Vec<bool>, value: u32, count: usize) {
+    for i in (0..count).rev() {
+        bits.push(value & (1 << i) != 0);
+    }
+}
+
+/// Mode indicator and index into `COUNT_BITS`. Shift JIS is not detected reliably in UTF-8
+/// text, so Kanji segments are written in byte mode.
+fn mode_index(mode: Mode) -> usize {
+    match mode {
+        Mode::Numeric => 0,
+        Mode::Alphanumeric => 1,
+        Mode::Byte | Mode::Kanji => 2,
+    }
+}
+
+fn data_len(mode: Mode, count: usize) -> usize {
+    match mode {
+        Mode::Numeric => count / 3 * 10 + [0, 4, 7][count % 3],
+        Mode::Alphanumeric => count / 2 * 11 + count % 2 * 6,
+        Mode::Byte | Mode::Kanji => count * 8,
+    }
+}
+
+fn segment_len(segment: &Segment, version: usize) -> usize {
+    3 + COUNT_BITS[mode_index(segment.mode)][version]
+        + data_len(segment.mode, segment.end - segment.begin)
+}
+
+/// Splits the data into segments, merging neighbours whenever a single segment is shorter.
+fn segments(data: &[u8], version: usize) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    for segment in Parser::new(data) {
+        if let Some(last) = segments.last_mut() {
+            let merged = Segment {
+                mode: last.mode.max(segment.mode),
+                begin: last.begin,
+                end: segment.end,
+            };
+            if segment_len(&merged, version)
+                <= segment_len(last, version) + segment_len(&segment, version)
+            {
+                *last = merged;
+                continue;
+            }
+        }
+        segments.push(segment);
+    }
+    segments
+}
+
+fn encoded_len(segments: &[Segment], version: usize) -> usize {
+    segments.iter().map(|s| segment_len(s, version)).sum()
+}
+
+/// BCH code of the format information, `data` is the version index plus 32 for ECC level H.
+fn format_info(data: u32, mask: u32) -> u32 {
+    let mut remainder = data << 12;
+    for i in (12..18).rev() {
+        if remainder & (1 << i) != 0 {
+            remainder ^= FORMAT_GENERATOR << (i - 12);
+        }
+    }
+    ((data << 12) | remainder) ^ mask
+}
+
+/// Finder, sub-finder, timing and alignment patterns, as dark modules and function modules.
+fn function_patterns(symbol: &SymbolInfo) -> (Matrix, Matrix) {
+    let (width, height) = (symbol.width, symbol.height);
+    let mut dark = Matrix::new(width, height);
+    let mut function = Matrix::new(width, height);
+    let mut set = |x: usize, y: usize, value: bool| {
+        dark.set(x, y, value);
+        function.set(x, y, true);
+    };
+
+    for x in 0..width {
+        set(x, 0, x % 2 == 0);
+        set(x, height - 1, x % 2 == 0);
+    }
+    for y in 0..height {
+        set(0, y, y % 2 == 0);
+        set(width - 1, y, y % 2 == 0);
+    }
+    for y in 0..7 {
+        for x in 0..7 {
+            let ring = x.min(y).min(6 - x).min(6 - y);
+            set(x, y, ring != 1);
+        }
+    }
+    for y in 0..5 {
+        for x in 0..5 {
+            let ring = x.min(y).min(4 - x).min(4 - y);
+            set(width - 5 + x, height - 5 + y, ring != 1);
+        }
+    }
+    // Corner patterns bottom left and top right.
+    set(0, height - 2, true);
+    set(1, height - 2, false);
+    set(1, height - 1, true);
+    set(width - 2, 0, true);
+    set(width - 2, 1, false);
+    set(width - 1, 1, true);
+    // Separator of the finder pattern.
+    for y in 0..7 {
+        set(7, y, false);
+    }
+    if height > 7 {
+        for x in 0..8 {
+            set(x, 7, false);
+        }
+    }
+    for &center in symbol.alignment_columns() {
+        for y in 0..height {
+            set(center, y, y % 2 == 0);
+        }
+        for &y in &[1, 2, height - 3, height - 2] {
+            set(center - 1, y, true);
+            set(center + 1, y, true);
+        }
+    }
+    // Format information, written after the mask.
+    for y in 0..5 {
+        for x in 0..3 {
+            set(8 + x, 1 + y, false);
+            set(width - 8 + x, height - 6 + y, false);
+        }
+    }
+    for i in 0..3 {
+        set(11, 1 + i, false);
+        set(width - 5 + i, height - 6, false);
+    }
+    (dark, function)
+}
+
+pub struct Rmqr {
+    height: u32,
+    payload: String,
+    ecc_level: EccLevel,
+    version: Option<i16>,
+}
+
+impl Rmqr {
+    /// `version` from 1 to 32 fixes the symbol to R7x43-R17x139, otherwise the one with
+    /// the smallest area is used.
+    pub fn new(payload: String, height: u32, ecc_level: EccLevel, version: Option<i16>) -> Self {
+        Rmqr {
+            payload,
+            height,
+            ecc_level,
+            version,
+        }
+    }
+
+    /// Picks the version and encodes the payload into its data codewords.
+    fn get_data(&self) -> Result<(usize, Vec<u8>), String> {
+        if self.ecc_level == EccLevel::L || self.ecc_level == EccLevel::Q {
+            return Err(format!(
+                "rMQR does not support ECC level {:?}, use M or H",
+                self.ecc_level
+            ));
+        }
+        let versions = match self.version {
+            Some(version) if (1..=VERSIONS as i16).contains(&version) => {
+                vec![version as usize - 1]
+            }
+            Some(version) => {
+                return Err(format!(
+                    "rMQR version must be between 1 and {}, got {}",
+                    VERSIONS, version
+                ))
+            }
+            None => {
+                let mut versions: Vec<usize> = (0..VERSIONS).collect();
+                versions.sort_by_key(|&v| SYMBOLS[v].height * SYMBOLS[v].width);
+                versions
+            }
+        };
+
+        let data = self.payload.as_bytes();
+        let fits = |version: usize| {
+            let needed = encoded_len(&segments(data, version), version);
+            needed <= SYMBOLS[version].data_codewords(self.ecc_level) * 8
+        };
+        match versions.iter().find(|&&version| fits(version)) {
+            Some(&version) => Ok((version, self.codewords(version))),
+            // The largest symbol holds the most, it is the last one checked unless fixed.
+            None => Err(self.capacity_error(*versions.last().unwrap())),
+        }
+    }
+
+    /// Explains why the payload does not fit in the symbol of `version`.
+    fn capacity_error(&self, version: usize) -> String {
+        let needed = encoded_len(&segments(self.payload.as_bytes(), version), version);
+        let symbol = &SYMBOLS[version];
+        let capacity = symbol.data_codewords(self.ecc_level) * 8;
+        format!(
+            "Payload needs {} bits, but rMQR {} with ECC level {:?} holds {}, exceeded by {} bits",
+            needed,
+            symbol.name(),
+            self.ecc_level,
+            capacity,
+            needed - capacity
+        )
+    }
+
+    /// Segments, terminator and padding, which fill the data codewords of `version`.
+    fn codewords(&self, version: usize) -> Vec<u8> {
+        let data = self.payload.as_bytes();
+        let capacity = SYMBOLS[version].data_codewords(self.ecc_level);
+        let mut bits = Vec::new();
+        for segment in segments(data, version) {
+            let index = mode_index(segment.mode);
+            let count = segment.end - segment.begin;
+            append_bits(&mut bits, index as u32 + 1, 3);
+            append_bits(&mut bits, count as u32, COUNT_BITS[index][version]);
+            let chunk = &data[segment.begin..segment.end];
+            match segment.mode {
+                Mode::Numeric => {
+                    for digits in chunk.chunks(3) {
+                        let value = digits.iter().fold(0, |v, &d| v * 10 + (d - b'0') as u32);
+                        append_bits(&mut bits, value, data_len(Mode::Numeric, digits.len()));
+                    }
+                }
+                Mode::Alphanumeric => {
+                    for pair in chunk.chunks(2) {
+                        let value = pair.iter().fold(0, |v, c| {
+                            v * 45 + ALPHANUMERIC.iter().position(|a| a == c).unwrap() as u32
+                        });
+                        append_bits(&mut bits, value, data_len(Mode::Alphanumeric, pair.len()));
+                    }
+                }
+                Mode::Byte | Mode::Kanji => {
+                    for &byte in chunk {
+                        append_bits(&mut bits, byte as u32, 8);
+                    }
+                }
+            }
+        }
+        // The terminator is cut short when the symbol is full.
+        let terminator = (capacity * 8 - bits.len()).min(3);
+        append_bits(&mut bits, 0, terminator);
+        while bits.len() % 8 != 0 {
+            bits.push(false);
+        }
+        let mut codewords: Vec<u8> = bits
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |v, &bit| v << 1 | bit as u8))
+            .collect();
+        for i in 0..capacity - codewords.len() {
+            codewords.push(PAD[i % 2]);
+        }
+        codewords
+    }
+
+    /// Splits the data into blocks, the longer ones last, and interleaves them with their
+    /// error correction codewords.
+    fn add_error_correction(&self, version: usize, data: &[u8]) -> Vec<u8> {
+        let symbol = &SYMBOLS[version];
+        let blocks = symbol.blocks(self.ecc_level);
+        let ec_count = (symbol.codewords - data.len()) / blocks;
+        let short_len = data.len() / blocks;
+        let long_blocks = data.len() % blocks;
+        let field = GaloisField::new(0x11D, 256, 0);
+
+        let mut data_blocks = Vec::with_capacity(blocks);
+        let mut begin = 0;
+        for block in 0..blocks {
+            let len = short_len + (block >= blocks - long_blocks) as usize;
+            let words: Vec<u16> = data[begin..begin + len].iter().map(|&w| w as u16).collect();
+            begin += len;
+            data_blocks.push(words);
+        }
+        let ec_blocks: Vec<Vec<u16>> = data_blocks
+            .iter()
+            .map(|words| field.encode(words, ec_count))
+            .collect();
+
+        let mut codewords = Vec::with_capacity(symbol.codewords);
+        for i in 0..=short_len {
+            for words in &data_blocks {
+                if let Some(&word) = words.get(i) {
+                    codewords.push(word as u8);
+                }
+            }
+        }
+        for i in 0..ec_count {
+            for words in &ec_blocks {
+                codewords.push(words[i] as u8);
+            }
+        }
+        codewords
+    }
+
+    pub fn get_matrix(&self) -> Result<Matrix, String> {
+        let (version, data) = self.get_data()?;
+        let codewords = self.add_error_correction(version, &data);
+        let symbol = &SYMBOLS[version];
+        let (width, height) = (symbol.width, symbol.height);
+        let (mut matrix, function) = function_patterns(symbol);
+
+        // Pairs of columns from the right, skipping the timing pattern, upwards and downwards
+        // in turn. Modules left over after the last codeword stay light before the mask.
+        let mut bits = codewords
+            .iter()
+            .flat_map(|&word| (0..8).rev().map(move |i| word & (1 << i) != 0));
+        let mut x = width - 3;
+        let mut upwards = true;
+        loop {
+            for step in 0..height {
+                let y = if upwards { height - 1 - step } else { step };
+                for &column in &[x + 1, x] {
+                    if !function.get(column, y) {
+                        let bit = bits.next().unwrap_or(false);
+                        matrix.set(column, y, bit ^ ((y / 2 + column / 3) % 2 == 0));
+                    }
+                }
+            }
+            if x < 2 {
+                break;
+            }
+            x -= 2;
+            upwards = !upwards;
+        }
+
+        let data = version as u32 + if self.ecc_level == EccLevel::H { 32 } else { 0 };
+        let left = format_info(data, FORMAT_MASK_LEFT);
+        let right = format_info(data, FORMAT_MASK_RIGHT);
+        for i in 0..5 {
+            for j in 0..3 {
+                let bit = j * 5 + i;
+                matrix.set(8 + j, 1 + i, left & (1 << bit) != 0);
+                matrix.set(width - 8 + j, height - 6 + i, right & (1 << bit) != 0);
+            }
+        }
+        for i in 0..3 {
+            matrix.set(11, 1 + i, left & (1 << (15 + i)) != 0);
+            matrix.set(width - 5 + i, height - 6, right & (1 << (15 + i)) != 0);
+        }
+        Ok(matrix)
+    }
+}
+
+impl Encode for Rmqr {
+    fn symbol(&self) -> Result<Matrix, String> {
+        self.get_matrix()
+    }
+
+    fn quiet_zone(&self) -> QuietZone {
+        QuietZone::uniform(QUIET_ZONE)
+    }
+
+    fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    fn get_params(&self) -> OutputParams {
+        OutputParams {
+            append_text: false,
+            font_size: 0,
+            height: self.height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(payload: &str, ecc_level: EccLevel, version: Option<i16>) -> Result<String, String> {
+        Rmqr::new(payload.to_owned(), 10, ecc_level, version)
+            .get_data()
+            .map(|(version, _)| SYMBOLS[version].name())
+    }
+
+    #[test]
+    fn test_symbols() {
+        for symbol in SYMBOLS.iter() {
+            let (_, function) = function_patterns(symbol);
+            let mut modules = 0;
+            for y in 0..symbol.height {
+                for x in 0..symbol.width {
+                    modules += !function.get(x, y) as usize;
+                }
+            }
+            assert_eq!(symbol.codewords, modules / 8, "{}", symbol.name());
+            for &level in &[EccLevel::M, EccLevel::H] {
+                let ec = symbol.codewords - symbol.data_codewords(level);
+                assert_eq!(0, ec % symbol.blocks(level), "{}", symbol.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_info() {
+        assert_eq!(0x1FAB2, format_info(0, FORMAT_MASK_LEFT));
+        assert_eq!(0x1E597, format_info(1, FORMAT_MASK_LEFT));
+        assert_eq!(0x20A7B, format_info(0, FORMAT_MASK_RIGHT));
+        assert_eq!(0x2155E, format_info(1, FORMAT_MASK_RIGHT));
+    }
+
+    #[test]
+    fn test_codewords() {
+        // Numeric mode, 5 digits in 4 bits, 123 and 45 in 10 and 7 bits, the terminator
+        // and padding.
+        let rmqr = Rmqr::new("12345".to_owned(), 10, EccLevel::M, None);
+        assert_eq!(vec![0x2A, 0x3D, 0xAD, 0x00, 0xEC, 0x11], rmqr.codewords(0));
+    }
+
+    #[test]
+    fn test_versions() {
+        // R11x27 is the smallest symbol, R7x43 is a little larger.
+        assert_eq!(Ok("R11x27".to_owned()), size("12345", EccLevel::M, None));
+        let digits = "12345678901234567890";
+        assert_eq!(Ok("R13x27".to_owned()), size(digits, EccLevel::M, None));
+        assert_eq!(Ok("R11x43".to_owned()), size(digits, EccLevel::H, None));
+        assert_eq!(Ok("R7x43".to_owned()), size("12345", EccLevel::M, Some(1)));
+        assert_eq!(
+            Ok("R17x139".to_owned()),
+            size("hello", EccLevel::M, Some(32))
+        );
+        // R17x99 holds 100 codewords, one too few for 100 bytes and their header.
+        let long = "a".repeat(100);
+        assert_eq!(Ok("R13x139".to_owned()), size(&long, EccLevel::M, None));
+    }
+
+    #[test]
+    fn test_capacity_exceeded() {
+        // R7x43 holds 6 codewords, 7 bytes take 3 bits for the mode, 3 for the length and 56.
+        assert_eq!(
+            Err(
+                "Payload needs 62 bits, but rMQR R7x43 with ECC level M holds 48, \
+                 exceeded by 14 bits"
+                    .to_owned()
+            ),
+            size("abcdefg", EccLevel::M, Some(1))
+        );
+        let error = size(&"a".repeat(200), EccLevel::H, None).unwrap_err();
+        assert!(error.starts_with("Payload needs 1611 bits, but rMQR R17x139 with ECC level H"));
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(size("1", EccLevel::L, None).is_err());
+        assert!(size("1", EccLevel::Q, None).is_err());
+        assert!(size("1", EccLevel::M, Some(0)).is_err());
+        assert!(size("1", EccLevel::M, Some(33)).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use image::{DynamicImage, GrayImage, Luma};
+
+/// Distance of the Bézier control points from the corner for a quarter circle of radius 1.
+const KAPPA: f64 = 0.552_284_8;
+/// Samples per pixel along each axis when rasterizing.
+const SAMPLES: u32 = 4;
+/// Straight lines replacing each Bézier curve when rasterizing.
+const CURVE_STEPS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+pub fn point(x: f64, y: f64) -> Point {
+    Point { x, y }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Segment {
+    Line(Point),
+    /// Cubic Bézier curve through two control points.
+    Curve(Point, Point, Point),
+}
+
+/// Closed outline starting and ending at `start`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subpath {
+    pub start: Point,
+    pub segments: Vec<Segment>,
+}
+
+/// Outlines filled with the even-odd rule, so that an inner outline cuts a hole.
+/// Shapes of a symbol do not overlap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shape {
+    pub subpaths: Vec<Subpath>,
+}
+
+impl Shape {
+    pub fn rect(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Shape::rounded_rect(x, y, width, height, 0.0)
+    }
+
+    /// Rectangle with corners rounded to `radius`, a circle when the radius is half the side.
+    pub fn rounded_rect(x: f64, y: f64, width: f64, height: f64, radius: f64) -> Self {
+        Shape {
+            subpaths: vec![rounded_outline(x, y, width, height, radius)],
+        }
+    }
+
+    /// Outer outline with the inner one cut out.
+    pub fn ring(outer: Shape, inner: Shape) -> Self {
+        Shape {
+            subpaths: outer.subpaths.into_iter().chain(inner.subpaths).collect(),
+        }
+    }
+
+    /// Moves every point through `f`, from module units to pixels or points.
+    pub fn map(&self, f: impl Fn(Point) -> Point) -> Shape {
+        let subpaths = self
+            .subpaths
+            .iter()
+            .map(|subpath| Subpath {
+                start: f(subpath.start),
+                segments: subpath
+                    .segments
+                    .iter()
+                    .map(|segment| match *segment {
+                        Segment::Line(p) => Segment::Line(f(p)),
+                        Segment::Curve(c1, c2, p) => Segment::Curve(f(c1), f(c2), f(p)),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Shape { subpaths }
+    }
+
+    /// Outlines as polygons, curves replaced by straight lines.
+    fn polygons(&self) -> Vec<Vec<Point>> {
+        self.subpaths
+            .iter()
+            .map(|subpath| {
+                let mut polygon = vec![subpath.start];
+                for segment in &subpath.segments {
+                    let from = *polygon.last().unwrap();
+                    match *segment {
+                        Segment::Line(p) => polygon.push(p),
+                        Segment::Curve(c1, c2, p) => {
+                            for step in 1..=CURVE_STEPS {
+                                polygon.push(bezier(
+                                    from,
+                                    c1,
+                                    c2,
+                                    p,
+                                    step as f64 / CURVE_STEPS as f64,
+                                ));
+                            }
+                        }
+                    }
+                }
+                polygon
+            })
+            .collect()
+    }
+}
+
+fn bezier(p0: Point, p1: Point, p2: Point, p3: Point, t: f64) -> Point {
+    let u = 1.0 - t;
+    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+    point(
+        a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+        a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+    )
+}
+
+/// Clockwise outline from the end of the top left corner.
+fn rounded_outline(x: f64, y: f64, width: f64, height: f64, radius: f64) -> Subpath {
+    let r = radius.min(width / 2.0).min(height / 2.0);
+    let k = r * KAPPA;
+    let (right, bottom) = (x + width, y + height);
+    let mut segments = Vec::new();
+    let mut corner = |line_to: Point, control1: Point, control2: Point, end: Point| {
+        segments.push(Segment::Line(line_to));
+        if r > 0.0 {
+            segments.push(Segment::Curve(control1, control2, end));
+        }
+    };
+    corner(
+        point(right - r, y),
+        point(right - r + k, y),
+        point(right, y + r - k),
+        point(right, y + r),
+    );
+    corner(
+        point(right, bottom - r),
+        point(right, bottom - r + k),
+        point(right - r + k, bottom),
+        point(right - r, bottom),
+    );
+    corner(
+        point(x + r, bottom),
+        point(x + r - k, bottom),
+        point(x, bottom - r + k),
+        point(x, bottom - r),
+    );
+    corner(
+        point(x, y + r),
+        point(x, y + r - k),
+        point(x + r - k, y),
+        point(x + r, y),
+    );
+    Subpath {
+        start: point(x + r, y),
+        segments,
+    }
+}
+
+/// Whether a point is inside the polygons by the even-odd rule.
+fn contains(polygons: &[Vec<Point>], p: Point) -> bool {
+    let mut inside = false;
+    for polygon in polygons {
+        let mut previous = *polygon.last().unwrap();
+        for &current in polygon {
+            if (current.y > p.y) != (previous.y > p.y) {
+                let x = previous.x
+                    + (p.y - previous.y) / (current.y - previous.y) * (current.x - previous.x);
+                if p.x < x {
+                    inside = !inside;
+                }
+            }
+            previous = current;
+        }
+    }
+    inside
+}
+
+/// Draws shapes given in pixels on a white image, edges are anti-aliased to grey.
+pub fn rasterize(shapes: &[Shape], width: u32, height: u32) -> DynamicImage {
+    let mut image = GrayImage::from_pixel(width, height, Luma([255]));
+    for shape in shapes {
+        let polygons = shape.polygons();
+        let points = polygons.iter().flatten();
+        let left = points
+            .clone()
+            .map(|p| p.x)
+            .fold(f64::MAX, f64::min)
+            .floor()
+            .max(0.0) as u32;
+        let top = points
+            .clone()
+            .map(|p| p.y)
+            .fold(f64::MAX, f64::min)
+            .floor()
+            .max(0.0) as u32;
+        let right = (points.clone().map(|p| p.x).fold(0.0, f64::max).ceil() as u32).min(width);
+        let bottom = (points.map(|p| p.y).fold(0.0, f64::max).ceil() as u32).min(height);
+        for y in top..bottom {
+            for x in left..right {
+                let mut covered = 0;
+                for sy in 0..SAMPLES {
+                    for sx in 0..SAMPLES {
+                        let sample = point(
+                            x as f64 + (sx as f64 + 0.5) / SAMPLES as f64,
+                            y as f64 + (sy as f64 + 0.5) / SAMPLES as f64,
+                        );
+                        if contains(&polygons, sample) {
+                            covered += 1;
+                        }
+                    }
+                }
+                let light = 255 - covered * 255 / (SAMPLES * SAMPLES);
+                let pixel = image.get_pixel_mut(x, y);
+                pixel[0] = pixel[0].min(light as u8);
+            }
+        }
+    }
+    DynamicImage::ImageLuma8(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounded_rect() {
+        let square = Shape::rect(1.0, 2.0, 3.0, 4.0);
+        assert_eq!(
+            vec![
+                Segment::Line(point(4.0, 2.0)),
+                Segment::Line(point(4.0, 6.0)),
+                Segment::Line(point(1.0, 6.0)),
+                Segment::Line(point(1.0, 2.0)),
+            ],
+            square.subpaths[0].segments
+        );
+        let circle = Shape::rounded_rect(0.0, 0.0, 2.0, 2.0, 1.0);
+        assert_eq!(8, circle.subpaths[0].segments.len());
+        assert_eq!(point(1.0, 0.0), circle.subpaths[0].start);
+    }
+
+    #[test]
+    fn test_rasterize() {
+        let ring = Shape::ring(
+            Shape::rect(0.0, 0.0, 6.0, 6.0),
+            Shape::rect(2.0, 2.0, 2.0, 2.0),
+        );
+        let circle = Shape::rounded_rect(6.0, 0.0, 4.0, 4.0, 2.0);
+        let image = rasterize(&[ring, circle], 10, 6).to_luma();
+        assert_eq!(0, image.get_pixel(0, 0)[0]);
+        // The hole of the ring.
+        assert_eq!(255, image.get_pixel(2, 2)[0]);
+        assert_eq!(0, image.get_pixel(8, 2)[0]);
+        // The edge of the circle is grey, lighter in its corner.
+        let (corner, edge) = (image.get_pixel(6, 0)[0], image.get_pixel(7, 0)[0]);
+        assert!(0 < edge && edge < corner && corner < 255);
+        assert_eq!(255, image.get_pixel(8, 5)[0]);
+    }
+}
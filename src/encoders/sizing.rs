@@ -0,0 +1,198 @@
+const MM_PER_INCH: f64 = 25.4;
+/// Widest module in pixels, 8 mm at 2400 dpi.
+pub const MAX_MODULE_PX: u32 = 750;
+/// Most pixels of a raster image, which is held in memory uncompressed while it is written.
+const MAX_PIXELS: u64 = 25_000_000;
+
+/// Width of a module requested instead of the one derived from `height`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModuleWidth {
+    /// Pixels per module.
+    Pixels(u32),
+    /// Width of the image including the quiet zone, snapped down to a multiple of the module count.
+    Image(u32),
+}
+
+/// Size of the raster output.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sizing {
+    pub module_width: Option<ModuleWidth>,
+    /// Resolution written to the image, so that printers scale it correctly.
+    pub dpi: Option<u32>,
+}
+
+impl Sizing {
+    /// Accepts at most one of `module_px`, `module_mm` and `width`, `module_mm` needs `dpi`.
+    pub fn new(
+        module_px: Option<u32>,
+        dpi: Option<u32>,
+        module_mm: Option<f64>,
+        width: Option<u32>,
+    ) -> Result<Self, String> {
+        if dpi == Some(0) {
+            return Err("dpi must be positive".to_owned());
+        }
+        let module_width = match (module_px, module_mm, width) {
+            (None, None, None) => None,
+            (Some(0), None, None) => return Err("module_px must be positive".to_owned()),
+            (Some(pixels), None, None) if pixels > MAX_MODULE_PX => {
+                return Err(format!(
+                    "module_px is at most {}, got {}",
+                    MAX_MODULE_PX, pixels
+                ))
+            }
+            (Some(pixels), None, None) => Some(ModuleWidth::Pixels(pixels)),
+            (None, Some(module_mm), None) => Some(ModuleWidth::Pixels(pixels_from_mm(
+                module_mm,
+                dpi.ok_or("module_mm needs dpi")?,
+            )?)),
+            (None, None, Some(0)) => return Err("width must be positive".to_owned()),
+            (None, None, Some(width)) => Some(ModuleWidth::Image(width)),
+            _ => return Err("Only one of module_px, module_mm and width can be set".to_owned()),
+        };
+        Ok(Sizing { module_width, dpi })
+    }
+
+    /// Pixels per module for a matrix `modules` wide, `default` when no width is requested.
+    pub fn module_width(&self, modules: usize, default: u32) -> Result<u32, String> {
+        let pixels = match self.module_width {
+            None => default,
+            Some(ModuleWidth::Pixels(pixels)) => pixels,
+            Some(ModuleWidth::Image(width)) if width as usize >= modules => width / modules as u32,
+            Some(ModuleWidth::Image(width)) => {
+                return Err(format!(
+                    "Width of {} pixels is narrower than the {} modules of the symbol",
+                    width, modules
+                ))
+            }
+        };
+        if pixels > MAX_MODULE_PX {
+            return Err(format!(
+                "Modules are at most {} pixels wide, got {}",
+                MAX_MODULE_PX, pixels
+            ));
+        }
+        Ok(pixels)
+    }
+}
+
+/// Rejects the image of a matrix `columns` by `rows` modules when it has more than
+/// `MAX_PIXELS` pixels.
+pub fn check_image_size(
+    columns: usize,
+    rows: usize,
+    module_width: u32,
+    module_height: u32,
+) -> Result<(), String> {
+    let width = columns as u64 * module_width as u64;
+    let height = rows as u64 * module_height as u64;
+    if width.saturating_mul(height) > MAX_PIXELS {
+        return Err(format!(
+            "Image of {}x{} pixels is larger than the maximum of {} pixels",
+            width, height, MAX_PIXELS
+        ));
+    }
+    Ok(())
+}
+
+/// Rounds the module to whole pixels, the printed module is off by half a pixel at most.
+fn pixels_from_mm(module_mm: f64, dpi: u32) -> Result<u32, String> {
+    if !(module_mm.is_finite() && module_mm > 0.0) {
+        return Err(format!("module_mm must be positive, got {}", module_mm));
+    }
+    let pixels = (module_mm / MM_PER_INCH * dpi as f64).round();
+    if pixels < 1.0 {
+        return Err(format!(
+            "Module of {} mm is smaller than a pixel at {} dpi",
+            module_mm, dpi
+        ));
+    }
+    if pixels > MAX_MODULE_PX as f64 {
+        return Err(format!(
+            "Module of {} mm is wider than {} pixels at {} dpi",
+            module_mm, MAX_MODULE_PX, dpi
+        ));
+    }
+    Ok(pixels as u32)
+}
+
+/// Adds a pHYs chunk with the resolution right after the IHDR chunk of a PNG.
+pub fn set_png_dpi(png: &[u8], dpi: u32) -> Vec<u8> {
+    // Signature, then the length, type, data and CRC of IHDR.
+    const IHDR_END: usize = 8 + 4 + 4 + 13 + 4;
+    let pixels_per_metre = (dpi as f64 * 1000.0 / MM_PER_INCH).round() as u32;
+    let mut chunk = b"pHYs".to_vec();
+    chunk.extend_from_slice(&pixels_per_metre.to_be_bytes());
+    chunk.extend_from_slice(&pixels_per_metre.to_be_bytes());
+    // Unit is the metre.
+    chunk.push(1);
+
+    let mut result = png[..IHDR_END].to_vec();
+    result.extend_from_slice(&9u32.to_be_bytes());
+    result.extend_from_slice(&chunk);
+    result.extend_from_slice(&crc32fast::hash(&chunk).to_be_bytes());
+    result.extend_from_slice(&png[IHDR_END..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_width() {
+        let sizing = Sizing::new(None, Some(300), Some(0.33), None).unwrap();
+        assert_eq!(Some(ModuleWidth::Pixels(4)), sizing.module_width);
+        assert_eq!(Ok(4), sizing.module_width(95, 2));
+
+        let sizing = Sizing::new(None, None, None, Some(400)).unwrap();
+        assert_eq!(Ok(3), sizing.module_width(113, 2));
+        assert!(sizing.module_width(401, 2).is_err());
+
+        assert_eq!(Ok(2), Sizing::default().module_width(95, 2));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Sizing::new(Some(0), None, None, None).is_err());
+        assert!(Sizing::new(None, None, Some(0.33), None).is_err());
+        assert!(Sizing::new(None, Some(72), Some(0.1), None).is_err());
+        assert!(Sizing::new(Some(2), None, None, Some(400)).is_err());
+        assert!(Sizing::new(None, Some(0), None, None).is_err());
+    }
+
+    #[test]
+    fn test_limits() {
+        assert!(Sizing::new(Some(MAX_MODULE_PX), None, None, None).is_ok());
+        assert!(Sizing::new(Some(MAX_MODULE_PX + 1), None, None, None).is_err());
+        assert!(Sizing::new(Some(u32::MAX), None, None, None).is_err());
+        assert!(Sizing::new(None, Some(u32::MAX), Some(1.0), None).is_err());
+
+        let sizing = Sizing::new(None, None, None, Some(u32::MAX)).unwrap();
+        assert!(sizing.module_width(21, 2).is_err());
+        assert!(Sizing::default()
+            .module_width(21, MAX_MODULE_PX + 1)
+            .is_err());
+
+        assert!(check_image_size(5000, 5000, 1, 1).is_ok());
+        assert!(check_image_size(5001, 5000, 1, 1).is_err());
+        // Would overflow u32.
+        assert!(check_image_size(177, 177, u32::MAX, u32::MAX).is_err());
+    }
+
+    #[test]
+    fn test_set_png_dpi() {
+        let image = image::DynamicImage::new_luma8(1, 1);
+        let mut png = Vec::new();
+        image
+            .write_to(&mut png, image::ImageOutputFormat::PNG)
+            .unwrap();
+        let png = set_png_dpi(&png, 300);
+        assert_eq!(b"\0\0\0\x09pHYs", &png[33..41]);
+        assert_eq!(11811u32.to_be_bytes(), png[41..45]);
+        assert_eq!(11811u32.to_be_bytes(), png[45..49]);
+        assert_eq!(1, png[49]);
+        // The decoder checks the CRC of every chunk.
+        assert!(image::load_from_memory(&png).is_ok());
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::encoders::color::Color;
+use crate::encoders::encoder::Encoded;
+use crate::encoders::matrix::Matrix;
+use crate::encoders::shape::{Point, Segment, Shape};
+
+/// Rectangle in module units.
+#[derive(Debug, PartialEq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Covers the dark modules with as few rectangles as the row by row merge allows:
+/// runs of dark modules in a row become one rectangle, which grows downwards
+/// while the rows below have a run with the same extent.
+pub fn rectangles(matrix: &Matrix) -> Vec<Rect> {
+    let mut rects: Vec<Rect> = Vec::new();
+    let mut open: HashMap<(usize, usize), usize> = HashMap::new();
+    for y in 0..matrix.height() {
+        let mut next_open = HashMap::new();
+        let mut x = 0;
+        while x < matrix.width() {
+            if !matrix.get(x, y) {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < matrix.width() && matrix.get(x, y) {
+                x += 1;
+            }
+            let index = match open.get(&(start, x)) {
+                Some(&index) => {
+                    rects[index].height += 1;
+                    index
+                }
+                None => {
+                    rects.push(Rect {
+                        x: start,
+                        y,
+                        width: x - start,
+                        height: 1,
+                    });
+                    rects.len() - 1
+                }
+            };
+            next_open.insert((start, x), index);
+        }
+        open = next_open;
+    }
+    rects
+}
+
+/// SVG document of the symbol turned to its orientation, with the module size of the raster image.
+pub fn output(code: &Encoded) -> Result<Vec<u8>, String> {
+    let options = &code.options;
+    let orientation = options.orientation;
+    let matrix = orientation.apply_matrix(&code.matrix);
+    let module_size = orientation.apply_size(code.module_size()?);
+    let svg = match code.shapes(orientation) {
+        Some(shapes) => {
+            let quiet_zone = orientation.apply_quiet_zone(code.quiet_zone);
+            let shapes: Vec<Shape> = shapes
+                .iter()
+                .map(|shape| {
+                    shape.map(|p| Point {
+                        x: p.x + quiet_zone.left as f64,
+                        y: p.y + quiet_zone.top as f64,
+                    })
+                })
+                .collect();
+            render_shapes(
+                &shapes,
+                (matrix.width(), matrix.height()),
+                module_size,
+                options.foreground,
+                options.background,
+            )
+        }
+        None => render(&matrix, module_size, options.foreground, options.background),
+    };
+    Ok(svg.into_bytes())
+}
+
+fn fill(color: Color) -> String {
+    if color.a == 255 {
+        format!("fill=\"{}\"", color.hex())
+    } else {
+        format!(
+            "fill=\"{}\" fill-opacity=\"{:.3}\"",
+            color.hex(),
+            color.opacity()
+        )
+    }
+}
+
+/// Writes the matrix as an SVG document with modules of `module_width` by `module_height` pixels.
+/// A fully transparent background is left out.
+pub fn render(
+    matrix: &Matrix,
+    module_size: (u32, u32),
+    foreground: Color,
+    background: Color,
+) -> String {
+    let mut path = String::new();
+    for rect in rectangles(matrix) {
+        write!(
+            path,
+            "M{},{}h{}v{}h-{}z",
+            rect.x, rect.y, rect.width, rect.height, rect.width
+        )
+        .unwrap();
+    }
+    document(
+        (matrix.width(), matrix.height()),
+        module_size,
+        " shape-rendering=\"crispEdges\"",
+        &path,
+        foreground,
+        background,
+    )
+}
+
+/// Writes shapes given in module units as an SVG document, like `render`.
+pub fn render_shapes(
+    shapes: &[Shape],
+    modules: (usize, usize),
+    module_size: (u32, u32),
+    foreground: Color,
+    background: Color,
+) -> String {
+    let mut path = String::new();
+    for subpath in shapes.iter().flat_map(|shape| &shape.subpaths) {
+        write!(path, "M{}", coordinates(&[subpath.start])).unwrap();
+        for segment in &subpath.segments {
+            match segment {
+                Segment::Line(p) => write!(path, "L{}", coordinates(&[*p])),
+                Segment::Curve(c1, c2, p) => write!(path, "C{}", coordinates(&[*c1, *c2, *p])),
+            }
+            .unwrap();
+        }
+        path.push('z');
+    }
+    document(
+        modules,
+        module_size,
+        " fill-rule=\"evenodd\"",
+        &path,
+        foreground,
+        background,
+    )
+}
+
+/// Points without trailing zeros, `1.5,2 3,4`.
+fn coordinates(points: &[Point]) -> String {
+    let number = |n: f64| {
+        let n = format!("{:.3}", n);
+        n.trim_end_matches('0').trim_end_matches('.').to_owned()
+    };
+    points
+        .iter()
+        .map(|p| format!("{},{}", number(p.x), number(p.y)))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn document(
+    (width, height): (usize, usize),
+    (module_width, module_height): (u32, u32),
+    attributes: &str,
+    path: &str,
+    foreground: Color,
+    background: Color,
+) -> String {
+    let background = if background.a == 0 {
+        String::new()
+    } else {
+        format!(
+            "<rect width=\"100%\" height=\"100%\" {}/>",
+            fill(background)
+        )
+    };
+    // Modules of linear symbols may be taller than wide.
+    let aspect_ratio = if module_width == module_height {
+        ""
+    } else {
+        " preserveAspectRatio=\"none\""
+    };
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"{}\" height=\"{}\" \
+         viewBox=\"0 0 {} {}\"{}{}>\
+         {}<path {} d=\"{}\"/></svg>",
+        width as u32 * module_width,
+        height as u32 * module_height,
+        width,
+        height,
+        aspect_ratio,
+        attributes,
+        background,
+        fill(foreground),
+        path
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoders::color::{BLACK, WHITE};
+
+    #[test]
+    fn test_rectangles() {
+        let rows = ["##.#", "##.#", "#..#"];
+        let mut matrix = Matrix::new(4, 3);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, module) in row.chars().enumerate() {
+                matrix.set(x, y, module == '#');
+            }
+        }
+        assert_eq!(
+            vec![
+                Rect {
+                    x: 0,
+                    y: 0,
+                    width: 2,
+                    height: 2
+                },
+                Rect {
+                    x: 3,
+                    y: 0,
+                    width: 1,
+                    height: 3
+                },
+                Rect {
+                    x: 0,
+                    y: 2,
+                    width: 1,
+                    height: 1
+                },
+            ],
+            rectangles(&matrix)
+        );
+    }
+
+    #[test]
+    fn test_bars_are_single_rectangles() {
+        let matrix = Matrix::from_bars(&[true, true, false, true], 50);
+        assert_eq!(2, rectangles(&matrix).len());
+    }
+
+    #[test]
+    fn test_render() {
+        let mut matrix = Matrix::new(2, 2);
+        matrix.set(0, 0, true);
+        assert_eq!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"20\" height=\"20\" \
+             viewBox=\"0 0 2 2\" shape-rendering=\"crispEdges\">\
+             <rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\
+             <path fill=\"#000000\" d=\"M0,0h1v1h-1z\"/></svg>",
+            render(&matrix, (10, 10), BLACK, WHITE)
+        );
+        let svg = render(
+            &matrix,
+            (10, 10),
+            Color::parse("ff000080").unwrap(),
+            Color::parse("00000000").unwrap(),
+        );
+        assert!(!svg.contains("<rect"));
+        assert!(svg.contains("<path fill=\"#ff0000\" fill-opacity=\"0.502\" d="));
+
+        let svg = render(&matrix, (2, 30), BLACK, WHITE);
+        assert!(svg.contains(
+            "width=\"4\" height=\"60\" viewBox=\"0 0 2 2\" preserveAspectRatio=\"none\""
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::encoders::color::Color;
+use crate::encoders::encoder::Encoded;
+use crate::encoders::matrix::Matrix;
+
+/// Bar height of linear symbols in modules, the rows of their matrix are sampled down to it.
+const BAR_ROWS: usize = 12;
+
+/// Characters a symbol is drawn with as `text/plain`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TerminalStyle {
+    /// Unicode half blocks, two rows of modules per line. The light modules are drawn,
+    /// so that the code reads right in the light text on a dark background of most terminals.
+    Blocks,
+    /// Two `#` per dark module, a line per row, for text files and light backgrounds.
+    Ascii,
+    /// Half blocks with both colours set by 24-bit ANSI escape codes, for any terminal.
+    Ansi,
+}
+
+/// Keeps every few rows of a linear symbol, so that its bars are `BAR_ROWS` modules high
+/// instead of the height in pixels.
+pub fn shorten_bars(symbol: &Matrix) -> Matrix {
+    let step = symbol.height().div_ceil(BAR_ROWS).max(1);
+    let rows = symbol.height().div_ceil(step);
+    let mut shortened = Matrix::new(symbol.width(), rows);
+    for y in 0..rows {
+        let source = (y * step + step / 2).min(symbol.height() - 1);
+        for x in 0..symbol.width() {
+            shortened.set(x, y, symbol.get(x, source));
+        }
+    }
+    shortened
+}
+
+/// Modules of the symbol drawn with characters, for terminals. Linear symbols get short bars.
+pub fn output(code: &Encoded, style: TerminalStyle) -> Vec<u8> {
+    let orientation = code.options.orientation;
+    let matrix = if code.encoder.is_linear() {
+        orientation.apply_matrix(&shorten_bars(&code.symbol).with_quiet_zone(code.quiet_zone))
+    } else {
+        orientation.apply_matrix(&code.matrix)
+    };
+    let options = &code.options;
+    render(&matrix, style, options.foreground, options.background).into_bytes()
+}
+
+/// Draws the matrix, including its quiet zone, one line of text per line of the result.
+pub fn render(
+    matrix: &Matrix,
+    style: TerminalStyle,
+    foreground: Color,
+    background: Color,
+) -> String {
+    match style {
+        TerminalStyle::Blocks => half_blocks(matrix, |top, bottom| {
+            match (top, bottom) {
+                (false, false) => "█",
+                (false, true) => "▀",
+                (true, false) => "▄",
+                (true, true) => " ",
+            }
+            .to_owned()
+        }),
+        TerminalStyle::Ascii => {
+            let mut text = String::new();
+            for y in 0..matrix.height() {
+                for x in 0..matrix.width() {
+                    text.push_str(if matrix.get(x, y) { "##" } else { "  " });
+                }
+                text.push('\n');
+            }
+            text
+        }
+        TerminalStyle::Ansi => {
+            let color = |dark| if dark { foreground } else { background };
+            let text = half_blocks(matrix, |top, bottom| {
+                let (top, bottom) = (color(top), color(bottom));
+                format!(
+                    "\x1b[38;2;{};{};{};48;2;{};{};{}m▀",
+                    top.r, top.g, top.b, bottom.r, bottom.g, bottom.b
+                )
+            });
+            // Resets the colours before every line break, so that they do not fill the rest of the line.
+            text.replace('\n', "\x1b[0m\n")
+        }
+    }
+}
+
+/// Draws two rows per line with `cell`, a light row below the last one when the height is odd.
+fn half_blocks(matrix: &Matrix, cell: impl Fn(bool, bool) -> String) -> String {
+    let mut text = String::new();
+    for y in (0..matrix.height()).step_by(2) {
+        for x in 0..matrix.width() {
+            let bottom = y + 1 < matrix.height() && matrix.get(x, y + 1);
+            write!(text, "{}", cell(matrix.get(x, y), bottom)).unwrap();
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoders::color::{BLACK, WHITE};
+
+    fn diagonal() -> Matrix {
+        let mut matrix = Matrix::new(3, 3);
+        for i in 0..3 {
+            matrix.set(i, i, true);
+        }
+        matrix
+    }
+
+    #[test]
+    fn test_blocks() {
+        assert_eq!(
+            "▄▀█\n██▄\n",
+            render(&diagonal(), TerminalStyle::Blocks, BLACK, WHITE)
+        );
+    }
+
+    #[test]
+    fn test_ascii() {
+        assert_eq!(
+            "##    \n  ##  \n    ##\n",
+            render(&diagonal(), TerminalStyle::Ascii, BLACK, WHITE)
+        );
+    }
+
+    #[test]
+    fn test_ansi() {
+        let text = render(
+            &diagonal(),
+            TerminalStyle::Ansi,
+            Color::parse("1a237e").unwrap(),
+            WHITE,
+        );
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with("\x1b[38;2;26;35;126;48;2;255;255;255m▀"));
+        assert!(lines[1].ends_with("\x1b[38;2;26;35;126;48;2;255;255;255m▀\x1b[0m"));
+    }
+
+    #[test]
+    fn test_shorten_bars() {
+        let symbol = Matrix::from_bars(&[true, false, true], 100);
+        let shortened = shorten_bars(&symbol);
+        assert_eq!((3, 12), (shortened.width(), shortened.height()));
+        assert!(shortened.get(2, 11) && !shortened.get(1, 11));
+        assert_eq!(4, shorten_bars(&Matrix::from_bars(&[true], 4)).height());
+    }
+}
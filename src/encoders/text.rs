@@ -0,0 +1,113 @@
+use image::{DynamicImage, GenericImage, GenericImageView, GrayImage, Luma};
+use imageproc::drawing::draw_text_mut;
+use rusttype::{point, Font, Scale};
+
+/// DejaVu Sans Mono, see `fonts/LICENSE`.
+const FONT: &[u8] = include_bytes!("../../fonts/DejaVuSansMono.ttf");
+
+/// Default font size in modules, so that a digit fits the seven modules of an EAN character.
+const FONT_SIZE_MODULES: u32 = 10;
+/// Smallest default font size in pixels, which is still legible.
+const MIN_FONT_SIZE: u32 = 12;
+
+/// Human readable interpretation requested for a linear symbol.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TextOptions {
+    pub show: bool,
+    /// Font size in pixels, by default derived from the module size.
+    pub font_size: Option<u32>,
+}
+
+impl TextOptions {
+    pub fn font_size(&self, module_size: u32) -> u32 {
+        self.font_size
+            .unwrap_or_else(|| std::cmp::max(MIN_FONT_SIZE, FONT_SIZE_MODULES * module_size))
+    }
+}
+
+/// Line of text centred on `x`, with its top at `y`, in pixels.
+#[derive(Debug, PartialEq)]
+pub struct Label {
+    pub text: String,
+    pub x: f32,
+    pub y: u32,
+}
+
+fn font() -> Font<'static> {
+    Font::from_bytes(FONT).expect("Bundled font is valid")
+}
+
+/// Draws the labels on the image, which grows downwards when they do not fit.
+pub fn draw(image: &DynamicImage, labels: &[Label], font_size: u32) -> DynamicImage {
+    let font = font();
+    let scale = Scale::uniform(font_size as f32);
+    let v_metrics = font.v_metrics(scale);
+    let line_height = (v_metrics.ascent - v_metrics.descent).ceil() as u32;
+    let height = labels
+        .iter()
+        .map(|label| label.y + line_height)
+        .fold(image.height(), std::cmp::max);
+
+    let mut canvas = GrayImage::from_pixel(image.width(), height, Luma([255]));
+    canvas.copy_from(&image.to_luma(), 0, 0);
+    for label in labels {
+        let width = text_width(&font, scale, &label.text);
+        let left = (label.x - width / 2.0).max(0.0).round() as u32;
+        draw_text_mut(
+            &mut canvas,
+            Luma([0]),
+            left,
+            label.y,
+            scale,
+            &font,
+            &label.text,
+        );
+    }
+    DynamicImage::ImageLuma8(canvas)
+}
+
+fn text_width(font: &Font, scale: Scale, text: &str) -> f32 {
+    font.layout(text, scale, point(0.0, 0.0))
+        .last()
+        .map_or(0.0, |glyph| {
+            glyph.position().x + glyph.unpositioned().h_metrics().advance_width
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_font_size() {
+        assert_eq!(12, TextOptions::default().font_size(1));
+        assert_eq!(30, TextOptions::default().font_size(3));
+        let options = TextOptions {
+            show: true,
+            font_size: Some(20),
+        };
+        assert_eq!(20, options.font_size(3));
+    }
+
+    #[test]
+    fn test_draw() {
+        let image = DynamicImage::ImageLuma8(GrayImage::from_pixel(100, 10, Luma([255])));
+        let label = Label {
+            text: "12".to_owned(),
+            x: 50.0,
+            y: 12,
+        };
+        let drawn = draw(&image, &[label], 20).to_luma();
+        assert_eq!(100, drawn.width());
+        assert!(drawn.height() > 30);
+
+        let dark: Vec<(u32, u32)> = drawn
+            .enumerate_pixels()
+            .filter(|(_, _, pixel)| pixel[0] < 128)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert!(!dark.is_empty());
+        // Two monospace digits are about 12 pixels wide, centred on 50.
+        assert!(dark.iter().all(|&(x, y)| x > 36 && x < 64 && y >= 12));
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+
+/// Longest SSID of IEEE 802.11, in bytes.
+const MAX_SSID_BYTES: usize = 32;
+
+/// Authentication of the network as the `T` field of the `WIFI:` payload.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Security {
+    /// WPA, WPA2 or WPA3 personal, scanners pick the one the network offers.
+    Wpa,
+    Wep,
+    /// Open network without a password.
+    Nopass,
+}
+
+impl Security {
+    fn name(self) -> &'static str {
+        match self {
+            Security::Wpa => "WPA",
+            Security::Wep => "WEP",
+            Security::Nopass => "nopass",
+        }
+    }
+}
+
+/// Escapes the characters which delimit the fields of the payload with a backslash.
+/// Double quotes are escaped as well, readers take a quoted value as a string rather than hex.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if let '\\' | ';' | ',' | ':' | '"' = c {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn is_hex(value: &str) -> bool {
+    value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Checks the password against the lengths the security type allows, so that a mistyped
+/// password fails here rather than on the phone which scans the code.
+fn check_password(security: Security, password: Option<&str>) -> Result<(), String> {
+    match (security, password) {
+        (Security::Nopass, None) => Ok(()),
+        (Security::Nopass, Some(_)) => {
+            Err("An open network has no password, set security to wpa or wep".to_owned())
+        }
+        (_, None) => Err(format!("{} needs a password", security.name())),
+        (Security::Wpa, Some(password)) => {
+            let length = password.chars().count();
+            let passphrase = password.is_ascii() && (8..=63).contains(&length);
+            let key = is_hex(password) && length == 64;
+            if passphrase || key {
+                Ok(())
+            } else {
+                Err(format!(
+                    "WPA passwords are 8 to 63 ASCII characters or 64 hex digits, got {} characters",
+                    length
+                ))
+            }
+        }
+        (Security::Wep, Some(key)) => {
+            let length = key.chars().count();
+            let ascii = key.is_ascii() && (length == 5 || length == 13);
+            let hex = is_hex(key) && (length == 10 || length == 26);
+            if ascii || hex {
+                Ok(())
+            } else {
+                Err(format!(
+                    "WEP keys are 5 or 13 ASCII characters or 10 or 26 hex digits, got {} characters",
+                    length
+                ))
+            }
+        }
+    }
+}
+
+/// `WIFI:` payload read by the camera apps of Android and iOS to join the network.
+pub fn payload(
+    ssid: &str,
+    security: Security,
+    password: Option<&str>,
+    hidden: bool,
+) -> Result<String, String> {
+    if ssid.is_empty() || ssid.len() > MAX_SSID_BYTES {
+        return Err(format!(
+            "SSID must be 1 to {} bytes long, got {}",
+            MAX_SSID_BYTES,
+            ssid.len()
+        ));
+    }
+    check_password(security, password)?;
+    let mut payload = format!("WIFI:T:{};S:{};", security.name(), escape(ssid));
+    if let Some(password) = password {
+        payload.push_str(&format!("P:{};", escape(password)));
+    }
+    if hidden {
+        payload.push_str("H:true;");
+    }
+    payload.push(';');
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        assert_eq!(
+            Ok("WIFI:T:WPA;S:Café;P:secret123;;".to_owned()),
+            payload("Café", Security::Wpa, Some("secret123"), false)
+        );
+        assert_eq!(
+            Ok("WIFI:T:nopass;S:Guests;H:true;;".to_owned()),
+            payload("Guests", Security::Nopass, None, true)
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            Ok("WIFI:T:WPA;S:a\\;b\\,c;P:p\\:w\\\\d\\\"1234;;".to_owned()),
+            payload("a;b,c", Security::Wpa, Some("p:w\\d\"1234"), false)
+        );
+    }
+
+    #[test]
+    fn test_password() {
+        assert!(payload("home", Security::Wpa, Some("short"), false).is_err());
+        assert!(payload("home", Security::Wpa, None, false).is_err());
+        assert!(payload("home", Security::Nopass, Some("secret123"), false).is_err());
+        assert!(payload("home", Security::Wpa, Some(&"ab".repeat(32)), false).is_ok());
+        assert!(payload("home", Security::Wep, Some("0123456789"), false).is_ok());
+        assert!(payload("home", Security::Wep, Some("012345"), false).is_err());
+        assert!(payload("", Security::Nopass, None, false).is_err());
+        assert!(payload(&"x".repeat(33), Security::Nopass, None, false).is_err());
+    }
+}
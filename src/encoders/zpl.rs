@@ -0,0 +1,129 @@
+use std::fmt::Write;
+
+use image::{DynamicImage, GenericImageView};
+
+use crate::encoders::encoder::Encoded;
+use crate::encoders::orientation::Rotation;
+
+/// Widest module of `^BY` and largest magnification of `^BQ`, in dots.
+pub const MAX_MODULE: u32 = 10;
+
+/// Field orientation of the barcode commands: normal, rotated clockwise, inverted and bottom up.
+pub fn orientation(rotation: Rotation) -> char {
+    match rotation {
+        Rotation::Zero => 'N',
+        Rotation::Ninety => 'R',
+        Rotation::OneEighty => 'I',
+        Rotation::TwoSeventy => 'B',
+    }
+}
+
+/// Field data after `^FH\`, which reads `\` and two hex digits as a byte, so that the command
+/// prefixes `^` and `~` and control characters can be printed.
+pub fn field_data(data: &str) -> String {
+    let mut field = "^FH\\^FD".to_owned();
+    for c in data.chars() {
+        match c {
+            '^' | '~' | '\\' => write!(field, "\\{:02X}", c as u8).unwrap(),
+            _ if c.is_ascii_control() => write!(field, "\\{:02X}", c as u8).unwrap(),
+            _ => field.push(c),
+        }
+    }
+    field + "^FS"
+}
+
+/// Label of `size` dots with the fields, the text is UTF-8 encoded. Without a size the label
+/// keeps the one configured in the printer.
+pub fn label(size: Option<(u32, u32)>, fields: &str) -> String {
+    let mut label = "^XA\n^CI28\n".to_owned();
+    if let Some((width, height)) = size {
+        writeln!(label, "^PW{}\n^LL{}", width, height).unwrap();
+    }
+    writeln!(label, "{}\n^XZ", fields).unwrap();
+    label
+}
+
+/// ZPL label for Zebra printers, one pixel of the raster image is one dot. Symbols without
+/// a native command are sent as a graphic field.
+pub fn output(code: &Encoded) -> Result<Vec<u8>, String> {
+    let orientation = code.options.orientation;
+    let image = code.print_image()?;
+    let native = if orientation.mirror {
+        None
+    } else {
+        code.encoder.zpl(code)?
+    };
+    let field = match native {
+        Some(command) => {
+            let quiet_zone = orientation.apply_quiet_zone(code.quiet_zone);
+            let (module_width, module_height) = orientation.apply_size(code.module_size()?);
+            format!(
+                "^FO{},{}{}",
+                quiet_zone.left as u32 * module_width,
+                quiet_zone.top as u32 * module_height,
+                command
+            )
+        }
+        None => format!("^FO0,0{}", graphic_field(&image)),
+    };
+    let size = (image.width(), image.height());
+    Ok(label(Some(size), &field).into_bytes())
+}
+
+/// Bitmap of the dark pixels as a `^GF` graphic field in ASCII hex. A row repeating the one
+/// above is written as `:` and trailing white bytes of a row are cut with `,`.
+pub fn graphic_field(image: &DynamicImage) -> String {
+    let luma = image.to_luma();
+    let (width, height) = luma.dimensions();
+    let row_bytes = (width as usize).div_ceil(8);
+    let mut data = String::new();
+    let mut previous: Option<Vec<u8>> = None;
+    for y in 0..height {
+        let mut row = vec![0u8; row_bytes];
+        for x in 0..width {
+            if luma.get_pixel(x, y)[0] < 128 {
+                row[x as usize / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        if previous.as_ref() == Some(&row) {
+            data.push(':');
+            continue;
+        }
+        let used = row.iter().rposition(|&byte| byte != 0).map_or(0, |i| i + 1);
+        for byte in &row[..used] {
+            write!(data, "{:02X}", byte).unwrap();
+        }
+        if used < row_bytes {
+            data.push(',');
+        }
+        previous = Some(row);
+    }
+    let total = row_bytes * height as usize;
+    format!("^GFA,{},{},{},{}^FS", total, total, row_bytes, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma};
+
+    use super::*;
+
+    #[test]
+    fn test_field_data() {
+        assert_eq!("^FH\\^FDa\\5Eb\\7Ec\\5C^FS", field_data("a^b~c\\"));
+        assert_eq!("^FH\\^FDтест\\0A^FS", field_data("тест\n"));
+    }
+
+    #[test]
+    fn test_graphic_field() {
+        // 10 pixels wide: the first row has a dark pixel at 0 and 9, the second repeats it,
+        // the third is white.
+        let image = GrayImage::from_fn(10, 3, |x, y| {
+            Luma([if y < 2 && (x == 0 || x == 9) { 0 } else { 255 }])
+        });
+        assert_eq!(
+            "^GFA,6,6,2,8040:,^FS",
+            graphic_field(&DynamicImage::ImageLuma8(image))
+        );
+    }
+}
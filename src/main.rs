@@ -11,6 +11,17 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .route("/", web::get().to(resources::index))
             .route("/encode/{encoding}", web::get().to(resources::get_code))
+            .route("/encode/{encoding}", web::post().to(resources::post_code))
+            .route("/encode/QRCode/wifi", web::get().to(resources::get_wifi))
+            .route("/encode/QRCode/wifi", web::post().to(resources::post_wifi))
+            .route(
+                "/encode/QRCode/contact",
+                web::get().to(resources::get_contact),
+            )
+            .route(
+                "/encode/QRCode/contact",
+                web::post().to(resources::post_contact),
+            )
     })
     .bind("127.0.0.1:8088")?
     .run()
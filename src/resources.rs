@@ -1,5 +1,4 @@
 use core::fmt;
-use std::panic;
 
 use actix_files::NamedFile;
 use actix_web::http::StatusCode;
@@ -7,17 +6,53 @@ use actix_web::{web, HttpRequest, HttpResponse};
 use base64::encode;
 use serde::{Deserialize, Serialize};
 
+use crate::encoders::aztec;
 use crate::encoders::barcode;
-use crate::encoders::encoder::Encode;
+use crate::encoders::color::{self, Color};
+use crate::encoders::contact::{CardFormat, Contact};
+use crate::encoders::datamatrix;
+use crate::encoders::ean;
+use crate::encoders::encoder::{Encode, Encoded, RenderOptions};
+use crate::encoders::escpos::{self, Paper};
+use crate::encoders::linear;
+use crate::encoders::logo::{self, Logo};
+use crate::encoders::matrix::QuietZone;
+use crate::encoders::micro_qr;
+use crate::encoders::orientation::{Orientation, Rotation};
+use crate::encoders::pdf417;
+use crate::encoders::print::PrintSize;
+use crate::encoders::qr_style::{EyeStyle, ModuleStyle, QrStyle};
 use crate::encoders::qrcode;
-use crate::response_format::{get_response_format, ResponseFormat};
+use crate::encoders::raster::{self, RasterFormat};
+use crate::encoders::rmqr;
+use crate::encoders::sizing::Sizing;
+use crate::encoders::terminal::{self, TerminalStyle};
+use crate::encoders::text::TextOptions;
+use crate::encoders::wifi::{self, Security};
+use crate::encoders::zpl;
+use crate::encoders::{eps, pdf, svg};
+use crate::response_format::{get_format_from_name, get_response_format, ResponseFormat};
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Deserialize, Debug)]
 enum Encoding {
     BarCode,
+    GS1128,
     QRCode,
+    MicroQR,
+    RMQR,
     Aztec,
     PDF417,
+    DataMatrix,
+    EAN13,
+    EAN8,
+    UPCA,
+    UPCE,
+    Code39,
+    Code93,
+    Codabar,
+    ITF,
+    ITF14,
 }
 
 impl fmt::Display for Encoding {
@@ -51,24 +86,347 @@ pub struct Params {
     payload: String,
     #[serde(default = "default_height")]
     height: u32,
+    #[serde(default = "default_ecc_percent")]
+    ecc_percent: u8,
+    security_level: Option<u8>,
+    columns: Option<usize>,
+    rows: Option<usize>,
+    #[serde(default = "default_shape")]
+    shape: datamatrix::Shape,
+    size: Option<String>,
+    add_on: Option<String>,
+    #[serde(default)]
+    checksum: bool,
+    #[serde(default)]
+    full_ascii: bool,
+    #[serde(default = "default_codabar_guard")]
+    start: char,
+    #[serde(default = "default_codabar_guard")]
+    stop: char,
+    #[serde(default = "default_ecc_level")]
+    ecc_level: qrcode::EccLevel,
+    version: Option<i16>,
+    min_version: Option<i16>,
+    mask: Option<u8>,
+    #[serde(default = "default_text")]
+    text: bool,
+    font_size: Option<u32>,
+    quiet_zone: Option<String>,
+    fg: Option<String>,
+    bg: Option<String>,
+    #[serde(default)]
+    check_contrast: bool,
+    #[serde(default)]
+    rotate: u16,
+    #[serde(default)]
+    mirror: bool,
+    module_px: Option<u32>,
+    dpi: Option<u32>,
+    module_mm: Option<f64>,
+    width: Option<u32>,
+    logo: Option<String>,
+    logo_size: Option<u32>,
+    #[serde(default = "default_module_style")]
+    module_style: ModuleStyle,
+    #[serde(default = "default_eye_style")]
+    eye_style: EyeStyle,
+    format: Option<String>,
+    #[serde(default)]
+    monochrome: bool,
+    #[serde(default = "default_terminal_style")]
+    terminal: TerminalStyle,
+    #[serde(default = "default_paper")]
+    paper: u16,
+    height_mm: Option<f64>,
+    quiet_zone_mm: Option<f64>,
 }
 
 fn default_height() -> u32 {
     300
 }
 
-fn process_request(encoding: &Encoding, payload: String, height: u32) -> Box<dyn Encode> {
+/// Physical size of vector documents, by default one pixel of `height` becomes one point.
+fn get_print_size(params: &Params) -> PrintSize {
+    PrintSize {
+        height_mm: params
+            .height_mm
+            .unwrap_or(params.height as f64 * 25.4 / 72.0),
+        quiet_zone_mm: params.quiet_zone_mm,
+    }
+}
+
+fn get_render_options(params: &Params) -> Result<RenderOptions, String> {
+    let quiet_zone = match &params.quiet_zone {
+        Some(quiet_zone) => Some(QuietZone::parse(quiet_zone)?),
+        None => None,
+    };
+    let parse_color = |value: &Option<String>, default| match value {
+        Some(value) => Color::parse(value),
+        None => Ok(default),
+    };
+    let foreground = parse_color(&params.fg, color::BLACK)?;
+    let background = parse_color(&params.bg, color::WHITE)?;
+    if params.check_contrast {
+        color::check_contrast(foreground, background)?;
+    }
+    let orientation = Orientation {
+        rotation: Rotation::from_degrees(params.rotate)?,
+        mirror: params.mirror,
+    };
+    let sizing = Sizing::new(params.module_px, params.dpi, params.module_mm, params.width)?;
+    Ok(RenderOptions {
+        quiet_zone,
+        foreground,
+        background,
+        orientation,
+        sizing,
+    })
+}
+
+/// The uploaded logo takes precedence over a pre-registered one.
+fn get_logo(params: &Params, upload: Option<&[u8]>) -> Result<Option<Logo>, String> {
+    let size = params.logo_size.unwrap_or(logo::DEFAULT_SIZE_PERCENT);
+    match (upload, &params.logo) {
+        (Some(bytes), _) => Ok(Some(Logo::from_bytes(bytes, size)?)),
+        (None, Some(name)) => Ok(Some(Logo::registered(name, size)?)),
+        (None, None) => Ok(None),
+    }
+}
+
+fn default_module_style() -> ModuleStyle {
+    ModuleStyle::Square
+}
+
+fn default_eye_style() -> EyeStyle {
+    EyeStyle::Square
+}
+
+fn default_terminal_style() -> TerminalStyle {
+    TerminalStyle::Blocks
+}
+
+/// Width of receipt paper in millimetres.
+fn default_paper() -> u16 {
+    80
+}
+
+fn default_text() -> bool {
+    true
+}
+
+fn default_ecc_percent() -> u8 {
+    aztec::DEFAULT_ECC_PERCENT
+}
+
+fn default_shape() -> datamatrix::Shape {
+    datamatrix::Shape::Square
+}
+
+fn default_codabar_guard() -> char {
+    'A'
+}
+
+fn default_ecc_level() -> qrcode::EccLevel {
+    qrcode::EccLevel::M
+}
+
+/// Network of the Wi-Fi QR code, the other parameters of `Params` apply to the code.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WifiParams {
+    ssid: String,
+    #[serde(default = "default_security")]
+    security: Security,
+    password: Option<String>,
+    #[serde(default)]
+    hidden: bool,
+}
+
+fn default_security() -> Security {
+    Security::Wpa
+}
+
+/// Contact of the QR code, `phones` and `emails` are separated by commas.
+/// The other parameters of `Params` apply to the code.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ContactParams {
+    name: String,
+    org: Option<String>,
+    phones: Option<String>,
+    emails: Option<String>,
+    address: Option<String>,
+    url: Option<String>,
+    #[serde(default = "default_card")]
+    card: CardFormat,
+}
+
+fn default_card() -> CardFormat {
+    CardFormat::Vcard3
+}
+
+impl ContactParams {
+    fn contact(&self) -> Contact {
+        let list = |values: &Option<String>| -> Vec<String> {
+            values
+                .iter()
+                .flat_map(|values| values.split(','))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_owned)
+                .collect()
+        };
+        let field = |value: &Option<String>| value.clone().filter(|value| !value.is_empty());
+        Contact {
+            name: self.name.clone(),
+            organisation: field(&self.org),
+            phones: list(&self.phones),
+            emails: list(&self.emails),
+            address: field(&self.address),
+            url: field(&self.url),
+        }
+    }
+}
+
+/// Parameters of the query string with the payload built from other ones.
+fn with_payload(query_string: &str, payload: String) -> Result<Params, String> {
+    let mut pairs: Vec<(String, String)> =
+        serde_urlencoded::from_str(query_string).map_err(|e| e.to_string())?;
+    if pairs.iter().any(|(name, _)| name == "payload") {
+        return Err("The payload is built from the other parameters, leave it out".to_owned());
+    }
+    pairs.push(("payload".to_owned(), payload));
+    let query_string = serde_urlencoded::to_string(&pairs).map_err(|e| e.to_string())?;
+    web::Query::<Params>::from_query(&query_string)
+        .map(web::Query::into_inner)
+        .map_err(|e| e.to_string())
+}
+
+fn process_request(encoding: &Encoding, params: &Params, logo: Option<Logo>) -> Box<dyn Encode> {
+    let payload = params.payload.clone();
+    let height = params.height;
+    let text = TextOptions {
+        show: params.text,
+        font_size: params.font_size,
+    };
     match encoding {
-        Encoding::BarCode => Box::new(barcode::BarCode::new(payload, height)),
-        Encoding::QRCode => Box::new(qrcode::QRCode::new(payload, height)),
-        _ => panic!("Not supported yet!!!"),
+        Encoding::BarCode => Box::new(barcode::BarCode::new(payload, height, text)),
+        Encoding::GS1128 => Box::new(barcode::BarCode::new_gs1(payload, height, text)),
+        Encoding::QRCode => {
+            let qrcode = qrcode::QRCode::new(
+                payload,
+                height,
+                params.ecc_level,
+                params.version,
+                params.min_version,
+                params.mask,
+            )
+            .with_style(QrStyle {
+                modules: params.module_style,
+                eyes: params.eye_style,
+            });
+            match logo {
+                Some(logo) => Box::new(qrcode.with_logo(logo)),
+                None => Box::new(qrcode),
+            }
+        }
+        Encoding::MicroQR => Box::new(micro_qr::MicroQR::new(
+            payload,
+            height,
+            params.ecc_level,
+            params.version,
+        )),
+        Encoding::RMQR => Box::new(rmqr::Rmqr::new(
+            payload,
+            height,
+            params.ecc_level,
+            params.version,
+        )),
+        Encoding::Aztec => Box::new(aztec::Aztec::new(payload, height, params.ecc_percent)),
+        Encoding::PDF417 => Box::new(pdf417::Pdf417::new(
+            payload,
+            height,
+            params.security_level,
+            params.columns,
+            params.rows,
+        )),
+        Encoding::DataMatrix => Box::new(datamatrix::DataMatrix::new(
+            payload,
+            height,
+            params.shape,
+            params.size.clone(),
+        )),
+        Encoding::EAN13 => Box::new(ean::Ean::new(
+            payload,
+            height,
+            ean::Symbology::Ean13,
+            params.add_on.clone(),
+            text,
+        )),
+        Encoding::EAN8 => Box::new(ean::Ean::new(
+            payload,
+            height,
+            ean::Symbology::Ean8,
+            params.add_on.clone(),
+            text,
+        )),
+        Encoding::UPCA => Box::new(ean::Ean::new(
+            payload,
+            height,
+            ean::Symbology::UpcA,
+            params.add_on.clone(),
+            text,
+        )),
+        Encoding::UPCE => Box::new(ean::Ean::new(
+            payload,
+            height,
+            ean::Symbology::UpcE,
+            params.add_on.clone(),
+            text,
+        )),
+        Encoding::Code39 => Box::new(linear::Linear::new(
+            payload,
+            height,
+            linear::Symbology::Code39 {
+                checksum: params.checksum,
+                full_ascii: params.full_ascii,
+            },
+            text,
+        )),
+        Encoding::Code93 => Box::new(linear::Linear::new(
+            payload,
+            height,
+            linear::Symbology::Code93,
+            text,
+        )),
+        Encoding::Codabar => Box::new(linear::Linear::new(
+            payload,
+            height,
+            linear::Symbology::Codabar {
+                start: params.start,
+                stop: params.stop,
+            },
+            text,
+        )),
+        Encoding::ITF => Box::new(linear::Linear::new(
+            payload,
+            height,
+            linear::Symbology::Interleaved2of5 {
+                checksum: params.checksum,
+            },
+            text,
+        )),
+        Encoding::ITF14 => Box::new(linear::Linear::new(
+            payload,
+            height,
+            linear::Symbology::Itf14,
+            text,
+        )),
     }
 }
 
 fn get_successful_output(data: Vec<u8>, format: ResponseFormat) -> HttpResponse {
     match format {
-        ResponseFormat::Image => HttpResponse::build(StatusCode::OK)
-            .content_type("image/png")
+        ResponseFormat::Image(format) => HttpResponse::build(StatusCode::OK)
+            .content_type(format.content_type())
             .body(data),
         ResponseFormat::HTML => {
             let result = encode(&data[..]);
@@ -82,11 +440,29 @@ fn get_successful_output(data: Vec<u8>, format: ResponseFormat) -> HttpResponse
         ResponseFormat::JSON => {
             HttpResponse::build(StatusCode::OK).json(JsonResponse::new_from_data(data))
         }
+        ResponseFormat::Svg => HttpResponse::build(StatusCode::OK)
+            .content_type("image/svg+xml")
+            .body(data),
+        ResponseFormat::Pdf => HttpResponse::build(StatusCode::OK)
+            .content_type("application/pdf")
+            .body(data),
+        ResponseFormat::Eps => HttpResponse::build(StatusCode::OK)
+            .content_type("application/postscript")
+            .body(data),
+        ResponseFormat::Text => HttpResponse::build(StatusCode::OK)
+            .content_type("text/plain; charset=utf-8")
+            .body(data),
+        ResponseFormat::Zpl => HttpResponse::build(StatusCode::OK)
+            .content_type("application/zpl; charset=utf-8")
+            .body(data),
+        ResponseFormat::EscPos => HttpResponse::build(StatusCode::OK)
+            .content_type("application/octet-stream")
+            .body(data),
     }
 }
 
 pub async fn index() -> Result<NamedFile, std::io::Error> {
-    Ok(NamedFile::open("static/index.html")?)
+    NamedFile::open("static/index.html")
 }
 
 pub async fn get_code(
@@ -94,28 +470,177 @@ pub async fn get_code(
     query: web::Query<Params>,
     req: HttpRequest,
 ) -> HttpResponse {
-    let process_result = panic::catch_unwind(|| {
-        process_request(&info.encoding, query.payload.clone(), query.height)
-    });
+    respond(&info.encoding, &query, None, req)
+}
+
+/// Same as `get_code`, with the logo uploaded as the request body.
+pub async fn post_code(
+    info: web::Path<Info>,
+    query: web::Query<Params>,
+    body: web::Bytes,
+    req: HttpRequest,
+) -> HttpResponse {
+    respond(&info.encoding, &query, Some(&body), req)
+}
+
+/// QR code which joins a Wi-Fi network.
+pub async fn get_wifi(wifi: web::Query<WifiParams>, req: HttpRequest) -> HttpResponse {
+    respond_wifi(&wifi, None, req)
+}
+
+/// Same as `get_wifi`, with the logo uploaded as the request body.
+pub async fn post_wifi(
+    wifi: web::Query<WifiParams>,
+    body: web::Bytes,
+    req: HttpRequest,
+) -> HttpResponse {
+    respond_wifi(&wifi, Some(&body), req)
+}
+
+/// QR code of a contact card.
+pub async fn get_contact(contact: web::Query<ContactParams>, req: HttpRequest) -> HttpResponse {
+    respond_contact(&contact, None, req)
+}
 
-    if process_result.is_err() {
-        return HttpResponse::build(StatusCode::BAD_REQUEST)
-            .content_type("text/html; charset=utf-8")
-            .body(format!("Encoding {:?} is not supported", info.encoding));
+/// Same as `get_contact`, with the logo uploaded as the request body.
+pub async fn post_contact(
+    contact: web::Query<ContactParams>,
+    body: web::Bytes,
+    req: HttpRequest,
+) -> HttpResponse {
+    respond_contact(&contact, Some(&body), req)
+}
+
+/// Whether the payload fits a QR code of the query, at ECC level H under a logo.
+fn fits_qr_code(query: &Params, payload: &str, has_logo: bool) -> bool {
+    let ecc_level = if has_logo {
+        qrcode::EccLevel::H
+    } else {
+        query.ecc_level
+    };
+    qrcode::QRCode::new(
+        payload.to_owned(),
+        query.height,
+        ecc_level,
+        query.version,
+        query.min_version,
+        None,
+    )
+    .fits()
+}
+
+fn respond_contact(
+    params: &ContactParams,
+    upload: Option<&[u8]>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let contact = params.contact();
+    let query = contact
+        .payload(params.card)
+        .and_then(|payload| with_payload(req.query_string(), payload));
+    let query = match query {
+        Ok(query) => query,
+        Err(error) => return error_response(error),
+    };
+    // A vCard is two to three times longer than the MeCard of the same contact.
+    let has_logo = upload.is_some() || query.logo.is_some();
+    if params.card != CardFormat::Mecard && !fits_qr_code(&query, &query.payload, has_logo) {
+        if let Ok(mecard) = contact.payload(CardFormat::Mecard) {
+            if fits_qr_code(&query, &mecard, has_logo) {
+                return error_response(format!(
+                    "The {} of {} bytes does not fit in a QR code, the MeCard of {} bytes does, set card=mecard",
+                    params.card.name(),
+                    query.payload.len(),
+                    mecard.len()
+                ));
+            }
+        }
     }
+    respond(&Encoding::QRCode, &query, upload, req)
+}
+
+fn respond_wifi(wifi: &WifiParams, upload: Option<&[u8]>, req: HttpRequest) -> HttpResponse {
+    // Forms send an empty field for an open network.
+    let password = wifi
+        .password
+        .as_deref()
+        .filter(|password| !password.is_empty());
+    let query = wifi::payload(&wifi.ssid, wifi.security, password, wifi.hidden)
+        .and_then(|payload| with_payload(req.query_string(), payload));
+    match query {
+        Ok(query) => respond(&Encoding::QRCode, &query, upload, req),
+        Err(error) => error_response(error),
+    }
+}
 
-    let code = process_result.unwrap();
-    let response_format = get_response_format(req);
-    match code.output() {
-        Ok(data) => get_successful_output(data, response_format),
-        Err(error) => HttpResponse::build(StatusCode::BAD_REQUEST)
-            .content_type("text/html; charset=utf-8")
-            .body(format!("<p>Error!</p><p>{}<p/>", error)),
+fn error_response(error: String) -> HttpResponse {
+    HttpResponse::build(StatusCode::BAD_REQUEST)
+        .content_type("text/html; charset=utf-8")
+        .body(format!("<p>Error!</p><p>{}<p/>", error))
+}
+
+fn respond(
+    encoding: &Encoding,
+    query: &Params,
+    upload: Option<&[u8]>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let logo = match get_logo(query, upload) {
+        Ok(Some(_)) if !matches!(encoding, Encoding::QRCode) => {
+            return error_response(format!("Encoding {:?} does not support a logo", encoding))
+        }
+        Ok(logo) => logo,
+        Err(error) => return error_response(error),
+    };
+    let has_logo = logo.is_some();
+    let response_format = match &query.format {
+        Some(name) => get_format_from_name(name),
+        None => Ok(get_response_format(req)),
+    };
+    let encoder = process_request(encoding, query, logo);
+    let output = response_format.and_then(|response_format| {
+        let code = Encoded::new(encoder.as_ref(), get_render_options(query)?)?;
+        let png = if query.monochrome {
+            RasterFormat::MonochromePng
+        } else {
+            RasterFormat::Png
+        };
+        let data = match response_format {
+            ResponseFormat::Svg
+            | ResponseFormat::Pdf
+            | ResponseFormat::Eps
+            | ResponseFormat::Text
+                if has_logo =>
+            {
+                Err("Logos are only drawn in raster images".to_owned())
+            }
+            ResponseFormat::Svg => svg::output(&code),
+            ResponseFormat::Pdf => pdf::output(&code, &get_print_size(query)),
+            ResponseFormat::Eps => eps::output(&code, &get_print_size(query)),
+            ResponseFormat::Text => Ok(terminal::output(&code, query.terminal)),
+            ResponseFormat::Zpl => zpl::output(&code),
+            ResponseFormat::EscPos => {
+                Paper::from_mm(query.paper).and_then(|paper| escpos::output(&code, paper))
+            }
+            ResponseFormat::Image(RasterFormat::Png) => raster::output(&code, png),
+            ResponseFormat::Image(_) if query.monochrome => {
+                Err("monochrome is only available for PNG images".to_owned())
+            }
+            ResponseFormat::Image(format) => raster::output(&code, format),
+            // Both embed a PNG.
+            ResponseFormat::HTML | ResponseFormat::JSON => raster::output(&code, png),
+        };
+        data.map(|data| (data, response_format))
+    });
+    match output {
+        Ok((data, response_format)) => get_successful_output(data, response_format),
+        Err(error) => error_response(error),
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use actix_web::body::Body;
     use actix_web::test;
 
     use super::*;
@@ -127,19 +652,27 @@ mod tests {
     //     // assert_eq!(resp.unwrap().status(), http::StatusCode::OK);
     // }
 
-    async fn test_from_encoding(
+    async fn test_from_query(
         encoding: Encoding,
-        payload: &str,
+        query_string: &str,
         req: HttpRequest,
     ) -> HttpResponse {
         let info = Info { encoding };
         let path: web::Path<Info> = web::Path::from(info);
 
-        let query_string = format!("payload={:?}", payload);
-        let query: web::Query<Params> = web::Query::from_query(&query_string).unwrap();
+        let query: web::Query<Params> = web::Query::from_query(query_string).unwrap();
         get_code(path, query, req).await
     }
 
+    async fn test_from_encoding(
+        encoding: Encoding,
+        payload: &str,
+        req: HttpRequest,
+    ) -> HttpResponse {
+        let query_string = format!("payload={:?}", payload);
+        test_from_query(encoding, &query_string, req).await
+    }
+
     #[actix_rt::test]
     async fn barcode_ok() {
         let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
@@ -162,16 +695,679 @@ mod tests {
     }
 
     #[actix_rt::test]
-    async fn pdf417_not_supported() {
+    async fn pdf417_ok() {
         let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
         let response = test_from_encoding(Encoding::PDF417, "aaa", req).await;
-        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert_eq!(StatusCode::OK, response.status());
     }
 
     #[actix_rt::test]
-    async fn aztec_not_supported() {
+    async fn pdf417_params() {
+        for (query, status) in [
+            ("payload=hello&security_level=8&columns=10", StatusCode::OK),
+            ("payload=hello&rows=30", StatusCode::OK),
+            ("payload=hello&security_level=9", StatusCode::BAD_REQUEST),
+            ("payload=hello&columns=31", StatusCode::BAD_REQUEST),
+            ("payload=hello&columns=1&rows=3", StatusCode::BAD_REQUEST),
+        ] {
+            let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+            let response = test_from_query(Encoding::PDF417, query, req).await;
+            assert_eq!(status, response.status(), "{}", query);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn datamatrix_ok() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_encoding(Encoding::DataMatrix, "aaa", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn aztec_ok() {
         let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
         let response = test_from_encoding(Encoding::Aztec, "aaa", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn ean13_ok() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response =
+            test_from_query(Encoding::EAN13, "payload=400638133393&add_on=52495", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn ean13_wrong_check_digit() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(Encoding::EAN13, "payload=4006381333932", req).await;
         assert_eq!(StatusCode::BAD_REQUEST, response.status());
     }
+
+    #[actix_rt::test]
+    async fn upce_ok() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(Encoding::UPCE, "payload=04252614", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn code39_full_ascii_ok() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(
+            Encoding::Code39,
+            "payload=hello&full_ascii=true&checksum=true",
+            req,
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn codabar_ok() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(Encoding::Codabar, "payload=123&start=B&stop=D", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn itf_checksum() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(Encoding::ITF, "payload=123&checksum=true", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(Encoding::ITF, "payload=123", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert!(body(&response).contains("add a leading zero"));
+    }
+
+    #[actix_rt::test]
+    async fn itf14_ok() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(Encoding::ITF14, "payload=0001234567890", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn gs1_128_ok() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(
+            Encoding::GS1128,
+            "payload=(01)09501101530003(17)251231(10)ABC",
+            req,
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn gs1_128_bad() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(Encoding::GS1128, "payload=(01)09501101530004", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn qrcode_params_ok() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(
+            Encoding::QRCode,
+            "payload=hello&ecc_level=H&min_version=2&mask=3",
+            req,
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn qrcode_version_too_small() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let query = format!("payload={}&version=1", "a".repeat(100));
+        let response = test_from_query(Encoding::QRCode, &query, req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn micro_qr_ok() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(Encoding::MicroQR, "payload=12345&ecc_level=L", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn micro_qr_too_long() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(Encoding::MicroQR, "payload=hello&version=2", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn rmqr_ok() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(Encoding::RMQR, "payload=hello&ecc_level=H", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn rmqr_too_long() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response =
+            test_from_query(Encoding::RMQR, "payload=hello%20world&version=1", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn svg_ok() {
+        let req = test::TestRequest::with_header("accept", "image/svg+xml").to_http_request();
+        let response = test_from_encoding(Encoding::QRCode, "hello", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            "image/svg+xml",
+            response.headers().get("content-type").unwrap()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn svg_barcode_ok() {
+        let req = test::TestRequest::with_header("accept", "image/svg+xml").to_http_request();
+        let response = test_from_query(Encoding::EAN13, "payload=400638133393", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn pdf_ok() {
+        let req = test::TestRequest::with_header("accept", "application/pdf").to_http_request();
+        let response = test_from_query(
+            Encoding::QRCode,
+            "payload=hello&height_mm=20&quiet_zone_mm=2",
+            req,
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            "application/pdf",
+            response.headers().get("content-type").unwrap()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn pdf_format_param() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response =
+            test_from_query(Encoding::ITF14, "payload=0001234567890&format=pdf", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            "application/pdf",
+            response.headers().get("content-type").unwrap()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn unknown_format() {
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response = test_from_query(Encoding::QRCode, "payload=hello&format=avif", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn pdf_negative_height() {
+        let req = test::TestRequest::with_header("accept", "application/pdf").to_http_request();
+        let response = test_from_query(Encoding::QRCode, "payload=hello&height_mm=-5", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn eps_ok() {
+        let req =
+            test::TestRequest::with_header("accept", "application/postscript").to_http_request();
+        let response =
+            test_from_query(Encoding::EAN13, "payload=400638133393&height_mm=20", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            "application/postscript",
+            response.headers().get("content-type").unwrap()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn text_options_ok() {
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response =
+            test_from_query(Encoding::EAN13, "payload=400638133393&font_size=20", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(Encoding::BarCode, "payload=hello&text=false", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn quiet_zone_ok() {
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response =
+            test_from_query(Encoding::BarCode, "payload=hello&quiet_zone=12,4", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(Encoding::QRCode, "payload=hello&quiet_zone=0", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn quiet_zone_bad() {
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(Encoding::BarCode, "payload=hello&quiet_zone=a", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn colors_ok() {
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(
+            Encoding::QRCode,
+            "payload=hello&fg=1a237e&bg=ffffff00&check_contrast=true",
+            req,
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn colors_low_contrast() {
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(
+            Encoding::BarCode,
+            "payload=hello&fg=f00&check_contrast=true",
+            req,
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(Encoding::BarCode, "payload=hello&fg=red", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn rotate_ok() {
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(
+            Encoding::EAN13,
+            "payload=400638133393&rotate=90&mirror=true",
+            req,
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let req = test::TestRequest::with_header("accept", "image/svg+xml").to_http_request();
+        let response = test_from_query(Encoding::QRCode, "payload=hello&rotate=270", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn rotate_bad() {
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(Encoding::QRCode, "payload=hello&rotate=45", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn sizing_ok() {
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(
+            Encoding::EAN13,
+            "payload=400638133393&dpi=300&module_mm=0.33",
+            req,
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(Encoding::QRCode, "payload=hello&width=500", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn sizing_bad() {
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(Encoding::QRCode, "payload=hello&module_mm=0.5", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(Encoding::QRCode, "payload=hello&width=10", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        for query in &[
+            "payload=hello&module_px=4294967295",
+            "payload=hello&width=4294967295",
+            "payload=hello&height=4294967295",
+            "payload=hello&module_px=750&format=svg",
+        ] {
+            let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+            let response = test_from_query(Encoding::QRCode, query, req).await;
+            assert_eq!(StatusCode::BAD_REQUEST, response.status(), "{}", query);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn logo_registered() {
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(
+            Encoding::QRCode,
+            "payload=https://example.com&logo=example&logo_size=25",
+            req,
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn logo_uploaded() {
+        let logo = std::fs::read("static/logos/example.png").unwrap();
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let path = web::Path::from(Info {
+            encoding: Encoding::QRCode,
+        });
+        let query = web::Query::from_query("payload=https://example.com").unwrap();
+        let response = post_code(path, query, web::Bytes::from(logo), req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn logo_bad() {
+        for query in &[
+            "payload=hello&logo=missing",
+            "payload=hello&logo=example&logo_size=60",
+            "payload=hello&logo=example&format=svg",
+        ] {
+            let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+            let response = test_from_query(Encoding::QRCode, query, req).await;
+            assert_eq!(StatusCode::BAD_REQUEST, response.status(), "{}", query);
+        }
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(Encoding::Aztec, "payload=hello&logo=example", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn qr_style_ok() {
+        for accept in &[
+            "image/png",
+            "image/svg+xml",
+            "application/pdf",
+            "application/postscript",
+        ] {
+            let req = test::TestRequest::with_header("accept", *accept).to_http_request();
+            let response = test_from_query(
+                Encoding::QRCode,
+                "payload=hello&module_style=dots&eye_style=circle&rotate=90",
+                req,
+            )
+            .await;
+            assert_eq!(StatusCode::OK, response.status(), "{}", accept);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn raster_formats_ok() {
+        for (accept, content_type) in &[
+            ("image/jpeg", "image/jpeg"),
+            ("image/gif", "image/gif"),
+            ("image/bmp", "image/bmp"),
+            ("image/tiff", "image/tiff"),
+            ("image/webp", "image/webp"),
+            ("image/avif", "image/png"),
+        ] {
+            let req = test::TestRequest::with_header("accept", *accept).to_http_request();
+            let response = test_from_query(Encoding::QRCode, "payload=hello", req).await;
+            assert_eq!(StatusCode::OK, response.status(), "{}", accept);
+            assert_eq!(
+                *content_type,
+                response.headers().get("content-type").unwrap(),
+                "{}",
+                accept
+            );
+        }
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response =
+            test_from_query(Encoding::EAN13, "payload=400638133393&format=jpg", req).await;
+        assert_eq!(
+            "image/jpeg",
+            response.headers().get("content-type").unwrap()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn monochrome_ok() {
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(
+            Encoding::QRCode,
+            "payload=hello&monochrome=true&fg=1a237e&dpi=300",
+            req,
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("image/png", response.headers().get("content-type").unwrap());
+        let req = test::TestRequest::with_header("accept", "image/gif").to_http_request();
+        let response =
+            test_from_query(Encoding::QRCode, "payload=hello&monochrome=true", req).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn text_ok() {
+        for query in &[
+            "payload=hello",
+            "payload=hello&terminal=ascii&rotate=90",
+            "payload=hello&terminal=ansi&fg=1a237e",
+        ] {
+            let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+            let response = test_from_query(Encoding::QRCode, query, req).await;
+            assert_eq!(StatusCode::OK, response.status(), "{}", query);
+            assert_eq!(
+                "text/plain; charset=utf-8",
+                response.headers().get("content-type").unwrap()
+            );
+        }
+        let req = test::TestRequest::with_header("accept", "text/plain").to_http_request();
+        let response =
+            test_from_query(Encoding::EAN13, "payload=400638133393&format=text", req).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    fn body_bytes(response: &HttpResponse) -> Vec<u8> {
+        match response.body().as_ref() {
+            Some(Body::Bytes(bytes)) => bytes.to_vec(),
+            _ => panic!("Response has no body"),
+        }
+    }
+
+    fn body(response: &HttpResponse) -> String {
+        String::from_utf8(body_bytes(response)).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn zpl_ok() {
+        for (encoding, query, command) in [
+            (
+                Encoding::QRCode,
+                "payload=hello&module_px=4",
+                "^BQN,2,4,M^FH\\^FDMA,hello^FS",
+            ),
+            (Encoding::QRCode, "payload=hello&rotate=90", "^GFA,"),
+            (
+                Encoding::QRCode,
+                "payload=https://example.com&logo=example",
+                "^GFA,",
+            ),
+            (
+                Encoding::GS1128,
+                "payload=(01)09501101530003",
+                "^BCN,300,Y,N,N,D",
+            ),
+            (Encoding::BarCode, "payload=hello&mirror=true", "^GFA,"),
+            (Encoding::DataMatrix, "payload=hello&rotate=180", "^BXI,"),
+            (Encoding::Aztec, "payload=hello", "^GFA,"),
+            (Encoding::PDF417, "payload=hello", "^BY8^B7N,24,2,1,,N"),
+        ] {
+            let req = test::TestRequest::with_header("accept", "application/zpl").to_http_request();
+            let response = test_from_query(encoding, query, req).await;
+            assert_eq!(StatusCode::OK, response.status(), "{}", query);
+            let label = body(&response);
+            assert!(label.starts_with("^XA"), "{}", query);
+            assert!(label.contains(command), "{}: {}", query, label);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn escpos_ok() {
+        for (encoding, query, command) in [
+            (
+                Encoding::QRCode,
+                "payload=hello&module_px=4",
+                &b"\x1d(k\x03\x001C\x04\x1d(k\x03\x001E1"[..],
+            ),
+            (Encoding::QRCode, "payload=hello&mask=2", &b"\x1dv0"[..]),
+            (
+                Encoding::BarCode,
+                "payload=hello",
+                &b"\x1dw\x03\x1dh\xff\x1dH\x02\x1dkI\x07{Bhello"[..],
+            ),
+            (Encoding::BarCode, "payload=hello&rotate=90", &b"\x1dv0"[..]),
+            (
+                Encoding::EAN13,
+                "payload=400638133393&module_px=2",
+                &b"\x1dkC\x0d4006381333931"[..],
+            ),
+            (Encoding::UPCE, "payload=0123456", &b"\x1dv0"[..]),
+            (
+                Encoding::QRCode,
+                "payload=https://example.com&logo=example",
+                &b"\x1dv0"[..],
+            ),
+            (Encoding::Aztec, "payload=hello&paper=58", &b"\x1dv0"[..]),
+        ] {
+            let req = test::TestRequest::with_header("accept", "application/vnd.escpos")
+                .to_http_request();
+            let response = test_from_query(encoding, query, req).await;
+            assert_eq!(StatusCode::OK, response.status(), "{}", query);
+            assert_eq!(
+                "application/octet-stream",
+                response.headers().get("content-type").unwrap()
+            );
+            let job = body_bytes(&response);
+            assert!(job.starts_with(b"\x1b@\x1ba\x01"), "{}", query);
+            assert!(job.ends_with(b"\x1bd\x03"), "{}", query);
+            assert!(
+                job.windows(command.len()).any(|window| window == command),
+                "{}: {:?}",
+                query,
+                job
+            );
+        }
+    }
+
+    #[actix_rt::test]
+    async fn escpos_paper() {
+        // A width of 1000 pixels fits no receipt.
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(
+            Encoding::QRCode,
+            "payload=hello&format=escpos&width=1000",
+            req,
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let req = test::TestRequest::with_header("accept", "image/png").to_http_request();
+        let response = test_from_query(
+            Encoding::QRCode,
+            "payload=hello&format=escpos&paper=110",
+            req,
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    async fn test_wifi(query_string: &str, accept: &str) -> HttpResponse {
+        let req = test::TestRequest::with_uri(&format!("/encode/QRCode/wifi?{}", query_string))
+            .header("accept", accept)
+            .to_http_request();
+        let wifi: web::Query<WifiParams> = web::Query::from_query(query_string).unwrap();
+        get_wifi(wifi, req).await
+    }
+
+    #[actix_rt::test]
+    async fn wifi_ok() {
+        let response = test_wifi(
+            "ssid=Caf%C3%A9%3B1&password=p%3Aw%2Cd%5C1234&module_px=2",
+            "image/png",
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("image/png", response.headers().get("content-type").unwrap());
+
+        let response = test_wifi(
+            "ssid=Guests&security=nopass&password=&hidden=true",
+            "text/plain",
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn wifi_bad() {
+        for query in &[
+            "ssid=home&password=short",
+            "ssid=home&security=nopass&password=secret123",
+            "ssid=home&password=secret123&payload=hello",
+            "ssid=home&password=secret123&format=avif",
+        ] {
+            let response = test_wifi(query, "image/png").await;
+            assert_eq!(StatusCode::BAD_REQUEST, response.status(), "{}", query);
+        }
+    }
+
+    async fn test_contact(query_string: &str) -> HttpResponse {
+        let req = test::TestRequest::with_uri(&format!("/encode/QRCode/contact?{}", query_string))
+            .header("accept", "image/png")
+            .to_http_request();
+        let contact: web::Query<ContactParams> = web::Query::from_query(query_string).unwrap();
+        get_contact(contact, req).await
+    }
+
+    #[actix_rt::test]
+    async fn contact_ok() {
+        for card in &["vcard3", "vcard4", "mecard"] {
+            let query = format!(
+                "name=Jane%20Doe&org=Acme%2C%20Inc.&phones=%2B1%20555%200100,%2B1%20555%200199\
+                 &emails=jane@example.com&address=1%20Main%20St%0ASpringfield\
+                 &url=https://example.com&card={}",
+                card
+            );
+            let response = test_contact(&query).await;
+            assert_eq!(StatusCode::OK, response.status(), "{}", card);
+            assert_eq!("image/png", response.headers().get("content-type").unwrap());
+        }
+    }
+
+    #[actix_rt::test]
+    async fn contact_too_long() {
+        // The vCard does not fit version 4, its MeCard does.
+        let query = "name=Jane%20Doe&phones=5550100&emails=jane@example.com&version=4";
+        let response = test_contact(query).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert!(body(&response).contains("set card=mecard"));
+        let response = test_contact(&format!("{}&card=mecard", query)).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let response = test_contact("name=&card=mecard").await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[actix_rt::test]
+    async fn contact_injection() {
+        let response =
+            test_contact("name=Jane%20Doe&url=https://example.com%0AEMAIL:x@example.com").await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        // The MeCard escapes the URL instead.
+        let response = test_contact(
+            "name=Jane%20Doe&url=https://example.com%0AEMAIL:x@example.com&card=mecard",
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
 }
@@ -1,12 +1,88 @@
 use actix_web::HttpRequest;
 
+use crate::encoders::raster::RasterFormat;
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug)]
 pub enum ResponseFormat {
     HTML,
-    Image,
+    Image(RasterFormat),
     JSON,
+    Svg,
+    Pdf,
+    Eps,
+    Text,
+    Zpl,
+    EscPos,
+}
+
+/// Format named by the `format` query parameter, which takes precedence over the `Accept` header.
+pub fn get_format_from_name(name: &str) -> Result<ResponseFormat, String> {
+    if let Some(format) = RasterFormat::from_name(name) {
+        return Ok(ResponseFormat::Image(format));
+    }
+    match name {
+        "svg" => Ok(ResponseFormat::Svg),
+        "pdf" => Ok(ResponseFormat::Pdf),
+        "eps" => Ok(ResponseFormat::Eps),
+        "text" => Ok(ResponseFormat::Text),
+        "zpl" => Ok(ResponseFormat::Zpl),
+        "escpos" => Ok(ResponseFormat::EscPos),
+        _ => Err(format!(
+            "Unknown format {:?}, expected png, jpeg, gif, bmp, tiff, webp, svg, pdf, eps, text, zpl or escpos",
+            name
+        )),
+    }
+}
+
+fn get_format_from_media_type(media_type: &str) -> Option<ResponseFormat> {
+    if let Some(format) = RasterFormat::from_media_type(media_type) {
+        return Some(ResponseFormat::Image(format));
+    }
+    match media_type {
+        "image/svg+xml" => Some(ResponseFormat::Svg),
+        "image/x-eps" | "application/postscript" => Some(ResponseFormat::Eps),
+        "application/pdf" => Some(ResponseFormat::Pdf),
+        "application/json" => Some(ResponseFormat::JSON),
+        "text/html" | "application/xhtml+xml" => Some(ResponseFormat::HTML),
+        "text/plain" => Some(ResponseFormat::Text),
+        "application/zpl" | "application/x-zpl" => Some(ResponseFormat::Zpl),
+        "application/vnd.escpos" => Some(ResponseFormat::EscPos),
+        _ => None,
+    }
+}
+
+/// Media types of an `Accept` header, lower cased, from the highest quality value down.
+/// Types with `q=0` are not acceptable and left out, as are those with a malformed
+/// quality value or one outside 0 to 1.
+fn parse_accept(accept_header: &str) -> Vec<String> {
+    let mut media_types: Vec<(String, f32)> = accept_header
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let media_type = parts.next()?.to_ascii_lowercase();
+            let quality = parts
+                .filter_map(|param| param.strip_prefix("q="))
+                .map(|q| q.parse().unwrap_or(0.0))
+                .next()
+                .unwrap_or(1.0);
+            if media_type.is_empty() || !(quality > 0.0 && quality <= 1.0) {
+                None
+            } else {
+                Some((media_type, quality))
+            }
+        })
+        .collect();
+    // The sort is stable, so that types of equal quality keep the order of the header.
+    media_types.sort_by(|a, b| b.1.total_cmp(&a.1));
+    media_types
+        .into_iter()
+        .map(|(media_type, _)| media_type)
+        .collect()
 }
 
+/// The first supported type of the `Accept` header. Browsers ask for image types
+/// which cannot be encoded, such as AVIF, along with others, otherwise they get a PNG.
 pub fn get_response_format(req: HttpRequest) -> ResponseFormat {
     let accept_header = req.headers().get("accept");
     if accept_header.is_none() {
@@ -15,13 +91,16 @@ pub fn get_response_format(req: HttpRequest) -> ResponseFormat {
     let accept_header = accept_header.unwrap();
     match accept_header.to_str() {
         Ok(accept_header) => {
-            if accept_header.starts_with("image") {
-                ResponseFormat::Image
-            } else if accept_header.starts_with("application/json") {
-                ResponseFormat::JSON
-            } else {
-                ResponseFormat::HTML
-            }
+            let media_types = parse_accept(accept_header);
+            let wants_image = media_types.iter().any(|t| t.starts_with("image/"));
+            media_types
+                .iter()
+                .find_map(|media_type| get_format_from_media_type(media_type))
+                .unwrap_or(if wants_image {
+                    ResponseFormat::Image(RasterFormat::Png)
+                } else {
+                    ResponseFormat::HTML
+                })
         }
         Err(_e) => ResponseFormat::HTML,
     }
@@ -50,7 +129,137 @@ mod tests {
 
     #[test]
     fn test_image() {
-        assert!(matches!(test_header("image/png"), ResponseFormat::Image));
+        assert!(matches!(
+            test_header("image/png"),
+            ResponseFormat::Image(RasterFormat::Png)
+        ));
+        assert!(matches!(
+            test_header("image/tiff"),
+            ResponseFormat::Image(RasterFormat::Tiff)
+        ));
+    }
+
+    #[test]
+    fn test_quality_values() {
+        assert!(matches!(
+            test_header("image/png;q=0.5, image/jpeg"),
+            ResponseFormat::Image(RasterFormat::Jpeg)
+        ));
+        assert!(matches!(
+            test_header("image/avif, image/gif;q=0.9, */*;q=0.8"),
+            ResponseFormat::Image(RasterFormat::Gif)
+        ));
+        assert!(matches!(
+            test_header("image/bmp;q=0, application/pdf;q=0.1"),
+            ResponseFormat::Pdf
+        ));
+        // Firefox loading an <img>.
+        assert!(matches!(
+            test_header("image/avif,image/webp,*/*"),
+            ResponseFormat::Image(RasterFormat::Webp)
+        ));
+        assert!(matches!(
+            test_header("text/html,application/xhtml+xml,image/webp,*/*;q=0.8"),
+            ResponseFormat::HTML
+        ));
+    }
+
+    #[test]
+    fn test_invalid_quality_values() {
+        assert!(matches!(
+            test_header("image/png;q=NaN, text/html"),
+            ResponseFormat::HTML
+        ));
+        assert!(matches!(
+            test_header("image/gif;q=inf, image/png;q=2, image/jpeg;q=-1, application/pdf"),
+            ResponseFormat::Pdf
+        ));
+        assert_eq!(
+            vec!["image/gif"],
+            parse_accept("image/png;q=nan, image/gif;q=0.1")
+        );
+    }
+
+    #[test]
+    fn test_svg() {
+        assert!(matches!(test_header("image/svg+xml"), ResponseFormat::Svg));
+    }
+
+    #[test]
+    fn test_pdf() {
+        assert!(matches!(
+            test_header("application/pdf"),
+            ResponseFormat::Pdf
+        ));
+    }
+
+    #[test]
+    fn test_eps() {
+        assert!(matches!(
+            test_header("application/postscript"),
+            ResponseFormat::Eps
+        ));
+        assert!(matches!(test_header("image/x-eps"), ResponseFormat::Eps));
+    }
+
+    #[test]
+    fn test_format_name() {
+        assert!(matches!(
+            get_format_from_name("pdf"),
+            Ok(ResponseFormat::Pdf)
+        ));
+        assert!(matches!(
+            get_format_from_name("png"),
+            Ok(ResponseFormat::Image(RasterFormat::Png))
+        ));
+        assert!(matches!(
+            get_format_from_name("jpg"),
+            Ok(ResponseFormat::Image(RasterFormat::Jpeg))
+        ));
+        assert!(matches!(
+            get_format_from_name("eps"),
+            Ok(ResponseFormat::Eps)
+        ));
+        assert!(matches!(
+            get_format_from_name("webp"),
+            Ok(ResponseFormat::Image(RasterFormat::Webp))
+        ));
+        assert!(get_format_from_name("avif").is_err());
+    }
+
+    #[test]
+    fn test_text() {
+        assert!(matches!(test_header("text/plain"), ResponseFormat::Text));
+        assert!(matches!(
+            get_format_from_name("text"),
+            Ok(ResponseFormat::Text)
+        ));
+        // curl sends */* by default.
+        assert!(matches!(test_header("*/*"), ResponseFormat::HTML));
+    }
+
+    #[test]
+    fn test_zpl() {
+        assert!(matches!(
+            test_header("application/zpl"),
+            ResponseFormat::Zpl
+        ));
+        assert!(matches!(
+            get_format_from_name("zpl"),
+            Ok(ResponseFormat::Zpl)
+        ));
+    }
+
+    #[test]
+    fn test_escpos() {
+        assert!(matches!(
+            test_header("application/vnd.escpos"),
+            ResponseFormat::EscPos
+        ));
+        assert!(matches!(
+            get_format_from_name("escpos"),
+            Ok(ResponseFormat::EscPos)
+        ));
     }
 
     #[test]
@@ -64,6 +273,9 @@ mod tests {
     #[test]
     fn test_accept_title_case() {
         let req = test::TestRequest::with_header("Accept", "image/webp").to_http_request();
-        assert!(matches!(get_response_format(req), ResponseFormat::Image));
+        assert!(matches!(
+            get_response_format(req),
+            ResponseFormat::Image(RasterFormat::Webp)
+        ));
     }
 }